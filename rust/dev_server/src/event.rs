@@ -0,0 +1,231 @@
+//! Conversions between plain HTTP and Lambda function URL events.
+
+use aws_lambda_events::{
+    http::{header, HeaderMap, HeaderValue, Method},
+    lambda_function_urls::{
+        LambdaFunctionUrlRequest, LambdaFunctionUrlRequestContext, LambdaFunctionUrlRequestContextHttpDescription,
+        LambdaFunctionUrlResponse,
+    },
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use bytes::Bytes;
+use chrono::Utc;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use tracing::warn;
+
+/// Builds a Lambda function URL event from the parts of an HTTP request
+/// the same way AWS does it for a real function URL.
+pub(crate) fn to_lambda_request(
+    method: &Method,
+    path: &str,
+    query: Option<&str>,
+    headers: &HeaderMap,
+    body: Bytes,
+    remote_addr: SocketAddr,
+) -> LambdaFunctionUrlRequest {
+    // repeated query params are joined with a comma, same as AWS does it
+    let mut query_string_parameters: HashMap<String, String> = HashMap::new();
+    for (k, v) in form_urlencoded::parse(query.unwrap_or_default().as_bytes()) {
+        query_string_parameters
+            .entry(k.into_owned())
+            .and_modify(|existing| {
+                existing.push(',');
+                existing.push_str(&v);
+            })
+            .or_insert_with(|| v.into_owned());
+    }
+
+    // AWS moves cookies out of the headers into a separate list
+    let mut headers = headers.clone();
+    let cookies = match headers.remove(header::COOKIE) {
+        Some(v) => v.to_str().ok().map(|v| {
+            v.split(';')
+                .map(|c| c.trim().to_string())
+                .filter(|c| !c.is_empty())
+                .collect()
+        }),
+        None => None,
+    };
+
+    // the handlers only work with text, so binary bodies are passed as base64
+    let (body, is_base64_encoded) = if body.is_empty() {
+        (None, false)
+    } else {
+        match String::from_utf8(body.to_vec()) {
+            Ok(v) => (Some(v), false),
+            Err(_) => (Some(BASE64.encode(&body)), true),
+        }
+    };
+
+    let user_agent = headers
+        .get(header::USER_AGENT)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string());
+
+    let now = Utc::now();
+
+    LambdaFunctionUrlRequest {
+        version: Some("2.0".to_string()),
+        raw_path: Some(path.to_string()),
+        raw_query_string: Some(query.unwrap_or_default().to_string()),
+        cookies,
+        headers,
+        query_string_parameters,
+        request_context: LambdaFunctionUrlRequestContext {
+            account_id: None,
+            request_id: None,
+            authorizer: None,
+            apiid: None,
+            domain_name: None,
+            domain_prefix: None,
+            time: Some(now.to_rfc2822()),
+            time_epoch: now.timestamp_millis(),
+            http: LambdaFunctionUrlRequestContextHttpDescription {
+                method: Some(method.to_string()),
+                path: Some(path.to_string()),
+                protocol: Some("HTTP/1.1".to_string()),
+                source_ip: Some(remote_addr.ip().to_string()),
+                user_agent,
+            },
+        },
+        body,
+        is_base64_encoded,
+    }
+}
+
+/// Extracts the status, headers and the body from a Lambda function URL response.
+/// Base64 bodies are decoded and cookies are converted into `Set-Cookie` headers.
+pub(crate) fn from_lambda_response(response: LambdaFunctionUrlResponse) -> (u16, HeaderMap, Bytes) {
+    let status = match u16::try_from(response.status_code) {
+        Ok(v) if (100..1000).contains(&v) => v,
+        _ => {
+            warn!("Invalid status code from the handler: {}", response.status_code);
+            500
+        }
+    };
+
+    let mut headers = response.headers;
+    for cookie in response.cookies {
+        match HeaderValue::from_str(&cookie) {
+            Ok(v) => {
+                headers.append(header::SET_COOKIE, v);
+            }
+            Err(e) => warn!("Invalid cookie from the handler: {cookie} / {e}"),
+        }
+    }
+
+    let body = match response.body {
+        Some(v) if response.is_base64_encoded => match BASE64.decode(&v) {
+            Ok(v) => Bytes::from(v),
+            Err(e) => {
+                warn!("Invalid base64 body from the handler: {e}");
+                return (500, HeaderMap::new(), Bytes::new());
+            }
+        },
+        Some(v) => Bytes::from(v),
+        None => Bytes::new(),
+    };
+
+    (status, headers, body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn remote() -> SocketAddr {
+        "127.0.0.1:5173".parse().unwrap()
+    }
+
+    #[test]
+    fn test_to_lambda_request() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::COOKIE, HeaderValue::from_static("a=1; b=2"));
+        headers.insert("x-bitie-token", HeaderValue::from_static("abc"));
+
+        let request = to_lambda_request(
+            &Method::GET,
+            "/q",
+            Some("topic=aws&qid=a%20b&answers=1&answers=2"),
+            &headers,
+            Bytes::new(),
+            remote(),
+        );
+
+        assert_eq!(request.raw_path.as_deref(), Some("/q"));
+        assert_eq!(request.query_string_parameters.get("topic").unwrap(), "aws");
+        assert_eq!(request.query_string_parameters.get("qid").unwrap(), "a b");
+        assert_eq!(request.query_string_parameters.get("answers").unwrap(), "1,2");
+        assert_eq!(request.cookies, Some(vec!["a=1".to_string(), "b=2".to_string()]));
+        assert!(request.headers.get(header::COOKIE).is_none());
+        assert_eq!(request.headers.get("x-bitie-token").unwrap(), "abc");
+        assert_eq!(request.request_context.http.method.as_deref(), Some("GET"));
+        assert_eq!(request.request_context.http.source_ip.as_deref(), Some("127.0.0.1"));
+        assert!(request.body.is_none());
+        assert!(!request.is_base64_encoded);
+    }
+
+    #[test]
+    fn test_to_lambda_request_body() {
+        let request = to_lambda_request(
+            &Method::POST,
+            "/feedback",
+            None,
+            &HeaderMap::new(),
+            Bytes::from_static(b"some text"),
+            remote(),
+        );
+        assert_eq!(request.body.as_deref(), Some("some text"));
+        assert!(!request.is_base64_encoded);
+        assert!(request.query_string_parameters.is_empty());
+
+        let request = to_lambda_request(
+            &Method::POST,
+            "/feedback",
+            None,
+            &HeaderMap::new(),
+            Bytes::from_static(&[0xff, 0xfe]),
+            remote(),
+        );
+        assert_eq!(request.body.as_deref(), Some("//4="));
+        assert!(request.is_base64_encoded);
+    }
+
+    #[test]
+    fn test_from_lambda_response() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("text/plain"));
+
+        let (status, headers, body) = from_lambda_response(LambdaFunctionUrlResponse {
+            status_code: 404,
+            headers,
+            body: Some("No question found".to_string()),
+            is_base64_encoded: false,
+            cookies: vec!["a=1".to_string()],
+        });
+        assert_eq!(status, 404);
+        assert_eq!(headers.get(header::CONTENT_TYPE).unwrap(), "text/plain");
+        assert_eq!(headers.get(header::SET_COOKIE).unwrap(), "a=1");
+        assert_eq!(body, Bytes::from_static(b"No question found"));
+
+        let (status, _, body) = from_lambda_response(LambdaFunctionUrlResponse {
+            status_code: 200,
+            headers: HeaderMap::new(),
+            body: Some("//4=".to_string()),
+            is_base64_encoded: true,
+            cookies: Vec::new(),
+        });
+        assert_eq!(status, 200);
+        assert_eq!(body, Bytes::from_static(&[0xff, 0xfe]));
+
+        let (status, _, _) = from_lambda_response(LambdaFunctionUrlResponse {
+            status_code: 0,
+            headers: HeaderMap::new(),
+            body: None,
+            is_base64_encoded: false,
+            cookies: Vec::new(),
+        });
+        assert_eq!(status, 500);
+    }
+}
@@ -0,0 +1,226 @@
+//! A local HTTP server that hosts all lambdas for front-end development.
+//!
+//! Plain HTTP requests are converted into `LambdaFunctionUrlRequest` events and passed
+//! to the `my_handler` function of the lambda mounted on the request path.
+//!
+//! The AWS SDK is pointed at a local endpoint, e.g. DynamoDB Local (`docker run -p 8000:8000 amazon/dynamodb-local`),
+//! and emails are written to the log instead of being sent via SES.
+//! Missing DDB tables and indexes are created on startup, see `tables.rs`.
+//! Any of the env vars set by `set_env_defaults` can be overridden from the command line.
+
+use aws_lambda_events::{
+    http::{Request, Response},
+    lambda_function_urls::{LambdaFunctionUrlRequest, LambdaFunctionUrlResponse},
+};
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper::{body::Incoming, server::conn::http1, service::service_fn};
+use hyper_util::rt::TokioIo;
+use lambda_runtime::{Context, Error, LambdaEvent};
+use lambda_utils::{
+    email::{EMAIL_SINK_CONSOLE, EMAIL_SINK_ENV_VAR},
+    response::CORS_ORIGINS_ENV_VAR,
+};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use tokio::net::TcpListener;
+use tracing::{error, info, warn};
+use tracing_subscriber::filter::LevelFilter;
+
+mod event;
+mod tables;
+
+/// The address the server listens on, e.g. `127.0.0.1:3000`.
+const ADDR_ENV_VAR: &str = "BITIE_DEV_ADDR";
+const DEFAULT_ADDR: &str = "127.0.0.1:3000";
+
+/// The default endpoint for all AWS services, e.g. DynamoDB Local.
+/// Use `AWS_ENDPOINT_URL_DYNAMODB` and `AWS_ENDPOINT_URL_S3` for per-service endpoints.
+const DEFAULT_AWS_ENDPOINT: &str = "http://localhost:8000";
+
+/// The lambdas hosted by the server.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Lambda {
+    Question,
+    QuestionList,
+    QuestionStage,
+    User,
+    Feedback,
+    Feed,
+    Embed,
+    Payments,
+}
+
+impl Lambda {
+    /// Returns the lambda mounted on the path.
+    /// The short paths used by CloudFront in prod are accepted as aliases.
+    fn from_path(path: &str) -> Option<Self> {
+        match path.trim_end_matches('/') {
+            "/q" => Some(Self::Question),
+            "/ql" => Some(Self::QuestionList),
+            "/stage" | "/qs" => Some(Self::QuestionStage),
+            "/u" => Some(Self::User),
+            "/feedback" | "/qf" => Some(Self::Feedback),
+            "/sitemap.xml" | "/rss.xml" | "/atom.xml" => Some(Self::Feed),
+            "/embed" | "/oembed" => Some(Self::Embed),
+            "/pay" | "/checkout" => Some(Self::Payments),
+            _ => None,
+        }
+    }
+
+    /// Passes the event to the handler of the lambda.
+    async fn invoke(self, request: LambdaFunctionUrlRequest) -> Result<LambdaFunctionUrlResponse, Error> {
+        let event = LambdaEvent::new(request, Context::default());
+        match self {
+            Self::Question => question_handler::my_handler(event).await,
+            Self::QuestionList => question_list_handler::my_handler(event).await,
+            Self::QuestionStage => question_stage_handler::my_handler(event).await,
+            Self::User => user_handler::my_handler(event).await,
+            Self::Feedback => feedback_handler::my_handler(event).await,
+            Self::Feed => feed_handler::my_handler(event).await,
+            Self::Embed => embed_handler::my_handler(event).await,
+            Self::Payments => payments_handler::my_handler(event).await,
+        }
+    }
+}
+
+fn main() -> Result<(), Error> {
+    tracing_subscriber::fmt().with_max_level(LevelFilter::INFO).init();
+
+    // env vars have to be set before any threads are started
+    set_env_defaults();
+
+    let addr = std::env::var(ADDR_ENV_VAR).unwrap_or_else(|_| DEFAULT_ADDR.to_string());
+
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?
+        .block_on(serve(addr))
+}
+
+/// Sets the env vars for local AWS stand-ins, the console email sink and CORS
+/// unless they are already set.
+fn set_env_defaults() {
+    for (name, value) in [
+        ("AWS_ENDPOINT_URL", DEFAULT_AWS_ENDPOINT),
+        ("AWS_REGION", "us-east-1"),
+        ("AWS_ACCESS_KEY_ID", "local"),
+        ("AWS_SECRET_ACCESS_KEY", "local"),
+        (EMAIL_SINK_ENV_VAR, EMAIL_SINK_CONSOLE),
+        // the default port of the Vite dev server
+        (CORS_ORIGINS_ENV_VAR, "http://localhost:5173"),
+    ] {
+        match std::env::var(name) {
+            Ok(v) => info!("{name}={v}"),
+            Err(_) => {
+                info!("{name}={value} (default)");
+                std::env::set_var(name, value);
+            }
+        }
+    }
+}
+
+/// Creates missing DDB tables, then accepts connections until the process is stopped.
+async fn serve(addr: String) -> Result<(), Error> {
+    let client = aws_sdk_dynamodb::Client::new(&aws_config::load_from_env().await);
+    tables::create_missing(&client).await;
+
+    let listener = TcpListener::bind(&addr).await?;
+    info!("Listening on http://{addr}");
+
+    loop {
+        let (stream, remote_addr) = match listener.accept().await {
+            Ok(v) => v,
+            Err(e) => {
+                warn!("Failed to accept a connection: {e}");
+                continue;
+            }
+        };
+
+        tokio::spawn(async move {
+            let service = service_fn(move |request| handle(request, remote_addr));
+            if let Err(e) = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                warn!("Connection error: {e}");
+            }
+        });
+    }
+}
+
+/// Routes the request to the matching lambda and converts the result back into plain HTTP.
+/// All errors are logged and returned to the client as text.
+async fn handle(request: Request<Incoming>, remote_addr: SocketAddr) -> Result<Response<Full<Bytes>>, Infallible> {
+    let (parts, body) = request.into_parts();
+    info!("{} {}", parts.method, parts.uri);
+
+    let lambda = match Lambda::from_path(parts.uri.path()) {
+        Some(v) => v,
+        None => return Ok(text(404, "No lambda is mounted on this path")),
+    };
+
+    let body = match body.collect().await {
+        Ok(v) => v.to_bytes(),
+        Err(e) => {
+            warn!("Failed to read the request body: {e}");
+            return Ok(text(400, "Failed to read the request body"));
+        }
+    };
+
+    let lambda_request = event::to_lambda_request(
+        &parts.method,
+        parts.uri.path(),
+        parts.uri.query(),
+        &parts.headers,
+        body,
+        remote_addr,
+    );
+
+    let lambda_response = match lambda.invoke(lambda_request).await {
+        Ok(v) => v,
+        Err(e) => {
+            error!("{lambda:?} handler failed: {e}");
+            return Ok(text(500, &e.to_string()));
+        }
+    };
+
+    let (status, headers, body) = event::from_lambda_response(lambda_response);
+    info!("{status} {} bytes", body.len());
+
+    let mut response = Response::new(Full::new(body));
+    *response.status_mut() = status.try_into().unwrap_or_default();
+    *response.headers_mut() = headers;
+    Ok(response)
+}
+
+/// A shortcut for errors raised by the server itself rather than by a lambda.
+fn text(status: u16, body: &str) -> Response<Full<Bytes>> {
+    let mut response = Response::new(Full::new(Bytes::from(body.to_string())));
+    *response.status_mut() = status.try_into().unwrap_or_default();
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lambda_from_path() {
+        assert_eq!(Lambda::from_path("/q"), Some(Lambda::Question));
+        assert_eq!(Lambda::from_path("/q/"), Some(Lambda::Question));
+        assert_eq!(Lambda::from_path("/ql"), Some(Lambda::QuestionList));
+        assert_eq!(Lambda::from_path("/stage"), Some(Lambda::QuestionStage));
+        assert_eq!(Lambda::from_path("/qs"), Some(Lambda::QuestionStage));
+        assert_eq!(Lambda::from_path("/u"), Some(Lambda::User));
+        assert_eq!(Lambda::from_path("/feedback"), Some(Lambda::Feedback));
+        assert_eq!(Lambda::from_path("/qf"), Some(Lambda::Feedback));
+        assert_eq!(Lambda::from_path("/sitemap.xml"), Some(Lambda::Feed));
+        assert_eq!(Lambda::from_path("/rss.xml"), Some(Lambda::Feed));
+        assert_eq!(Lambda::from_path("/oembed"), Some(Lambda::Embed));
+        assert_eq!(Lambda::from_path("/pay"), Some(Lambda::Payments));
+        assert_eq!(Lambda::from_path("/checkout"), Some(Lambda::Payments));
+        assert_eq!(Lambda::from_path("/"), None);
+        assert_eq!(Lambda::from_path("/question"), None);
+    }
+}
@@ -0,0 +1,183 @@
+//! Creates the DDB tables and indexes used by `DdbRepository` and `DdbRateLimiter`
+//! in a local stand-in, e.g. DynamoDB Local, so the lambdas work on a fresh checkout.
+
+use anyhow::Result;
+use aws_sdk_dynamodb::{
+    types::{
+        AttributeDefinition, BillingMode, GlobalSecondaryIndex, KeySchemaElement, KeyType, Projection, ProjectionType,
+        ScalarAttributeType,
+    },
+    Client,
+};
+use bitie_types::ddb::{fields, tables};
+use tracing::{error, info};
+
+/// A table or an index with its partition and optional sort key.
+/// All keys are strings.
+struct Keys {
+    name: &'static str,
+    pk: &'static str,
+    sk: Option<&'static str>,
+}
+
+/// A table with its global secondary indexes.
+struct Table {
+    keys: Keys,
+    indexes: &'static [Keys],
+}
+
+/// The same names and keys as in the queries of `DdbRepository` and `DdbRateLimiter`.
+/// The indexes project all attributes, which is a superset of what the deployed ones have.
+const TABLES: &[Table] = &[
+    Table {
+        keys: Keys {
+            name: tables::QUESTIONS,
+            pk: fields::TOPIC,
+            sk: Some(fields::QID),
+        },
+        indexes: &[
+            Keys {
+                name: tables::QUESTIONS_IDX_TOPIC,
+                pk: fields::TOPIC,
+                sk: Some(fields::STAGE),
+            },
+            Keys {
+                name: tables::QUESTIONS_IDX_AUTHOR,
+                pk: fields::AUTHOR,
+                sk: None,
+            },
+        ],
+    },
+    Table {
+        keys: Keys {
+            name: tables::USERS,
+            pk: fields::EMAIL,
+            sk: Some(fields::SORT_KEY),
+        },
+        indexes: &[Keys {
+            name: tables::USERS_IDX_EMAIL_HASH,
+            pk: fields::EMAIL_HASH,
+            sk: Some(fields::EMAIL),
+        }],
+    },
+    Table {
+        keys: Keys {
+            name: tables::USED_TOKENS,
+            pk: fields::TOKEN_ID,
+            sk: None,
+        },
+        indexes: &[],
+    },
+    Table {
+        keys: Keys {
+            name: tables::RATE_LIMITS,
+            pk: fields::RATE_LIMIT_KEY,
+            sk: None,
+        },
+        indexes: &[],
+    },
+    Table {
+        keys: Keys {
+            name: tables::FEEDBACK,
+            pk: fields::QID,
+            sk: Some(fields::FID),
+        },
+        indexes: &[Keys {
+            name: tables::FEEDBACK_IDX_STATUS,
+            pk: fields::STATUS,
+            sk: Some(fields::CREATED),
+        }],
+    },
+    Table {
+        keys: Keys {
+            name: tables::EMBEDS,
+            pk: fields::QID,
+            sk: Some(fields::HOST),
+        },
+        indexes: &[],
+    },
+    Table {
+        keys: Keys {
+            name: tables::LINKS,
+            pk: fields::URL,
+            sk: None,
+        },
+        indexes: &[],
+    },
+];
+
+/// Creates the tables that do not exist yet. Existing tables are left as they are.
+/// All errors are logged inside the function, so the server can still start.
+pub(crate) async fn create_missing(client: &Client) {
+    let existing = match client.list_tables().send().await {
+        Ok(v) => v.table_names.unwrap_or_default(),
+        Err(e) => {
+            error!("Failed to list DDB tables. Is DynamoDB Local running? {:?}", e);
+            return;
+        }
+    };
+
+    for table in TABLES
+        .iter()
+        .filter(|v| !existing.iter().any(|name| name == v.keys.name))
+    {
+        match create_table(client, table).await {
+            Ok(()) => info!("Created DDB table {}", table.keys.name),
+            Err(e) => error!("Failed to create DDB table {}: {:?}", table.keys.name, e),
+        }
+    }
+}
+
+async fn create_table(client: &Client, table: &Table) -> Result<()> {
+    // every key attribute has to be defined once
+    let mut attributes = Vec::new();
+    for keys in std::iter::once(&table.keys).chain(table.indexes) {
+        for name in std::iter::once(keys.pk).chain(keys.sk) {
+            if !attributes.contains(&name) {
+                attributes.push(name);
+            }
+        }
+    }
+
+    let mut request = client
+        .create_table()
+        .table_name(table.keys.name)
+        .billing_mode(BillingMode::PayPerRequest)
+        .set_key_schema(Some(key_schema(&table.keys)?));
+    for name in attributes {
+        request = request.attribute_definitions(
+            AttributeDefinition::builder()
+                .attribute_name(name)
+                .attribute_type(ScalarAttributeType::S)
+                .build()?,
+        );
+    }
+    for index in table.indexes {
+        request = request.global_secondary_indexes(
+            GlobalSecondaryIndex::builder()
+                .index_name(index.name)
+                .set_key_schema(Some(key_schema(index)?))
+                .projection(Projection::builder().projection_type(ProjectionType::All).build())
+                .build()?,
+        );
+    }
+
+    request.send().await?;
+    Ok(())
+}
+
+fn key_schema(keys: &Keys) -> Result<Vec<KeySchemaElement>> {
+    let mut schema = vec![KeySchemaElement::builder()
+        .attribute_name(keys.pk)
+        .key_type(KeyType::Hash)
+        .build()?];
+    if let Some(sk) = keys.sk {
+        schema.push(
+            KeySchemaElement::builder()
+                .attribute_name(sk)
+                .key_type(KeyType::Range)
+                .build()?,
+        );
+    }
+    Ok(schema)
+}
@@ -7,12 +7,37 @@ use tracing::{error, info};
 pub const CHARSET: &str = "UTF-8";
 /// All emails are sent from this address.
 pub const FROM: &str = "Bite-sized learning <max@bitesized.info>";
+/// Set this env var to `console` to log emails instead of sending them via SES.
+/// Used by the local dev server.
+pub const EMAIL_SINK_ENV_VAR: &str = "BITIE_EMAIL_SINK";
+/// The value of `EMAIL_SINK_ENV_VAR` that redirects emails to the log.
+pub const EMAIL_SINK_CONSOLE: &str = "console";
+
+/// The env var with the moderators' address for new feedback tickets and questions.
+pub const MODS_EMAIL_ENV_VAR: &str = "BITIE_MODS_EMAIL";
+/// The moderators' address used if `MODS_EMAIL_ENV_VAR` is not set.
+const DEFAULT_MODS_EMAIL: &str = "max@onebro.me";
+
+/// Returns the moderators' address from `MODS_EMAIL_ENV_VAR`, if it is set and not empty,
+/// or the default one.
+pub fn mods_email() -> String {
+    std::env::var(MODS_EMAIL_ENV_VAR)
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| DEFAULT_MODS_EMAIL.to_string())
+}
 
 /// Sends a plain text email using SES.
 /// All errors are logged inside the function.
 pub async fn send_text_email(to: &str, subject: &str, body: &str) {
     info!("Sending {subject} email to: {to}, body: {body}");
 
+    if std::env::var(EMAIL_SINK_ENV_VAR).is_ok_and(|v| v == EMAIL_SINK_CONSOLE) {
+        info!("Email not sent: {EMAIL_SINK_ENV_VAR}={EMAIL_SINK_CONSOLE}");
+        return;
+    }
+
     let config = aws_config::load_from_env().await;
     let client = aws_sdk_sesv2::Client::new(&config);
 
@@ -0,0 +1,203 @@
+//! A common error model for all API responses.
+//!
+//! Every error is returned as JSON, e.g. `{"code":"not_found","message":"No question found"}`.
+//! The `code` values are stable and can be used by the front-end to tell errors apart.
+
+use crate::repository::RepositoryError;
+use aws_lambda_events::{
+    http::{HeaderMap, HeaderValue},
+    lambda_function_urls::LambdaFunctionUrlResponse,
+};
+use lambda_runtime::Error;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use tracing::{error, info};
+
+/// An error that is returned to the client with the matching HTTP status code.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApiError {
+    /// 400: the request is malformed or has invalid params
+    Validation(String),
+    /// 401: missing or invalid token
+    Unauthorized,
+    /// 403: the user is known, but is not allowed to perform the action
+    Forbidden(String),
+    /// 404: the requested record or path does not exist
+    NotFound(String),
+    /// 405: the path exists, but does not support the method
+    MethodNotAllowed,
+    /// 409: the request conflicts with the current state of the record
+    Conflict(String),
+    /// 429: the client is rate limited and can retry after this many seconds
+    TooManyRequests { retry_after: u32 },
+    /// 502: a dependency such as DDB, SES or Stripe failed
+    Upstream(String),
+    /// 500: a failure inside the lambda, most likely a bug
+    Internal(String),
+}
+
+/// The JSON body of all error responses.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ApiErrorBody {
+    /// A machine-readable error code, e.g. `not_found`
+    pub code: String,
+    /// A user-friendly message
+    pub message: String,
+}
+
+impl ApiError {
+    /// HTTP status code for the response.
+    pub fn status(&self) -> i64 {
+        match self {
+            Self::Validation(_) => 400,
+            Self::Unauthorized => 401,
+            Self::Forbidden(_) => 403,
+            Self::NotFound(_) => 404,
+            Self::MethodNotAllowed => 405,
+            Self::Conflict(_) => 409,
+            Self::TooManyRequests { .. } => 429,
+            Self::Upstream(_) => 502,
+            Self::Internal(_) => 500,
+        }
+    }
+
+    /// A stable machine-readable code for the response body.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Validation(_) => "validation",
+            Self::Unauthorized => "unauthorized",
+            Self::Forbidden(_) => "forbidden",
+            Self::NotFound(_) => "not_found",
+            Self::MethodNotAllowed => "method_not_allowed",
+            Self::Conflict(_) => "conflict",
+            Self::TooManyRequests { .. } => "rate_limited",
+            Self::Upstream(_) => "upstream_failure",
+            Self::Internal(_) => "internal",
+        }
+    }
+
+    /// A user-friendly message for the response body.
+    pub fn message(&self) -> &str {
+        match self {
+            Self::Unauthorized => "Unauthorized",
+            Self::MethodNotAllowed => "Unsupported HTTP method",
+            Self::TooManyRequests { .. } => "Too many requests, try again later",
+            Self::Validation(v)
+            | Self::Forbidden(v)
+            | Self::NotFound(v)
+            | Self::Conflict(v)
+            | Self::Upstream(v)
+            | Self::Internal(v) => v,
+        }
+    }
+
+    /// Logs the error and converts it into a JSON response for the lambda runtime.
+    pub fn into_response(self) -> Result<LambdaFunctionUrlResponse, Error> {
+        let status = self.status();
+        if status >= 500 {
+            error!("Responding with {status}: {self}");
+        } else {
+            info!("Responding with {status}: {self}");
+        }
+
+        let body = ApiErrorBody {
+            code: self.code().to_string(),
+            message: self.message().to_string(),
+        };
+
+        let mut headers = HeaderMap::new();
+        headers.append(
+            "Content-Type",
+            HeaderValue::from_static("application/json; charset=utf-8"),
+        );
+        headers.append("Cache-Control", HeaderValue::from_static("no-store"));
+        if let Self::TooManyRequests { retry_after } = &self {
+            headers.append("Retry-After", HeaderValue::from(*retry_after));
+        }
+
+        Ok(LambdaFunctionUrlResponse {
+            status_code: status,
+            headers,
+            cookies: Default::default(),
+            // serializing two strings cannot fail
+            body: serde_json::to_string(&body).ok(),
+            is_base64_encoded: false,
+        })
+    }
+}
+
+impl Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.code(), self.message())
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+/// Storage errors are mapped to client errors where possible, otherwise they are upstream failures.
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        match e.downcast_ref::<RepositoryError>() {
+            Some(RepositoryError::QuestionNotFound) => Self::NotFound("No question found".to_string()),
+            Some(RepositoryError::FeedbackNotFound) => Self::NotFound("No feedback found".to_string()),
+            Some(RepositoryError::AuthorMismatch) => {
+                Self::Forbidden("The question was created by another user".to_string())
+            }
+            None => Self::Upstream(e.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_into_response() {
+        let response = ApiError::NotFound("No question found".to_string())
+            .into_response()
+            .unwrap();
+        assert_eq!(response.status_code, 404);
+        assert_eq!(
+            response.headers.get("Content-Type").unwrap(),
+            "application/json; charset=utf-8"
+        );
+        assert_eq!(
+            serde_json::from_str::<ApiErrorBody>(&response.body.unwrap()).unwrap(),
+            ApiErrorBody {
+                code: "not_found".to_string(),
+                message: "No question found".to_string()
+            }
+        );
+
+        let response = ApiError::Unauthorized.into_response().unwrap();
+        assert_eq!(response.status_code, 401);
+        assert_eq!(
+            response.body.unwrap(),
+            r#"{"code":"unauthorized","message":"Unauthorized"}"#
+        );
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let response = ApiError::TooManyRequests { retry_after: 30 }.into_response().unwrap();
+        assert_eq!(response.status_code, 429);
+        assert_eq!(response.headers.get("Retry-After").unwrap(), "30");
+    }
+
+    #[test]
+    fn test_from_anyhow() {
+        assert_eq!(
+            ApiError::from(anyhow::Error::new(RepositoryError::QuestionNotFound)).status(),
+            404
+        );
+        assert_eq!(
+            ApiError::from(anyhow::Error::new(RepositoryError::AuthorMismatch)).status(),
+            403
+        );
+        assert_eq!(
+            ApiError::from(anyhow::Error::msg("DDB error")),
+            ApiError::Upstream("DDB error".to_string())
+        );
+    }
+}
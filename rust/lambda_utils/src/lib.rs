@@ -8,10 +8,41 @@ use serde::Serialize;
 use tracing::info;
 
 pub mod email;
+pub mod error;
+pub mod rate_limit;
+pub mod repository;
+pub mod request;
+pub mod response;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
 
 /// An HTTP header for the JWT token.
 pub const X_BITIE_TOKEN_HEADER: &str = "x-bitie-token";
 
+/// The env var with the secret for signing and verifying answer tokens, see `bitie_types::answer_token`.
+/// Answer tokens are ignored if it is not set.
+pub const ANSWER_TOKEN_SECRET_ENV_VAR: &str = "ANSWER_TOKEN_SECRET";
+
+/// Returns the secret for answer tokens from `ANSWER_TOKEN_SECRET_ENV_VAR`, if it is set and not empty.
+pub fn answer_token_secret() -> Option<Vec<u8>> {
+    std::env::var(ANSWER_TOKEN_SECRET_ENV_VAR)
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .map(|v| v.trim().as_bytes().to_vec())
+}
+
+/// The env var with the Ed25519 private key in PEM format for signing offline bundles, see `bitie_types::offline`.
+/// Offline bundles are not available if it is not set.
+pub const OFFLINE_BUNDLE_KEY_ENV_VAR: &str = "OFFLINE_BUNDLE_KEY";
+
+/// Returns the key for signing offline bundles from `OFFLINE_BUNDLE_KEY_ENV_VAR`, if it is set and not empty.
+pub fn offline_bundle_key() -> Option<Vec<u8>> {
+    std::env::var(OFFLINE_BUNDLE_KEY_ENV_VAR)
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .map(|v| v.trim().as_bytes().to_vec())
+}
+
 // /// The header name for the question format.
 // /// The value should be one of the `QuestionFormat` enum values.
 // /// CloudFront has to be configured to vary the cache depending on the header contents.
@@ -36,14 +67,15 @@ pub fn text_response(body: Option<String>, status: i64) -> Result<LambdaFunction
 
 /// A shortcut for returning the lambda response in the required format.
 /// May return an error if the serialization of the body object fails.
-/// Adds `Content-Type=application/json` header.
+/// Adds `Content-Type=application/json` and `Cache-Control=no-store` headers.
+/// Use `response::ResponseBuilder` for cacheable responses.
 pub fn json_response<T: Serialize>(body: Option<&T>, status: i64) -> Result<LambdaFunctionUrlResponse, Error> {
     let body = match body {
         Some(v) => match serde_json::to_string(v) {
             Ok(v) => Some(v),
             Err(e) => {
                 info!("Failed to serialize the response: {:?}", e);
-                return text_response(Some(e.to_string()), 400);
+                return error::ApiError::Internal("Failed to serialize the response".to_string()).into_response();
             }
         },
         None => None,
@@ -55,6 +87,7 @@ pub fn json_response<T: Serialize>(body: Option<&T>, status: i64) -> Result<Lamb
         "Content-Type",
         HeaderValue::from_static("application/json; charset=utf-8"),
     );
+    headers.append("Cache-Control", HeaderValue::from_static("no-store"));
 
     Ok(LambdaFunctionUrlResponse {
         status_code: status,
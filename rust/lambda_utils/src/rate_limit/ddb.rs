@@ -0,0 +1,139 @@
+//! DynamoDB implementation of the rate limiter.
+//!
+//! Each bucket is an item in `tables::RATE_LIMITS` with the number of tokens, the last update time
+//! and a TTL for when the bucket would be full again, so DDB can delete idle buckets.
+//! Concurrent updates are detected with a condition on the last update time and retried.
+
+use super::{take_token, Bucket, RateDecision, RateLimit, RateLimiter};
+use anyhow::{Error, Result};
+use async_trait::async_trait;
+use aws_sdk_dynamodb::{types::AttributeValue, Client};
+use bitie_types::ddb::{fields, item::Item, tables};
+use chrono::Utc;
+use tracing::{error, info, warn};
+
+/// How many times to retry the update if another request updated the same bucket.
+const MAX_ATTEMPTS: usize = 3;
+
+/// Stores token buckets in DynamoDB.
+#[derive(Clone, Debug)]
+pub struct DdbRateLimiter {
+    client: Client,
+}
+
+impl DdbRateLimiter {
+    /// Wraps an existing DDB client.
+    pub fn new(client: Client) -> Self {
+        Self { client }
+    }
+
+    /// Creates a new DDB client from the environment.
+    pub async fn from_env() -> Self {
+        Self::new(Client::new(&aws_config::load_from_env().await))
+    }
+
+    /// Reads the bucket with a consistent read. Returns None if there is no bucket or it is invalid.
+    async fn get_bucket(&self, key: &str) -> Result<Option<Bucket>> {
+        let item = match self
+            .client
+            .get_item()
+            .table_name(tables::RATE_LIMITS)
+            .key(fields::RATE_LIMIT_KEY, AttributeValue::S(key.to_string()))
+            .consistent_read(true)
+            .send()
+            .await
+        {
+            Ok(v) => v.item,
+            Err(e) => {
+                error!("Failed to get rate limit bucket {key}: {:?}", e);
+                return Err(Error::msg("DDB error".to_string()));
+            }
+        };
+
+        Ok(item.and_then(|item| item_to_bucket(&item)))
+    }
+}
+
+#[async_trait]
+impl RateLimiter for DdbRateLimiter {
+    async fn check(&self, key: &str, limit: &RateLimit) -> Result<RateDecision> {
+        for _ in 0..MAX_ATTEMPTS {
+            let existing = self.get_bucket(key).await?;
+            let now = Utc::now().timestamp_millis();
+            let (bucket, decision) = take_token(existing, limit, now);
+
+            // nothing to update if the bucket is empty
+            if decision != RateDecision::Allowed {
+                return Ok(decision);
+            }
+
+            let ttl = now / 1000 + limit.full_refill_secs();
+
+            let request = self
+                .client
+                .put_item()
+                .table_name(tables::RATE_LIMITS)
+                .item(fields::RATE_LIMIT_KEY, AttributeValue::S(key.to_string()))
+                .item(fields::TOKENS, AttributeValue::N(bucket.tokens.to_string()))
+                .item(fields::UPDATED, AttributeValue::N(bucket.updated.to_string()))
+                .item(fields::TTL, AttributeValue::N(ttl.to_string()))
+                .expression_attribute_names("#key", fields::RATE_LIMIT_KEY)
+                .expression_attribute_names("#updated", fields::UPDATED);
+
+            // fail the write if another request updated the bucket after it was read
+            let request = match existing {
+                Some(v) => request
+                    .condition_expression("#updated = :updated OR attribute_not_exists(#key)")
+                    .expression_attribute_values(":updated", AttributeValue::N(v.updated.to_string())),
+                None => request.condition_expression("attribute_not_exists(#key) OR attribute_not_exists(#updated)"),
+            };
+
+            match request.send().await {
+                Ok(_) => return Ok(decision),
+                Err(e)
+                    if e.as_service_error()
+                        .is_some_and(|e| e.is_conditional_check_failed_exception()) =>
+                {
+                    info!("Concurrent update of rate limit bucket {key}, retrying");
+                }
+                Err(e) => {
+                    error!("Failed to update rate limit bucket {key}: {:?}", e);
+                    return Err(Error::msg("DDB error".to_string()));
+                }
+            }
+        }
+
+        warn!("Gave up updating rate limit bucket {key} after {MAX_ATTEMPTS} attempts");
+        Err(Error::msg("Too many concurrent updates".to_string()))
+    }
+}
+
+/// Converts a DDB item into a bucket. Returns None if any of the fields is missing or invalid.
+fn item_to_bucket(item: &Item) -> Option<Bucket> {
+    let tokens = item.get(fields::TOKENS)?.as_n().ok()?.parse::<f64>().ok()?;
+    let updated = item.get(fields::UPDATED)?.as_n().ok()?.parse::<i64>().ok()?;
+    Some(Bucket { tokens, updated })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_item_to_bucket() {
+        let item = Item::from([
+            (fields::TOKENS.to_string(), AttributeValue::N("1.5".to_string())),
+            (fields::UPDATED.to_string(), AttributeValue::N("1000".to_string())),
+        ]);
+        assert_eq!(
+            item_to_bucket(&item),
+            Some(Bucket {
+                tokens: 1.5,
+                updated: 1000
+            })
+        );
+
+        let item = Item::from([(fields::TOKENS.to_string(), AttributeValue::S("1".to_string()))]);
+        assert_eq!(item_to_bucket(&item), None);
+    }
+}
@@ -0,0 +1,57 @@
+//! In-memory implementation of the rate limiter for tests and local development.
+
+use super::{take_token, Bucket, RateDecision, RateLimit, RateLimiter};
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::Utc;
+use std::{collections::HashMap, sync::Mutex};
+
+/// Keeps the buckets in a hashmap behind a mutex. Expired buckets are never removed.
+#[derive(Default, Debug)]
+pub struct MemoryRateLimiter {
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl MemoryRateLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl RateLimiter for MemoryRateLimiter {
+    async fn check(&self, key: &str, limit: &RateLimit) -> Result<RateDecision> {
+        let mut buckets = self.buckets.lock().expect("Poisoned mutex. It's a bug.");
+        let (bucket, decision) = take_token(buckets.get(key).copied(), limit, Utc::now().timestamp_millis());
+        buckets.insert(key.to_string(), bucket);
+        Ok(decision)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error::ApiError, rate_limit::enforce};
+
+    #[tokio::test]
+    async fn test_enforce() {
+        let limiter = MemoryRateLimiter::new();
+        let limit = RateLimit {
+            capacity: 2,
+            refill_secs: 3600,
+        };
+        let keys = ["test/ip/1.2.3.4".to_string()];
+
+        assert!(enforce(&limiter, &keys, &limit).await.is_ok());
+        assert!(enforce(&limiter, &keys, &limit).await.is_ok());
+        match enforce(&limiter, &keys, &limit).await {
+            Err(ApiError::TooManyRequests { retry_after }) => assert!(retry_after > 3500 && retry_after <= 3600),
+            v => panic!("Expected TooManyRequests, got {:?}", v),
+        }
+
+        // other keys have their own buckets
+        assert!(enforce(&limiter, &["test/ip/5.6.7.8".to_string()], &limit)
+            .await
+            .is_ok());
+    }
+}
@@ -0,0 +1,170 @@
+//! Token bucket rate limiting for endpoints that can be abused, e.g. feedback and question submission.
+//!
+//! Buckets are keyed by a free-form string, e.g. `feedback/ip/1.2.3.4` or `save/user/<email hash>`.
+//! `DdbRateLimiter` stores the buckets in DynamoDB with a TTL, `MemoryRateLimiter` keeps them in memory for tests.
+
+use crate::error::ApiError;
+use anyhow::Result;
+use async_trait::async_trait;
+use tracing::{error, info};
+
+pub use ddb::DdbRateLimiter;
+pub use memory::MemoryRateLimiter;
+
+mod ddb;
+mod memory;
+
+/// Token bucket settings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    /// The max number of requests in a burst.
+    pub capacity: u32,
+    /// One token is added to the bucket every `refill_secs` seconds.
+    pub refill_secs: u32,
+}
+
+impl RateLimit {
+    /// Anonymous feedback: 5 messages in a burst, then one every 12 minutes.
+    pub const FEEDBACK: Self = Self {
+        capacity: 5,
+        refill_secs: 720,
+    };
+
+    /// Saving questions by an author: 20 saves in a burst, then one every 3 minutes.
+    pub const QUESTION_SAVE: Self = Self {
+        capacity: 20,
+        refill_secs: 180,
+    };
+
+    /// Offline bundle downloads by a learner: 5 bundles in a burst, then one an hour.
+    pub const OFFLINE_BUNDLE: Self = Self {
+        capacity: 5,
+        refill_secs: 3600,
+    };
+
+    /// Syncing answers given offline by a learner: 10 batches in a burst, then one every 6 minutes.
+    pub const OFFLINE_SYNC: Self = Self {
+        capacity: 10,
+        refill_secs: 360,
+    };
+
+    /// Embed stats from a single IP: 30 views or answers in a burst, then one every 10 seconds.
+    pub const EMBED_STATS: Self = Self {
+        capacity: 30,
+        refill_secs: 10,
+    };
+
+    /// The number of seconds it takes to refill an empty bucket.
+    /// A bucket that was not touched for this long is the same as a new one and can be deleted.
+    pub fn full_refill_secs(&self) -> i64 {
+        self.capacity as i64 * self.refill_secs as i64
+    }
+}
+
+/// The state of a bucket as stored by the backends.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bucket {
+    /// The number of remaining tokens. It is fractional because tokens are refilled continuously.
+    pub tokens: f64,
+    /// When the bucket was last updated, in milliseconds since the epoch.
+    pub updated: i64,
+}
+
+/// The outcome of taking a token from a bucket.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RateDecision {
+    Allowed,
+    /// The bucket is empty. The request can be retried in this many seconds.
+    Limited {
+        retry_after: u32,
+    },
+}
+
+/// A store for token buckets.
+#[async_trait]
+pub trait RateLimiter: Send + Sync {
+    /// Takes one token from the bucket for the key, creating a full bucket if there is none.
+    async fn check(&self, key: &str, limit: &RateLimit) -> Result<RateDecision>;
+}
+
+/// Refills the bucket for the time elapsed since the last update and takes one token from it.
+/// Returns the new state of the bucket and the decision. The state is unchanged if the request is limited.
+pub fn take_token(bucket: Option<Bucket>, limit: &RateLimit, now: i64) -> (Bucket, RateDecision) {
+    let capacity = limit.capacity as f64;
+    let refill_ms = limit.refill_secs.max(1) as f64 * 1000.0;
+
+    let tokens = match bucket {
+        Some(v) => (v.tokens + (now - v.updated).max(0) as f64 / refill_ms).min(capacity),
+        None => capacity,
+    };
+
+    if tokens >= 1.0 {
+        (
+            Bucket {
+                tokens: tokens - 1.0,
+                updated: now,
+            },
+            RateDecision::Allowed,
+        )
+    } else {
+        let retry_after = ((1.0 - tokens) * refill_ms / 1000.0).ceil() as u32;
+        (
+            bucket.unwrap_or(Bucket { tokens, updated: now }),
+            RateDecision::Limited {
+                retry_after: retry_after.max(1),
+            },
+        )
+    }
+}
+
+/// Takes a token from every bucket and returns `ApiError::TooManyRequests` if any of them is empty.
+/// Storage errors are logged and the request is let through to avoid blocking legit users.
+pub async fn enforce<L: RateLimiter>(limiter: &L, keys: &[String], limit: &RateLimit) -> Result<(), ApiError> {
+    for key in keys {
+        match limiter.check(key, limit).await {
+            Ok(RateDecision::Allowed) => {}
+            Ok(RateDecision::Limited { retry_after }) => {
+                info!("Rate limited: {key}, retry after {retry_after}s");
+                return Err(ApiError::TooManyRequests { retry_after });
+            }
+            Err(e) => error!("Rate limiter failed for {key}: {:?}", e),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMIT: RateLimit = RateLimit {
+        capacity: 2,
+        refill_secs: 10,
+    };
+
+    #[test]
+    fn test_take_token() {
+        // a new bucket starts full
+        let (bucket, decision) = take_token(None, &LIMIT, 0);
+        assert_eq!(decision, RateDecision::Allowed);
+        assert_eq!(bucket.tokens, 1.0);
+
+        let (bucket, decision) = take_token(Some(bucket), &LIMIT, 0);
+        assert_eq!(decision, RateDecision::Allowed);
+        assert_eq!(bucket.tokens, 0.0);
+
+        // empty, 4s into the 10s refill
+        let (bucket, decision) = take_token(Some(bucket), &LIMIT, 4_000);
+        assert_eq!(decision, RateDecision::Limited { retry_after: 6 });
+        assert_eq!(bucket.updated, 0, "the state is not changed when limited");
+
+        // one token refilled
+        let (bucket, decision) = take_token(Some(bucket), &LIMIT, 10_000);
+        assert_eq!(decision, RateDecision::Allowed);
+        assert_eq!(bucket.tokens, 0.0);
+
+        // never refilled above the capacity
+        let (bucket, _) = take_token(Some(bucket), &LIMIT, 1_000_000);
+        assert_eq!(bucket.tokens, 1.0);
+    }
+}
@@ -0,0 +1,1026 @@
+//! DynamoDB implementation of the repository traits.
+
+use super::{
+    new_unsubscribe_token, FeedbackRepository, LinkRepository, QuestionRepository, RepositoryError, UserRepository,
+};
+use anyhow::{Error, Result};
+use async_trait::async_trait;
+use aws_sdk_dynamodb::{
+    operation::{transact_write_items::TransactWriteItemsError, update_item::builders::UpdateItemFluentBuilder},
+    types::{AttributeValue, KeysAndAttributes, ReturnValue, TransactWriteItem, Update},
+    Client,
+};
+use bitie_types::{
+    ddb::{
+        fields,
+        item::{timestamp_to_attr, Item},
+        tables, DEFAULT_USER_TABLE_SK_VALUE, EMBED_HOSTS_COUNTER_SK_VALUE,
+    },
+    embed::MAX_EMBED_HOSTS,
+    feedback::{Feedback, FeedbackStatus},
+    links::LinkStatus,
+    question::{PublishStage, Question},
+    user::{AnswerStatus, AskedQuestion, User},
+};
+use chrono::{DateTime, Utc};
+use std::cmp::Reverse;
+use tracing::{error, info, warn};
+
+/// The max number of keys in a single BatchGetItem request.
+const MAX_BATCH_GET_KEYS: usize = 100;
+
+/// Stores questions, users, feedback and link checks in DynamoDB tables listed in `bitie_types::ddb::tables`.
+#[derive(Clone, Debug)]
+pub struct DdbRepository {
+    client: Client,
+}
+
+impl DdbRepository {
+    /// Wraps an existing DDB client.
+    pub fn new(client: Client) -> Self {
+        Self { client }
+    }
+
+    /// Creates a new DDB client from the environment.
+    pub async fn from_env() -> Self {
+        Self::new(Client::new(&aws_config::load_from_env().await))
+    }
+
+    /// Fetches a single question item by its keys.
+    async fn get_question_item(&self, topic: &str, qid: &str) -> Result<Option<Item>> {
+        info!("Query for {topic} / {qid}");
+
+        match self
+            .client
+            .query()
+            .table_name(tables::QUESTIONS)
+            .key_condition_expression("#topic = :topic AND #qid = :qid")
+            .expression_attribute_names("#topic", fields::TOPIC)
+            .expression_attribute_values(":topic", AttributeValue::S(topic.to_owned()))
+            .expression_attribute_names("#qid", fields::QID)
+            .expression_attribute_values(":qid", AttributeValue::S(qid.to_owned()))
+            .send()
+            .await
+        {
+            Ok(v) => match v.items.and_then(|items| items.into_iter().next()) {
+                Some(item) => Ok(Some(item)),
+                None => {
+                    warn!("No items in query response for {topic} / {qid}");
+                    Ok(None)
+                }
+            },
+            Err(e) => {
+                info!("Query for {topic} / {qid} failed: {:?}", e);
+                Err(Error::msg("DDB error".to_string()))
+            }
+        }
+    }
+
+    /// Runs a query against one of the question indexes and converts the items into list display questions.
+    /// The topic index is limited to published questions.
+    /// Invalid items are logged and skipped.
+    async fn query_question_index(&self, index: &str, key_field: &str, key_value: &str) -> Result<Vec<Question>> {
+        let query = self
+            .client
+            .query()
+            .table_name(tables::QUESTIONS)
+            .index_name(index)
+            .expression_attribute_names("#key", key_field)
+            .expression_attribute_values(":key", AttributeValue::S(key_value.to_owned()));
+
+        let query = if index == tables::QUESTIONS_IDX_TOPIC {
+            query
+                .key_condition_expression("#key = :key AND #stage = :stage")
+                .expression_attribute_names("#stage", fields::STAGE)
+                .expression_attribute_values(":stage", AttributeValue::S(PublishStage::Published.to_string()))
+        } else {
+            query.key_condition_expression("#key = :key")
+        };
+
+        let items = match query.send().await {
+            // TODO: add pagination when there are enough questions to fill multiple pages
+            Ok(v) => v.items.unwrap_or_default(),
+            Err(e) => {
+                error!("Query for {key_value} failed: {:?}", e);
+                return Err(Error::msg("DDB error".to_string()));
+            }
+        };
+
+        // invalid items are logged inside try_from
+        let mut questions = items
+            .into_iter()
+            .filter_map(|v| Question::try_from(v).ok())
+            .collect::<Vec<Question>>();
+        info!("Fetched questions: {}", questions.len());
+
+        // sort the questions by updated date
+        questions.sort_by_key(|v| Reverse(v.updated));
+
+        Ok(questions)
+    }
+
+    /// Returns an update of the embed stats that increments `counter` for the host, without a condition.
+    fn embed_stats_update(&self, topic: &str, qid: &str, host: &str, counter: &str) -> UpdateItemFluentBuilder {
+        self.client
+            .update_item()
+            .table_name(tables::EMBEDS)
+            .update_expression(embed_stats_expression(counter))
+            .key(fields::QID, AttributeValue::S(qid.to_string()))
+            .key(fields::HOST, AttributeValue::S(host.to_string()))
+            .expression_attribute_names("#topic", fields::TOPIC)
+            .expression_attribute_names("#updated", fields::UPDATED)
+            .expression_attribute_values(":v", AttributeValue::N("1".to_string()))
+            .expression_attribute_values(":topic", AttributeValue::S(topic.to_string()))
+            .expression_attribute_values(":updated", timestamp_to_attr(&Utc::now()))
+    }
+
+    /// Adds a new host to the embed stats and increments the per-question host counter in a single transaction.
+    /// Nothing is written if the question already has `MAX_EMBED_HOSTS` hosts or the host was added in the meantime.
+    async fn add_embed_host(&self, topic: &str, qid: &str, host: &str, counter: &str) -> Result<AddedHost> {
+        let hosts_counter = Update::builder()
+            .table_name(tables::EMBEDS)
+            .key(fields::QID, AttributeValue::S(qid.to_string()))
+            .key(
+                fields::HOST,
+                AttributeValue::S(EMBED_HOSTS_COUNTER_SK_VALUE.to_string()),
+            )
+            .update_expression("ADD #hosts :v")
+            .condition_expression("attribute_not_exists(#hosts) OR #hosts < :max")
+            .expression_attribute_names("#hosts", fields::EMBED_HOSTS)
+            .expression_attribute_values(":v", AttributeValue::N("1".to_string()))
+            .expression_attribute_values(":max", AttributeValue::N(MAX_EMBED_HOSTS.to_string()))
+            .build()?;
+
+        let stats = Update::builder()
+            .table_name(tables::EMBEDS)
+            .key(fields::QID, AttributeValue::S(qid.to_string()))
+            .key(fields::HOST, AttributeValue::S(host.to_string()))
+            .update_expression(embed_stats_expression(counter))
+            .condition_expression("attribute_not_exists(#host)")
+            .expression_attribute_names("#host", fields::HOST)
+            .expression_attribute_names("#topic", fields::TOPIC)
+            .expression_attribute_names("#updated", fields::UPDATED)
+            .expression_attribute_values(":v", AttributeValue::N("1".to_string()))
+            .expression_attribute_values(":topic", AttributeValue::S(topic.to_string()))
+            .expression_attribute_values(":updated", timestamp_to_attr(&Utc::now()))
+            .build()?;
+
+        match self
+            .client
+            .transact_write_items()
+            .transact_items(TransactWriteItem::builder().update(hosts_counter).build())
+            .transact_items(TransactWriteItem::builder().update(stats).build())
+            .send()
+            .await
+        {
+            Ok(_) => Ok(AddedHost::Added),
+            Err(e) => {
+                // the reasons are listed in the same order as the items in the transaction
+                let failed = e
+                    .as_service_error()
+                    .and_then(|e| match e {
+                        TransactWriteItemsError::TransactionCanceledException(e) => Some(e),
+                        _ => None,
+                    })
+                    .map(|e| {
+                        e.cancellation_reasons()
+                            .iter()
+                            .map(|v| v.code() == Some("ConditionalCheckFailed"))
+                            .collect::<Vec<bool>>()
+                    })
+                    .unwrap_or_default();
+                match failed.as_slice() {
+                    [true, ..] => Ok(AddedHost::TooMany),
+                    [_, true] => Ok(AddedHost::Exists),
+                    _ => {
+                        error!("Failed to add embed host {host}: {:?}", e);
+                        Err(Error::msg("Failed to update embed stats".to_string()))
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The outcome of `DdbRepository::add_embed_host`.
+enum AddedHost {
+    Added,
+    /// The host was added by a concurrent request.
+    Exists,
+    /// The question already has `MAX_EMBED_HOSTS` hosts.
+    TooMany,
+}
+
+/// Increments the counter and refreshes the topic and the timestamp of an embed stats item.
+fn embed_stats_expression(counter: &str) -> String {
+    ["ADD ", counter, " :v SET #topic = :topic, #updated = :updated"].concat()
+}
+
+#[async_trait]
+impl QuestionRepository for DdbRepository {
+    async fn get_question(&self, topic: &str, qid: &str) -> Result<Option<Question>> {
+        match self.get_question_item(topic, qid).await? {
+            Some(item) => {
+                let question = Question::try_from(item)?;
+                info!("Returning {topic} / {qid}");
+                Ok(Some(question))
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn save_question(&self, question: &Question) -> Result<()> {
+        info!("Saving question {}/{}", question.topic, question.qid);
+        info!("{:?}", question);
+
+        // these fields are optional, but must be present for the question to be saved
+        if question.author.is_none() || question.updated.is_none() {
+            error!("Missing author or updated field. It's a bug.");
+            return Err(Error::msg("Failed to save question".to_string()));
+        }
+
+        // the keys cannot be updated, so only the other attributes are included in the expression
+        let mut item = Item::from(question);
+        item.remove(fields::TOPIC);
+        item.remove(fields::QID);
+
+        // this has to be an update to prevent overwriting photo IDs and stats
+        // the author is only set once and never changed
+        let update_expression = [
+            "SET #author = if_not_exists(#author, :author), ".to_string(),
+            item.keys()
+                .filter(|v| v.as_str() != fields::AUTHOR)
+                .map(|v| format!("#{v} = :{v}"))
+                .collect::<Vec<String>>()
+                .join(", "),
+        ]
+        .concat();
+
+        match self
+            .client
+            .update_item()
+            .table_name(tables::QUESTIONS)
+            .update_expression(update_expression)
+            .key(fields::TOPIC, AttributeValue::S(question.topic.clone()))
+            .key(fields::QID, AttributeValue::S(question.qid.clone()))
+            .set_expression_attribute_names(Some(item.keys().map(|v| (["#", v].concat(), v.clone())).collect()))
+            .set_expression_attribute_values(Some(item.into_iter().map(|(k, v)| ([":", &k].concat(), v)).collect()))
+            .condition_expression("#author = :author OR attribute_not_exists(#author)") // makes the query fail with an error if the author is different
+            .send()
+            .await
+        {
+            Ok(_) => {
+                info!("Question saved in DDB");
+                Ok(())
+            }
+            Err(e)
+                if e.as_service_error()
+                    .is_some_and(|e| e.is_conditional_check_failed_exception()) =>
+            {
+                warn!(
+                    "Failed to save question {}/{}: author mismatch",
+                    question.topic, question.qid
+                );
+                Err(RepositoryError::AuthorMismatch.into())
+            }
+            Err(e) => {
+                error!("Failed to save question {}/{}: {:?}", question.topic, question.qid, e);
+                Err(Error::msg("Failed to save question".to_string()))
+            }
+        }
+    }
+
+    async fn change_publish_stage(&self, topic: &str, qid: &str, stage: PublishStage) -> Result<()> {
+        info!("Changing publish stage for {topic} / {qid} to {stage}");
+
+        let question = match self.get_question_item(topic, qid).await? {
+            Some(item) => Question::try_from(item)?.with_stage(stage),
+            None => return Err(RepositoryError::QuestionNotFound.into()),
+        };
+
+        info!("Saving question {}/{}", question.topic, question.qid);
+
+        // this has to be an update to prevent overwriting photo IDs
+        const UPDATE_EXPRESSION: &str = "SET #details = :details, #stage = :stage, #updated = :updated";
+
+        match self
+            .client
+            .update_item()
+            .table_name(tables::QUESTIONS)
+            .update_expression(UPDATE_EXPRESSION)
+            .key(fields::TOPIC, AttributeValue::S(question.topic.clone()))
+            .key(fields::QID, AttributeValue::S(question.qid.clone()))
+            .expression_attribute_names("#details", fields::DETAILS)
+            .expression_attribute_values(":details", AttributeValue::S(question.to_string()))
+            .expression_attribute_names("#stage", fields::STAGE)
+            .expression_attribute_values(":stage", AttributeValue::S(question.stage.to_string()))
+            .expression_attribute_names("#updated", fields::UPDATED)
+            .expression_attribute_values(":updated", timestamp_to_attr(&Utc::now()))
+            .send()
+            .await
+        {
+            Ok(_) => {
+                info!("Question updated in DDB");
+                Ok(())
+            }
+            Err(e) => {
+                error!("Failed to save question {}/{}: {:?}", question.topic, question.qid, e);
+                Err(Error::msg("Failed to save question".to_string()))
+            }
+        }
+    }
+
+    async fn increment_answer_stats(&self, topic: &str, qid: &str, status: &AnswerStatus) -> Result<()> {
+        let counter = match status {
+            AnswerStatus::Asked(_) => return Ok(()),
+            AnswerStatus::Correct(_) => fields::QUESTION_STATS_CORRECT,
+            AnswerStatus::Incorrect(_) => fields::QUESTION_STATS_INCORRECT,
+            AnswerStatus::Skipped(_) => fields::QUESTION_STATS_SKIPPED,
+        };
+
+        match self
+            .client
+            .update_item()
+            .table_name(tables::QUESTIONS)
+            .update_expression(["ADD ", counter, " :v"].concat())
+            .key(fields::TOPIC, AttributeValue::S(topic.to_string()))
+            .key(fields::QID, AttributeValue::S(qid.to_string()))
+            .expression_attribute_values(":v", AttributeValue::N("1".to_string()))
+            .send()
+            .await
+        {
+            Ok(_) => {
+                info!("Question stats updated");
+                Ok(())
+            }
+            Err(e) => {
+                error!("Failed to update question stats: {:?}", e);
+                Err(Error::msg("Failed to update question stats".to_string()))
+            }
+        }
+    }
+
+    async fn increment_embed_stats(&self, topic: &str, qid: &str, host: &str, status: &AnswerStatus) -> Result<()> {
+        let counter = match status {
+            AnswerStatus::Asked(_) => fields::QUESTION_STATS_VIEWS,
+            AnswerStatus::Correct(_) => fields::QUESTION_STATS_CORRECT,
+            AnswerStatus::Incorrect(_) => fields::QUESTION_STATS_INCORRECT,
+            AnswerStatus::Skipped(_) => fields::QUESTION_STATS_SKIPPED,
+        };
+
+        // known hosts are updated straight away, new ones are added in a transaction with the per-question host counter
+        match self
+            .embed_stats_update(topic, qid, host, counter)
+            .condition_expression("attribute_exists(#host)")
+            .expression_attribute_names("#host", fields::HOST)
+            .send()
+            .await
+        {
+            Ok(_) => {
+                info!("Embed stats updated for {host}");
+                return Ok(());
+            }
+            Err(e)
+                if e.as_service_error()
+                    .is_some_and(|e| e.is_conditional_check_failed_exception()) =>
+            {
+                info!("New embed host {host}");
+            }
+            Err(e) => {
+                error!("Failed to update embed stats for {host}: {:?}", e);
+                return Err(Error::msg("Failed to update embed stats".to_string()));
+            }
+        }
+
+        match self.add_embed_host(topic, qid, host, counter).await? {
+            AddedHost::Added => {
+                info!("Embed stats added for {host}");
+                return Ok(());
+            }
+            AddedHost::TooMany => {
+                warn!("Too many embed hosts for {qid}, {host} is not counted");
+                return Ok(());
+            }
+            AddedHost::Exists => info!("Embed host {host} was added by another request"),
+        }
+
+        match self.embed_stats_update(topic, qid, host, counter).send().await {
+            Ok(_) => {
+                info!("Embed stats updated for {host}");
+                Ok(())
+            }
+            Err(e) => {
+                error!("Failed to update embed stats for {host}: {:?}", e);
+                Err(Error::msg("Failed to update embed stats".to_string()))
+            }
+        }
+    }
+
+    async fn get_published_questions_by_topic(&self, topic: &str) -> Result<Vec<Question>> {
+        info!("Getting all questions for {topic}");
+        self.query_question_index(tables::QUESTIONS_IDX_TOPIC, fields::TOPIC, topic)
+            .await
+    }
+
+    async fn get_all_questions_by_author(&self, email_hash: &str) -> Result<Vec<Question>> {
+        info!("Getting author questions for {email_hash}");
+        self.query_question_index(tables::QUESTIONS_IDX_AUTHOR, fields::AUTHOR, email_hash)
+            .await
+    }
+}
+
+#[async_trait]
+impl UserRepository for DdbRepository {
+    async fn get_user(&self, email: &str) -> Result<Option<User>> {
+        info!("Getting user: {}", email);
+
+        match self
+            .client
+            .query()
+            .table_name(tables::USERS)
+            .key_condition_expression("#email = :email")
+            .expression_attribute_names("#email", fields::EMAIL)
+            .expression_attribute_values(":email", AttributeValue::S(email.to_string()))
+            .send()
+            .await
+        {
+            Ok(v) => match v.items {
+                // extract a single item from the response - there should be only one
+                Some(items) => {
+                    // check how many items there are
+                    if items.len() > 1 {
+                        // should not happen, but carry on anyway
+                        warn!("Found multiple records for {email}. Returning one only.");
+                    }
+                    item_to_user(items.into_iter().next(), email)
+                }
+                None => {
+                    warn!("No query response for {email}");
+                    Ok(None)
+                }
+            },
+            Err(e) => {
+                info!("Query for {email} failed: {:?}", e);
+                Err(Error::msg("DDB error".to_string()))
+            }
+        }
+    }
+
+    async fn create_user(&self, email: &str, email_hash: &str) -> Result<Option<User>> {
+        info!("Creating new user: {}", email);
+
+        // this has to be an update to prevent overwriting photo IDs
+        const UPDATE_EXPRESSION: &str = "SET #updated = :updated, #email_hash = :email_hash";
+
+        match self
+            .client
+            .update_item()
+            .table_name(tables::USERS)
+            .update_expression(UPDATE_EXPRESSION)
+            .key(fields::EMAIL, AttributeValue::S(email.to_string()))
+            .key(
+                fields::SORT_KEY,
+                AttributeValue::S(DEFAULT_USER_TABLE_SK_VALUE.to_string()),
+            )
+            .expression_attribute_names("#updated", fields::UPDATED)
+            .expression_attribute_values(":updated", timestamp_to_attr(&Utc::now()))
+            .expression_attribute_names("#email_hash", fields::EMAIL_HASH)
+            .expression_attribute_values(":email_hash", AttributeValue::S(email_hash.to_string()))
+            .return_values(ReturnValue::AllNew)
+            .send()
+            .await
+        {
+            Ok(v) => item_to_user(v.attributes, email),
+            Err(e) => {
+                error!("Failed to create user {}: {:?}", email, e);
+                Err(Error::msg("Failed to create user".to_string()))
+            }
+        }
+    }
+
+    async fn update_subscription(&self, email: &str, topics: Vec<String>) -> Result<Option<User>> {
+        info!("Updating user sub: {}", email);
+
+        // this has to be an update to prevent overwriting photo IDs
+        const UPDATE_EXPRESSION: &str = "SET #topics = :topics, #unsubscribe = :unsubscribe, #updated = :updated";
+
+        // empty list of topics = unsubscribe and requires deletion of topics
+        // ideally it should remove TOPICS via REMOVE action, but complicates the code
+        let topics = if topics.is_empty() {
+            AttributeValue::Null(true)
+        } else {
+            AttributeValue::Ss(topics)
+        };
+
+        match self
+            .client
+            .update_item()
+            .table_name(tables::USERS)
+            .update_expression(UPDATE_EXPRESSION)
+            .key(fields::EMAIL, AttributeValue::S(email.to_string()))
+            .key(
+                fields::SORT_KEY,
+                AttributeValue::S(DEFAULT_USER_TABLE_SK_VALUE.to_string()),
+            )
+            .expression_attribute_names("#topics", fields::TOPICS)
+            .expression_attribute_values([":", fields::TOPICS].concat(), topics)
+            .expression_attribute_names("#unsubscribe", fields::UNSUBSCRIBE)
+            .expression_attribute_values(
+                [":", fields::UNSUBSCRIBE].concat(),
+                AttributeValue::S(new_unsubscribe_token()),
+            )
+            .expression_attribute_names("#updated", fields::UPDATED)
+            .expression_attribute_values([":", fields::UPDATED].concat(), timestamp_to_attr(&Utc::now()))
+            .return_values(ReturnValue::AllNew)
+            .send()
+            .await
+        {
+            Ok(v) => item_to_user(v.attributes, email),
+            Err(e) => {
+                error!("Failed to save user subs {}: {:?}", email, e);
+                Err(Error::msg("Failed to save question".to_string()))
+            }
+        }
+    }
+
+    async fn add_asked_question(&self, email: &str, asked_question: &AskedQuestion) -> Result<()> {
+        // it has to be a vec to work with DDB SET type
+        let asked_question = vec![asked_question.to_string()];
+
+        match self
+            .client
+            .update_item()
+            .table_name(tables::USERS)
+            .update_expression("ADD #questions :questions")
+            .key(fields::EMAIL, AttributeValue::S(email.to_string()))
+            .key(
+                fields::SORT_KEY,
+                AttributeValue::S(DEFAULT_USER_TABLE_SK_VALUE.to_string()),
+            )
+            .expression_attribute_names("#questions", fields::QUESTIONS)
+            .expression_attribute_values(":questions", AttributeValue::Ss(asked_question))
+            .send()
+            .await
+        {
+            Ok(_) => {
+                info!("User answers updated");
+                Ok(())
+            }
+            Err(e) => {
+                error!("Failed to update user answers {}: {:?}", email, e);
+                Err(Error::msg("Failed to update user answers".to_string()))
+            }
+        }
+    }
+
+    async fn get_question_history(&self, email: &str) -> Result<Option<Vec<AskedQuestion>>> {
+        info!("Getting user question history for {email}");
+
+        // try to get the history from DDB
+        let history = match self
+            .client
+            .query()
+            .table_name(tables::USERS)
+            .key_condition_expression("#email = :email AND #sk = :sk")
+            .expression_attribute_names("#email", fields::EMAIL)
+            .expression_attribute_values(":email", AttributeValue::S(email.to_owned()))
+            .expression_attribute_names("#sk", fields::SORT_KEY)
+            .expression_attribute_values(":sk", AttributeValue::S(DEFAULT_USER_TABLE_SK_VALUE.to_owned()))
+            .send()
+            .await
+        {
+            Ok(v) => {
+                let items = v.items.unwrap_or_default();
+                if items.len() > 1 {
+                    warn!("Duplicate records for user: {email}");
+                    return Err(Error::msg("Duplicate user records in DDB".to_string()));
+                }
+
+                match items.into_iter().next() {
+                    Some(item) => User::try_from(item)?.questions,
+                    None => {
+                        warn!("No record for {email}");
+                        return Ok(None);
+                    }
+                }
+            }
+            Err(e) => {
+                error!("Query for {email} failed: {:?}", e);
+                return Err(Error::msg("DDB error".to_string()));
+            }
+        };
+        info!("Found history records in DDB: {}", history.len());
+
+        if history.is_empty() {
+            info!("No questions for {email}");
+            return Ok(None);
+        }
+
+        Ok(Some(history))
+    }
+
+    async fn get_user_by_email_hash(&self, email_hash: &str) -> Result<Option<User>> {
+        info!("Getting user by email hash: {email_hash}");
+
+        // the index only has the keys, so the full record is fetched by email
+        let email = match self
+            .client
+            .query()
+            .table_name(tables::USERS)
+            .index_name(tables::USERS_IDX_EMAIL_HASH)
+            .key_condition_expression("#email_hash = :email_hash")
+            .expression_attribute_names("#email_hash", fields::EMAIL_HASH)
+            .expression_attribute_values(":email_hash", AttributeValue::S(email_hash.to_owned()))
+            .send()
+            .await
+        {
+            Ok(v) => match v.items.and_then(|items| items.into_iter().next()) {
+                Some(item) => match item.get(fields::EMAIL).and_then(|v| v.as_s().ok()) {
+                    Some(v) => v.clone(),
+                    None => {
+                        error!("No email in the index item for {email_hash}. It's a bug.");
+                        return Ok(None);
+                    }
+                },
+                None => {
+                    warn!("No user with email hash {email_hash}");
+                    return Ok(None);
+                }
+            },
+            Err(e) => {
+                error!("Query for {email_hash} failed: {:?}", e);
+                return Err(Error::msg("DDB error".to_string()));
+            }
+        };
+
+        self.get_user(&email).await
+    }
+
+    async fn use_one_time_token(&self, token_id: &str, expires: DateTime<Utc>) -> Result<bool> {
+        // the put fails if the token is already there, so concurrent requests cannot both use it
+        match self
+            .client
+            .put_item()
+            .table_name(tables::USED_TOKENS)
+            .item(fields::TOKEN_ID, AttributeValue::S(token_id.to_string()))
+            .item(fields::TTL, AttributeValue::N(expires.timestamp().to_string()))
+            .condition_expression("attribute_not_exists(#token_id)")
+            .expression_attribute_names("#token_id", fields::TOKEN_ID)
+            .send()
+            .await
+        {
+            Ok(_) => Ok(true),
+            Err(e)
+                if e.as_service_error()
+                    .is_some_and(|e| e.is_conditional_check_failed_exception()) =>
+            {
+                info!("One-time token already used: {token_id}");
+                Ok(false)
+            }
+            Err(e) => {
+                error!("Failed to save one-time token {token_id}: {:?}", e);
+                Err(Error::msg("DDB error".to_string()))
+            }
+        }
+    }
+
+    async fn update_feedback_opt_out(&self, email: &str, opt_out: bool) -> Result<Option<User>> {
+        info!("Updating feedback opt-out for {email}: {opt_out}");
+
+        match self
+            .client
+            .update_item()
+            .table_name(tables::USERS)
+            .update_expression("SET #opt_out = :opt_out, #updated = :updated")
+            .key(fields::EMAIL, AttributeValue::S(email.to_string()))
+            .key(
+                fields::SORT_KEY,
+                AttributeValue::S(DEFAULT_USER_TABLE_SK_VALUE.to_string()),
+            )
+            .expression_attribute_names("#opt_out", fields::FEEDBACK_OPT_OUT)
+            .expression_attribute_values(":opt_out", AttributeValue::Bool(opt_out))
+            .expression_attribute_names("#updated", fields::UPDATED)
+            .expression_attribute_values(":updated", timestamp_to_attr(&Utc::now()))
+            .return_values(ReturnValue::AllNew)
+            .send()
+            .await
+        {
+            Ok(v) => item_to_user(v.attributes, email),
+            Err(e) => {
+                error!("Failed to update feedback opt-out for {email}: {:?}", e);
+                Err(Error::msg("Failed to update user".to_string()))
+            }
+        }
+    }
+
+    async fn update_links_opt_out(&self, email: &str, opt_out: bool) -> Result<Option<User>> {
+        info!("Updating links opt-out for {email}: {opt_out}");
+
+        match self
+            .client
+            .update_item()
+            .table_name(tables::USERS)
+            .update_expression("SET #opt_out = :opt_out, #updated = :updated")
+            .key(fields::EMAIL, AttributeValue::S(email.to_string()))
+            .key(
+                fields::SORT_KEY,
+                AttributeValue::S(DEFAULT_USER_TABLE_SK_VALUE.to_string()),
+            )
+            .expression_attribute_names("#opt_out", fields::LINKS_OPT_OUT)
+            .expression_attribute_values(":opt_out", AttributeValue::Bool(opt_out))
+            .expression_attribute_names("#updated", fields::UPDATED)
+            .expression_attribute_values(":updated", timestamp_to_attr(&Utc::now()))
+            .return_values(ReturnValue::AllNew)
+            .send()
+            .await
+        {
+            Ok(v) => item_to_user(v.attributes, email),
+            Err(e) => {
+                error!("Failed to update links opt-out for {email}: {:?}", e);
+                Err(Error::msg("Failed to update user".to_string()))
+            }
+        }
+    }
+
+    async fn update_feedback_notified(
+        &self,
+        email: &str,
+        notified: DateTime<Utc>,
+        previous: Option<DateTime<Utc>>,
+    ) -> Result<bool> {
+        let request = self
+            .client
+            .update_item()
+            .table_name(tables::USERS)
+            .update_expression("SET #notified = :notified")
+            .key(fields::EMAIL, AttributeValue::S(email.to_string()))
+            .key(
+                fields::SORT_KEY,
+                AttributeValue::S(DEFAULT_USER_TABLE_SK_VALUE.to_string()),
+            )
+            .expression_attribute_names("#notified", fields::FEEDBACK_NOTIFIED)
+            .expression_attribute_values(":notified", timestamp_to_attr(&notified));
+
+        // fail the update if another request sent a digest after the user record was read
+        let request = match previous {
+            Some(v) => request
+                .condition_expression("#notified = :previous")
+                .expression_attribute_values(":previous", timestamp_to_attr(&v)),
+            None => request.condition_expression("attribute_not_exists(#notified)"),
+        };
+
+        match request.send().await {
+            Ok(_) => Ok(true),
+            Err(e)
+                if e.as_service_error()
+                    .is_some_and(|e| e.is_conditional_check_failed_exception()) =>
+            {
+                info!("Feedback digest for {email} was already sent by another request");
+                Ok(false)
+            }
+            Err(e) => {
+                error!("Failed to update feedback notification time for {email}: {:?}", e);
+                Err(Error::msg("Failed to update user".to_string()))
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl FeedbackRepository for DdbRepository {
+    async fn save_feedback(&self, feedback: &Feedback) -> Result<()> {
+        info!("Saving feedback {}/{}", feedback.qid, feedback.fid);
+
+        match self
+            .client
+            .put_item()
+            .table_name(tables::FEEDBACK)
+            .set_item(Some(Item::from(feedback)))
+            .send()
+            .await
+        {
+            Ok(_) => {
+                info!("Feedback saved in DDB");
+                Ok(())
+            }
+            Err(e) => {
+                error!("Failed to save feedback {}/{}: {:?}", feedback.qid, feedback.fid, e);
+                Err(Error::msg("Failed to save feedback".to_string()))
+            }
+        }
+    }
+
+    async fn get_feedback_for_question(&self, topic: &str, qid: &str) -> Result<Vec<Feedback>> {
+        info!("Getting feedback for {topic} / {qid}");
+
+        let items = match self
+            .client
+            .query()
+            .table_name(tables::FEEDBACK)
+            .key_condition_expression("#qid = :qid")
+            .expression_attribute_names("#qid", fields::QID)
+            .expression_attribute_values(":qid", AttributeValue::S(qid.to_owned()))
+            .send()
+            .await
+        {
+            Ok(v) => v.items.unwrap_or_default(),
+            Err(e) => {
+                error!("Feedback query for {qid} failed: {:?}", e);
+                return Err(Error::msg("DDB error".to_string()));
+            }
+        };
+
+        Ok(items_to_feedback(items, |v| v.topic == topic))
+    }
+
+    async fn get_feedback_by_status(&self, status: FeedbackStatus) -> Result<Vec<Feedback>> {
+        info!("Getting {status} feedback");
+
+        let items = match self
+            .client
+            .query()
+            .table_name(tables::FEEDBACK)
+            .index_name(tables::FEEDBACK_IDX_STATUS)
+            .key_condition_expression("#status = :status")
+            .expression_attribute_names("#status", fields::STATUS)
+            .expression_attribute_values(":status", AttributeValue::S(status.to_string()))
+            .send()
+            .await
+        {
+            // TODO: add pagination if the number of open tickets ever grows beyond a page
+            Ok(v) => v.items.unwrap_or_default(),
+            Err(e) => {
+                error!("Feedback query for {status} failed: {:?}", e);
+                return Err(Error::msg("DDB error".to_string()));
+            }
+        };
+
+        Ok(items_to_feedback(items, |_| true))
+    }
+
+    async fn resolve_feedback(&self, qid: &str, fid: &str, reply: Option<String>) -> Result<Feedback> {
+        info!("Resolving feedback {qid} / {fid}");
+
+        // an empty reply removes the previous one
+        let update_expression = match reply {
+            Some(_) => "SET #status = :status, #updated = :updated, #reply = :reply",
+            None => "SET #status = :status, #updated = :updated REMOVE #reply",
+        };
+
+        let request = self
+            .client
+            .update_item()
+            .table_name(tables::FEEDBACK)
+            .update_expression(update_expression)
+            .key(fields::QID, AttributeValue::S(qid.to_string()))
+            .key(fields::FID, AttributeValue::S(fid.to_string()))
+            .expression_attribute_names("#fid", fields::FID)
+            .expression_attribute_names("#status", fields::STATUS)
+            .expression_attribute_values(":status", AttributeValue::S(FeedbackStatus::Resolved.to_string()))
+            .expression_attribute_names("#updated", fields::UPDATED)
+            .expression_attribute_values(":updated", timestamp_to_attr(&Utc::now()))
+            .expression_attribute_names("#reply", fields::REPLY)
+            .condition_expression("attribute_exists(#fid)") // update_item would create a new item otherwise
+            .return_values(ReturnValue::AllNew);
+
+        let request = match reply {
+            Some(v) => request.expression_attribute_values(":reply", AttributeValue::S(v)),
+            None => request,
+        };
+
+        match request.send().await {
+            Ok(v) => Ok(Feedback::try_from(v.attributes.unwrap_or_default())?),
+            Err(e)
+                if e.as_service_error()
+                    .is_some_and(|e| e.is_conditional_check_failed_exception()) =>
+            {
+                warn!("No feedback to resolve: {qid} / {fid}");
+                Err(RepositoryError::FeedbackNotFound.into())
+            }
+            Err(e) => {
+                error!("Failed to resolve feedback {qid} / {fid}: {:?}", e);
+                Err(Error::msg("Failed to resolve feedback".to_string()))
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl LinkRepository for DdbRepository {
+    async fn get_link(&self, url: &str) -> Result<Option<LinkStatus>> {
+        info!("Getting link {url}");
+
+        match self
+            .client
+            .get_item()
+            .table_name(tables::LINKS)
+            .key(fields::URL, AttributeValue::S(url.to_string()))
+            .send()
+            .await
+        {
+            Ok(v) => match v.item {
+                Some(item) => Ok(Some(LinkStatus::try_from(item)?)),
+                None => Ok(None),
+            },
+            Err(e) => {
+                info!("Get item for {url} failed: {:?}", e);
+                Err(Error::msg("DDB error".to_string()))
+            }
+        }
+    }
+
+    async fn get_links(&self, urls: &[String]) -> Result<Vec<LinkStatus>> {
+        info!("Getting {} links", urls.len());
+
+        let mut links = Vec::with_capacity(urls.len());
+        for chunk in urls.chunks(MAX_BATCH_GET_KEYS) {
+            let keys = chunk
+                .iter()
+                .map(|v| Item::from([(fields::URL.to_string(), AttributeValue::S(v.clone()))]))
+                .collect::<Vec<Item>>();
+            let keys_and_attributes = KeysAndAttributes::builder().set_keys(Some(keys)).build()?;
+
+            match self
+                .client
+                .batch_get_item()
+                .request_items(tables::LINKS, keys_and_attributes)
+                .send()
+                .await
+            {
+                Ok(v) => {
+                    // unprocessed keys are not retried because the titles are only nice to have
+                    if v.unprocessed_keys.as_ref().is_some_and(|v| !v.is_empty()) {
+                        warn!("Some links were not fetched");
+                    }
+                    // invalid items are logged inside try_from
+                    links.extend(
+                        v.responses
+                            .and_then(|mut v| v.remove(tables::LINKS))
+                            .unwrap_or_default()
+                            .into_iter()
+                            .filter_map(|v| LinkStatus::try_from(v).ok()),
+                    );
+                }
+                Err(e) => {
+                    info!("Batch get for {} links failed: {:?}", chunk.len(), e);
+                    return Err(Error::msg("DDB error".to_string()));
+                }
+            }
+        }
+
+        Ok(links)
+    }
+
+    async fn save_link(&self, link: &LinkStatus) -> Result<()> {
+        info!("Saving link {}: {:?}", link.url, link.http_status);
+
+        match self
+            .client
+            .put_item()
+            .table_name(tables::LINKS)
+            .set_item(Some(Item::from(link)))
+            .send()
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                error!("Failed to save link {}: {:?}", link.url, e);
+                Err(Error::msg("Failed to save link".to_string()))
+            }
+        }
+    }
+}
+
+/// Converts feedback items, keeps the ones matching the filter and sorts them by `created` desc.
+/// Invalid items are logged and skipped.
+fn items_to_feedback(items: Vec<Item>, filter: impl Fn(&Feedback) -> bool) -> Vec<Feedback> {
+    // invalid items are logged inside try_from
+    let mut feedback = items
+        .into_iter()
+        .filter_map(|v| Feedback::try_from(v).ok())
+        .filter(|v| filter(v))
+        .collect::<Vec<Feedback>>();
+    info!("Fetched feedback: {}", feedback.len());
+
+    feedback.sort_by_key(|v| Reverse(v.created));
+    feedback
+}
+
+/// Converts a user item into User without the question history.
+/// The history is only needed in the context of questions and is fetched separately.
+fn item_to_user(item: Option<Item>, email: &str) -> Result<Option<User>> {
+    match item {
+        Some(v) => {
+            info!("Returning user dets");
+            Ok(Some(User {
+                questions: Vec::new(),
+                ..User::try_from(v)?
+            }))
+        }
+        None => {
+            // should not happen, but carry on anyway
+            warn!("No items in query response for {email}");
+            Ok(None)
+        }
+    }
+}
@@ -0,0 +1,612 @@
+//! In-memory implementation of the repository traits for tests and local development.
+//! It mimics the behavior of `DdbRepository`, including the author check on save.
+
+use super::{
+    new_unsubscribe_token, FeedbackRepository, LinkRepository, QuestionRepository, RepositoryError, UserRepository,
+};
+use anyhow::{Error, Result};
+use async_trait::async_trait;
+use bitie_types::{
+    embed::{EmbedStats, MAX_EMBED_HOSTS},
+    feedback::{Feedback, FeedbackStatus},
+    links::LinkStatus,
+    question::{PublishStage, Question, Stats},
+    user::{AnswerStatus, AskedQuestion, User},
+};
+use chrono::{DateTime, Timelike, Utc};
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap},
+    sync::Mutex,
+};
+use tracing::{info, warn};
+
+/// Keeps all the data in hashmaps behind mutexes.
+/// Questions are keyed by `(topic, qid)`, users by email, feedback by `(qid, fid)`, embed stats by `(qid, host)`,
+/// links by URL, used one-time tokens by their ID.
+#[derive(Default, Debug)]
+pub struct MemoryRepository {
+    questions: Mutex<BTreeMap<(String, String), Question>>,
+    users: Mutex<HashMap<String, User>>,
+    feedback: Mutex<BTreeMap<(String, String), Feedback>>,
+    embeds: Mutex<BTreeMap<(String, String), EmbedStats>>,
+    links: Mutex<HashMap<String, LinkStatus>>,
+    used_tokens: Mutex<HashMap<String, DateTime<Utc>>>,
+}
+
+impl MemoryRepository {
+    /// Creates an empty repository.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a repository pre-populated with the given questions.
+    /// Unlike `save_question`, the stage and stats are stored as-is.
+    pub fn with_questions(questions: Vec<Question>) -> Self {
+        let repo = Self::new();
+        {
+            let mut stored = repo.questions.lock().expect("Poisoned mutex. It's a bug.");
+            for question in questions {
+                stored.insert((question.topic.clone(), question.qid.clone()), question);
+            }
+        }
+        repo
+    }
+
+    /// Returns a copy of all stored questions.
+    pub fn all_questions(&self) -> Vec<Question> {
+        self.questions
+            .lock()
+            .expect("Poisoned mutex. It's a bug.")
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    /// Adds or replaces a user record as-is, e.g. to set up a moderator in tests.
+    pub fn insert_user(&self, user: User) {
+        self.users
+            .lock()
+            .expect("Poisoned mutex. It's a bug.")
+            .insert(user.email.clone(), user);
+    }
+
+    /// Returns the stats of the question embedded on the host, if any.
+    pub fn embed_stats(&self, qid: &str, host: &str) -> Option<EmbedStats> {
+        self.embeds
+            .lock()
+            .expect("Poisoned mutex. It's a bug.")
+            .get(&(qid.to_string(), host.to_string()))
+            .cloned()
+    }
+
+    /// Returns all feedback matching the filter sorted by `created` desc.
+    fn list_feedback(&self, filter: impl Fn(&Feedback) -> bool) -> Vec<Feedback> {
+        let mut feedback = self
+            .feedback
+            .lock()
+            .expect("Poisoned mutex. It's a bug.")
+            .values()
+            .filter(|v| filter(v))
+            .cloned()
+            .collect::<Vec<Feedback>>();
+        feedback.sort_by_key(|v| Reverse(v.created));
+        info!("Fetched feedback: {}", feedback.len());
+        feedback
+    }
+
+    /// Returns all questions matching the filter, stripped for list display and sorted by `updated` desc.
+    fn list_questions(&self, filter: impl Fn(&Question) -> bool) -> Vec<Question> {
+        let mut questions = self
+            .questions
+            .lock()
+            .expect("Poisoned mutex. It's a bug.")
+            .values()
+            .filter(|v| filter(v))
+            // the indexes do not project the stats, but include the author
+            .map(|v| Question {
+                author: v.author.clone(),
+                stats: None,
+                ..v.clone().strip_for_list_display()
+            })
+            .collect::<Vec<Question>>();
+        questions.sort_by_key(|v| Reverse(v.updated));
+        info!("Fetched questions: {}", questions.len());
+        questions
+    }
+}
+
+#[async_trait]
+impl QuestionRepository for MemoryRepository {
+    async fn get_question(&self, topic: &str, qid: &str) -> Result<Option<Question>> {
+        let questions = self.questions.lock().expect("Poisoned mutex. It's a bug.");
+        Ok(questions.get(&(topic.to_string(), qid.to_string())).map(|v| Question {
+            // DDB always returns the counters, even if they are all zeros
+            stats: Some(v.stats.clone().unwrap_or(Stats {
+                correct: 0,
+                incorrect: 0,
+                skipped: 0,
+            })),
+            ..v.clone()
+        }))
+    }
+
+    async fn save_question(&self, question: &Question) -> Result<()> {
+        let author = match &question.author {
+            Some(v) => v.clone(),
+            None => {
+                warn!("Missing author field. It's a bug.");
+                return Err(Error::msg("Failed to save question".to_string()));
+            }
+        };
+        if question.updated.is_none() {
+            warn!("Missing updated field. It's a bug.");
+            return Err(Error::msg("Failed to save question".to_string()));
+        }
+
+        let mut questions = self.questions.lock().expect("Poisoned mutex. It's a bug.");
+        let key = (question.topic.clone(), question.qid.clone());
+
+        // the author cannot be changed and the stats are not overwritten, same as in DDB
+        let stats = match questions.get(&key) {
+            Some(existing) if existing.author.is_some() && existing.author.as_ref() != Some(&author) => {
+                warn!(
+                    "Failed to save question {}/{}: author mismatch",
+                    question.topic, question.qid
+                );
+                return Err(RepositoryError::AuthorMismatch.into());
+            }
+            Some(existing) => existing.stats.clone(),
+            None => None,
+        };
+
+        questions.insert(
+            key,
+            Question {
+                stats,
+                ..question.clone()
+            },
+        );
+
+        Ok(())
+    }
+
+    async fn change_publish_stage(&self, topic: &str, qid: &str, stage: PublishStage) -> Result<()> {
+        let mut questions = self.questions.lock().expect("Poisoned mutex. It's a bug.");
+        match questions.get_mut(&(topic.to_string(), qid.to_string())) {
+            Some(v) => {
+                v.stage = stage;
+                v.updated = Utc::now().with_nanosecond(0);
+                Ok(())
+            }
+            None => {
+                warn!("No question found for {topic} / {qid}");
+                Err(RepositoryError::QuestionNotFound.into())
+            }
+        }
+    }
+
+    async fn increment_answer_stats(&self, topic: &str, qid: &str, status: &AnswerStatus) -> Result<()> {
+        let mut questions = self.questions.lock().expect("Poisoned mutex. It's a bug.");
+
+        // DDB ADD creates the record if it does not exist, but a stats-only question is of no use here
+        let question = match questions.get_mut(&(topic.to_string(), qid.to_string())) {
+            Some(v) => v,
+            None => {
+                warn!("No question found for {topic} / {qid}");
+                return Ok(());
+            }
+        };
+
+        let stats = question.stats.get_or_insert(Stats {
+            correct: 0,
+            incorrect: 0,
+            skipped: 0,
+        });
+
+        match status {
+            AnswerStatus::Asked(_) => {}
+            AnswerStatus::Correct(_) => stats.correct += 1,
+            AnswerStatus::Incorrect(_) => stats.incorrect += 1,
+            AnswerStatus::Skipped(_) => stats.skipped += 1,
+        }
+
+        Ok(())
+    }
+
+    async fn increment_embed_stats(&self, _topic: &str, qid: &str, host: &str, status: &AnswerStatus) -> Result<()> {
+        let mut embeds = self.embeds.lock().expect("Poisoned mutex. It's a bug.");
+        let key = (qid.to_string(), host.to_string());
+        if !embeds.contains_key(&key) && embeds.keys().filter(|(v, _)| v == qid).count() >= MAX_EMBED_HOSTS {
+            warn!("Too many embed hosts for {qid}, {host} is not counted");
+            return Ok(());
+        }
+        let stats = embeds.entry(key).or_default();
+
+        match status {
+            AnswerStatus::Asked(_) => stats.views += 1,
+            AnswerStatus::Correct(_) => stats.correct += 1,
+            AnswerStatus::Incorrect(_) => stats.incorrect += 1,
+            AnswerStatus::Skipped(_) => stats.skipped += 1,
+        }
+
+        Ok(())
+    }
+
+    async fn get_published_questions_by_topic(&self, topic: &str) -> Result<Vec<Question>> {
+        Ok(self.list_questions(|v| v.topic == topic && v.stage == PublishStage::Published))
+    }
+
+    async fn get_all_questions_by_author(&self, email_hash: &str) -> Result<Vec<Question>> {
+        Ok(self.list_questions(|v| v.author.as_deref() == Some(email_hash)))
+    }
+}
+
+#[async_trait]
+impl UserRepository for MemoryRepository {
+    async fn get_user(&self, email: &str) -> Result<Option<User>> {
+        let users = self.users.lock().expect("Poisoned mutex. It's a bug.");
+        Ok(users.get(email).map(|v| User {
+            // the history is not returned with the user details, same as in DDB
+            questions: Vec::new(),
+            ..v.clone()
+        }))
+    }
+
+    async fn create_user(&self, email: &str, email_hash: &str) -> Result<Option<User>> {
+        {
+            let mut users = self.users.lock().expect("Poisoned mutex. It's a bug.");
+            let user = users.entry(email.to_string()).or_insert_with(|| blank_user(email));
+            user.email_hash = email_hash.to_string();
+            user.updated = Utc::now().with_nanosecond(0);
+        }
+        self.get_user(email).await
+    }
+
+    async fn update_subscription(&self, email: &str, topics: Vec<String>) -> Result<Option<User>> {
+        {
+            let mut users = self.users.lock().expect("Poisoned mutex. It's a bug.");
+            let user = users.entry(email.to_string()).or_insert_with(|| blank_user(email));
+            user.topics = topics;
+            user.unsubscribe = new_unsubscribe_token();
+            user.updated = Utc::now().with_nanosecond(0);
+        }
+        self.get_user(email).await
+    }
+
+    async fn add_asked_question(&self, email: &str, asked_question: &AskedQuestion) -> Result<()> {
+        let mut users = self.users.lock().expect("Poisoned mutex. It's a bug.");
+        let user = users.entry(email.to_string()).or_insert_with(|| blank_user(email));
+
+        // DDB String Sets have no duplicates
+        if !user.questions.contains(asked_question) {
+            user.questions.push(asked_question.clone());
+        }
+
+        Ok(())
+    }
+
+    async fn get_question_history(&self, email: &str) -> Result<Option<Vec<AskedQuestion>>> {
+        let users = self.users.lock().expect("Poisoned mutex. It's a bug.");
+        Ok(users.get(email).map(|v| v.questions.clone()).filter(|v| !v.is_empty()))
+    }
+
+    async fn get_user_by_email_hash(&self, email_hash: &str) -> Result<Option<User>> {
+        let email = {
+            let users = self.users.lock().expect("Poisoned mutex. It's a bug.");
+            match users.values().find(|v| v.email_hash == email_hash) {
+                Some(v) => v.email.clone(),
+                None => return Ok(None),
+            }
+        };
+        self.get_user(&email).await
+    }
+
+    async fn use_one_time_token(&self, token_id: &str, expires: DateTime<Utc>) -> Result<bool> {
+        let mut used_tokens = self.used_tokens.lock().expect("Poisoned mutex. It's a bug.");
+        if used_tokens.contains_key(token_id) {
+            info!("One-time token already used: {token_id}");
+            return Ok(false);
+        }
+        used_tokens.insert(token_id.to_string(), expires);
+        Ok(true)
+    }
+
+    async fn update_feedback_opt_out(&self, email: &str, opt_out: bool) -> Result<Option<User>> {
+        {
+            let mut users = self.users.lock().expect("Poisoned mutex. It's a bug.");
+            let user = users.entry(email.to_string()).or_insert_with(|| blank_user(email));
+            user.feedback_opt_out = Some(opt_out);
+            user.updated = Utc::now().with_nanosecond(0);
+        }
+        self.get_user(email).await
+    }
+
+    async fn update_links_opt_out(&self, email: &str, opt_out: bool) -> Result<Option<User>> {
+        {
+            let mut users = self.users.lock().expect("Poisoned mutex. It's a bug.");
+            let user = users.entry(email.to_string()).or_insert_with(|| blank_user(email));
+            user.links_opt_out = Some(opt_out);
+            user.updated = Utc::now().with_nanosecond(0);
+        }
+        self.get_user(email).await
+    }
+
+    async fn update_feedback_notified(
+        &self,
+        email: &str,
+        notified: DateTime<Utc>,
+        previous: Option<DateTime<Utc>>,
+    ) -> Result<bool> {
+        let mut users = self.users.lock().expect("Poisoned mutex. It's a bug.");
+        let user = users.entry(email.to_string()).or_insert_with(|| blank_user(email));
+        if user.feedback_notified != previous {
+            info!("Feedback digest for {email} was already sent by another request");
+            return Ok(false);
+        }
+        user.feedback_notified = Some(notified);
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl FeedbackRepository for MemoryRepository {
+    async fn save_feedback(&self, feedback: &Feedback) -> Result<()> {
+        self.feedback
+            .lock()
+            .expect("Poisoned mutex. It's a bug.")
+            .insert((feedback.qid.clone(), feedback.fid.clone()), feedback.clone());
+        Ok(())
+    }
+
+    async fn get_feedback_for_question(&self, topic: &str, qid: &str) -> Result<Vec<Feedback>> {
+        Ok(self.list_feedback(|v| v.topic == topic && v.qid == qid))
+    }
+
+    async fn get_feedback_by_status(&self, status: FeedbackStatus) -> Result<Vec<Feedback>> {
+        Ok(self.list_feedback(|v| v.status == status))
+    }
+
+    async fn resolve_feedback(&self, qid: &str, fid: &str, reply: Option<String>) -> Result<Feedback> {
+        let mut feedback = self.feedback.lock().expect("Poisoned mutex. It's a bug.");
+        match feedback.get_mut(&(qid.to_string(), fid.to_string())) {
+            Some(v) => {
+                *v = v.clone().resolve(reply);
+                Ok(v.clone())
+            }
+            None => {
+                warn!("No feedback to resolve: {qid} / {fid}");
+                Err(RepositoryError::FeedbackNotFound.into())
+            }
+        }
+    }
+}
+
+/// A user record with no details other than the email, as DDB would create it on update.
+fn blank_user(email: &str) -> User {
+    User {
+        email: email.to_string(),
+        email_hash: String::new(),
+        topics: Vec::new(),
+        questions: Vec::new(),
+        unsubscribe: String::new(),
+        updated: None,
+        is_mod: None,
+        feedback_opt_out: None,
+        feedback_notified: None,
+        links_opt_out: None,
+    }
+}
+
+#[async_trait]
+impl LinkRepository for MemoryRepository {
+    async fn get_link(&self, url: &str) -> Result<Option<LinkStatus>> {
+        Ok(self
+            .links
+            .lock()
+            .expect("Poisoned mutex. It's a bug.")
+            .get(url)
+            .cloned())
+    }
+
+    async fn get_links(&self, urls: &[String]) -> Result<Vec<LinkStatus>> {
+        let links = self.links.lock().expect("Poisoned mutex. It's a bug.");
+        Ok(urls.iter().filter_map(|v| links.get(v).cloned()).collect())
+    }
+
+    async fn save_link(&self, link: &LinkStatus) -> Result<()> {
+        self.links
+            .lock()
+            .expect("Poisoned mutex. It's a bug.")
+            .insert(link.url.clone(), link.clone());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+    use std::str::FromStr;
+
+    /// A minimal valid question.
+    fn question(topic: &str, qid: &str, author: &str) -> Question {
+        test_utils::question(topic, qid).with_author(author).with_updated()
+    }
+
+    #[tokio::test]
+    async fn test_save_and_get_question() {
+        let repo = MemoryRepository::new();
+        let q = question("aws", "89yZBXJBa9t2LB6xfj46Rm", "author1");
+
+        repo.save_question(&q).await.unwrap();
+        let saved = repo
+            .get_question("aws", "89yZBXJBa9t2LB6xfj46Rm")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(saved.question, q.question);
+        assert_eq!(saved.stats.unwrap().correct, 0);
+
+        assert!(repo.get_question("aws", "missing").await.unwrap().is_none());
+
+        // a different author cannot overwrite the question
+        let q2 = q.clone().with_author("author2");
+        assert!(repo.save_question(&q2).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_question_lists() {
+        let q1 = question("aws", "89yZBXJBa9t2LB6xfj46Rm", "author1").with_stage(PublishStage::Published);
+        let q2 = question("aws", "NgGdoZov4T6jV46ty4JUX6", "author2");
+        let q3 = question("rust", "3RuWxwkgBgpWk6ZUARaZx6", "author1").with_stage(PublishStage::Published);
+        let repo = MemoryRepository::with_questions(vec![q1.clone(), q2, q3]);
+
+        let published = repo.get_published_questions_by_topic("aws").await.unwrap();
+        assert_eq!(published.len(), 1);
+        assert_eq!(published[0].qid, q1.qid);
+        assert!(published[0].question.is_empty(), "list questions must be stripped");
+
+        let by_author = repo.get_all_questions_by_author("author1").await.unwrap();
+        assert_eq!(by_author.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_stats_and_stage() {
+        let q = question("aws", "89yZBXJBa9t2LB6xfj46Rm", "author1");
+        let repo = MemoryRepository::with_questions(vec![q.clone()]);
+
+        let now = Utc::now();
+        repo.increment_answer_stats("aws", &q.qid, &AnswerStatus::Correct(now))
+            .await
+            .unwrap();
+        repo.increment_answer_stats("aws", &q.qid, &AnswerStatus::Skipped(now))
+            .await
+            .unwrap();
+        repo.increment_answer_stats("aws", &q.qid, &AnswerStatus::Asked(now))
+            .await
+            .unwrap();
+        repo.change_publish_stage("aws", &q.qid, PublishStage::Published)
+            .await
+            .unwrap();
+
+        let saved = repo.get_question("aws", &q.qid).await.unwrap().unwrap();
+        assert_eq!(
+            saved.stats,
+            Some(Stats {
+                correct: 1,
+                incorrect: 0,
+                skipped: 1
+            })
+        );
+        assert_eq!(saved.stage, PublishStage::Published);
+
+        assert!(repo
+            .change_publish_stage("aws", "missing", PublishStage::Draft)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_embed_hosts_cap() {
+        let repo = MemoryRepository::new();
+        let qid = "89yZBXJBa9t2LB6xfj46Rm";
+        let asked = AnswerStatus::Asked(Utc::now());
+        for i in 0..=MAX_EMBED_HOSTS {
+            repo.increment_embed_stats("aws", qid, &format!("site{i}.com"), &asked)
+                .await
+                .unwrap();
+        }
+
+        // new hosts are ignored once the cap is reached, but the known ones are still counted
+        assert!(repo.embed_stats(qid, &format!("site{MAX_EMBED_HOSTS}.com")).is_none());
+        repo.increment_embed_stats("aws", qid, "site0.com", &asked)
+            .await
+            .unwrap();
+        assert_eq!(repo.embed_stats(qid, "site0.com").unwrap().views, 2);
+
+        // other questions have their own cap
+        repo.increment_embed_stats("aws", "NgGdoZov4T6jV46ty4JUX6", "new.com", &asked)
+            .await
+            .unwrap();
+        assert!(repo.embed_stats("NgGdoZov4T6jV46ty4JUX6", "new.com").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_users() {
+        let repo = MemoryRepository::new();
+        assert!(repo.get_user("a@b.c").await.unwrap().is_none());
+
+        let user = repo.create_user("a@b.c", "hash").await.unwrap().unwrap();
+        assert_eq!(user.email_hash, "hash");
+
+        let user = repo
+            .update_subscription("a@b.c", vec!["aws".to_string()])
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(user.topics, vec!["aws".to_string()]);
+        assert!(!user.unsubscribe.is_empty());
+
+        assert!(repo.get_question_history("a@b.c").await.unwrap().is_none());
+        let asked = AskedQuestion::from_str("aws/3RuWxwkgBgpWk6ZUARaZx6/2024-10-31T08:39:17Zc").unwrap();
+        repo.add_asked_question("a@b.c", &asked).await.unwrap();
+        repo.add_asked_question("a@b.c", &asked).await.unwrap();
+        assert_eq!(repo.get_question_history("a@b.c").await.unwrap(), Some(vec![asked]));
+    }
+
+    #[tokio::test]
+    async fn test_feedback() {
+        let repo = MemoryRepository::new();
+        let f1 = Feedback::new("aws", "89yZBXJBa9t2LB6xfj46Rm", None, "1.2.3.4", "Typo in the question");
+        let f2 = Feedback::new("aws", "NgGdoZov4T6jV46ty4JUX6", None, "1.2.3.4", "Wrong answer");
+        repo.save_feedback(&f1).await.unwrap();
+        repo.save_feedback(&f2).await.unwrap();
+
+        let for_question = repo.get_feedback_for_question("aws", &f1.qid).await.unwrap();
+        assert_eq!(for_question, vec![f1.clone()]);
+        assert!(repo
+            .get_feedback_for_question("rust", &f1.qid)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let resolved = repo
+            .resolve_feedback(&f1.qid, &f1.fid, Some("Fixed".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(resolved.status, FeedbackStatus::Resolved);
+
+        let open = repo.get_feedback_by_status(FeedbackStatus::Open).await.unwrap();
+        assert_eq!(open, vec![f2.clone()]);
+
+        let e = repo.resolve_feedback(&f1.qid, &f2.fid, None).await.unwrap_err();
+        assert_eq!(
+            e.downcast_ref::<RepositoryError>(),
+            Some(&RepositoryError::FeedbackNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_feedback_notifications() {
+        let repo = MemoryRepository::new();
+        repo.create_user("a@b.c", "hash").await.unwrap();
+        assert_eq!(
+            repo.get_user_by_email_hash("hash").await.unwrap().unwrap().email,
+            "a@b.c"
+        );
+        assert!(repo.get_user_by_email_hash("other").await.unwrap().is_none());
+
+        let user = repo.update_feedback_opt_out("a@b.c", true).await.unwrap().unwrap();
+        assert_eq!(user.feedback_opt_out, Some(true));
+        let user = repo.update_links_opt_out("a@b.c", true).await.unwrap().unwrap();
+        assert_eq!(user.links_opt_out, Some(true));
+        assert_eq!(user.feedback_opt_out, Some(true));
+
+        // only the first of two concurrent updates succeeds
+        let now = Utc::now();
+        assert!(repo.update_feedback_notified("a@b.c", now, None).await.unwrap());
+        assert!(!repo.update_feedback_notified("a@b.c", now, None).await.unwrap());
+        assert!(repo.update_feedback_notified("a@b.c", now, Some(now)).await.unwrap());
+    }
+}
@@ -0,0 +1,162 @@
+//! Storage abstraction for questions, users, feedback and link checks.
+//!
+//! Handlers talk to the storage through `QuestionRepository`, `UserRepository`, `FeedbackRepository`
+//! and `LinkRepository` traits so that the handler logic can be tested offline against `MemoryRepository`.
+//! `DdbRepository` is the production implementation.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use bitie_types::{
+    feedback::{Feedback, FeedbackStatus},
+    links::LinkStatus,
+    question::{PublishStage, Question},
+    user::{AnswerStatus, AskedQuestion, User},
+};
+use chrono::{DateTime, Utc};
+
+pub use ddb::DdbRepository;
+pub use memory::MemoryRepository;
+
+mod ddb;
+mod memory;
+
+/// Storage errors that the handlers may need to tell apart from other failures.
+/// They are returned inside `anyhow::Error` and can be checked with `downcast_ref`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RepositoryError {
+    /// There is no question with the given topic and qid.
+    QuestionNotFound,
+    /// The question exists, but was created by another user.
+    AuthorMismatch,
+    /// There is no feedback with the given qid and fid.
+    FeedbackNotFound,
+}
+
+impl std::fmt::Display for RepositoryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::QuestionNotFound => write!(f, "No question found"),
+            Self::AuthorMismatch => write!(f, "Author mismatch"),
+            Self::FeedbackNotFound => write!(f, "No feedback found"),
+        }
+    }
+}
+
+impl std::error::Error for RepositoryError {}
+
+/// Read and write access to the questions table.
+#[async_trait]
+pub trait QuestionRepository: Send + Sync {
+    /// Returns the full question with stats and the publish stage.
+    /// Returns None if no records found.
+    async fn get_question(&self, topic: &str, qid: &str) -> Result<Option<Question>>;
+
+    /// Saves a question, replacing the existing record.
+    /// Returns `RepositoryError::AuthorMismatch` if the question was authored by someone else.
+    async fn save_question(&self, question: &Question) -> Result<()>;
+
+    /// Changes the publish stage inside the question details and in the stage attribute.
+    /// Returns `RepositoryError::QuestionNotFound` if there is no such question.
+    async fn change_publish_stage(&self, topic: &str, qid: &str, stage: PublishStage) -> Result<()>;
+
+    /// Increments the stats counter matching the answer status.
+    /// `AnswerStatus::Asked` is not counted.
+    async fn increment_answer_stats(&self, topic: &str, qid: &str, status: &AnswerStatus) -> Result<()>;
+
+    /// Increments the embed stats counter matching the answer status for the host of the embedding site.
+    /// `AnswerStatus::Asked` is counted as a view.
+    /// New hosts are ignored once the question has `MAX_EMBED_HOSTS` of them.
+    async fn increment_embed_stats(&self, topic: &str, qid: &str, host: &str, status: &AnswerStatus) -> Result<()>;
+
+    /// Returns a list of published questions for the given topic, most recently updated first.
+    /// Only the list display fields are included, plus the author.
+    async fn get_published_questions_by_topic(&self, topic: &str) -> Result<Vec<Question>>;
+
+    /// Returns a list of all questions by the given author, most recently updated first.
+    /// Only the list display fields are included.
+    async fn get_all_questions_by_author(&self, email_hash: &str) -> Result<Vec<Question>>;
+}
+
+/// Read and write access to the users table.
+#[async_trait]
+pub trait UserRepository: Send + Sync {
+    /// Returns the user without the question history.
+    /// Returns None if no records found.
+    async fn get_user(&self, email: &str) -> Result<Option<User>>;
+
+    /// Creates a new user record, or updates the existing one with a new timestamp.
+    async fn create_user(&self, email: &str, email_hash: &str) -> Result<Option<User>>;
+
+    /// Replaces the list of subscribed topics and the unsubscribe token.
+    /// An empty list of topics unsubscribes the user.
+    async fn update_subscription(&self, email: &str, topics: Vec<String>) -> Result<Option<User>>;
+
+    /// Adds an entry to the user's question history.
+    async fn add_asked_question(&self, email: &str, asked_question: &AskedQuestion) -> Result<()>;
+
+    /// Returns the full question history of the user in no particular order.
+    /// Returns None if there is no such user or the user has no history.
+    async fn get_question_history(&self, email: &str) -> Result<Option<Vec<AskedQuestion>>>;
+
+    /// Returns the user with the given email hash, e.g. the author of a question, without the question history.
+    /// Returns None if no records found.
+    async fn get_user_by_email_hash(&self, email_hash: &str) -> Result<Option<User>>;
+
+    /// Records a one-time token as used until it expires.
+    /// Returns false if the token was used before. Storage errors are returned as errors, so callers can reject the token.
+    async fn use_one_time_token(&self, token_id: &str, expires: DateTime<Utc>) -> Result<bool>;
+
+    /// Turns feedback notifications off or back on.
+    async fn update_feedback_opt_out(&self, email: &str, opt_out: bool) -> Result<Option<User>>;
+
+    /// Turns broken link reports off or back on.
+    async fn update_links_opt_out(&self, email: &str, opt_out: bool) -> Result<Option<User>>;
+
+    /// Sets the time of the last feedback digest, but only if it is still `previous`.
+    /// Returns false if another request updated it first, so that only one of them sends the digest.
+    async fn update_feedback_notified(
+        &self,
+        email: &str,
+        notified: DateTime<Utc>,
+        previous: Option<DateTime<Utc>>,
+    ) -> Result<bool>;
+}
+
+/// Read and write access to the feedback table.
+#[async_trait]
+pub trait FeedbackRepository: Send + Sync {
+    /// Saves a new feedback ticket.
+    async fn save_feedback(&self, feedback: &Feedback) -> Result<()>;
+
+    /// Returns all feedback for the question, most recent first.
+    async fn get_feedback_for_question(&self, topic: &str, qid: &str) -> Result<Vec<Feedback>>;
+
+    /// Returns all feedback with the given status across all questions, most recent first.
+    async fn get_feedback_by_status(&self, status: FeedbackStatus) -> Result<Vec<Feedback>>;
+
+    /// Marks the feedback as resolved with an optional reply from the moderator.
+    /// Returns `RepositoryError::FeedbackNotFound` if there is no such feedback.
+    async fn resolve_feedback(&self, qid: &str, fid: &str, reply: Option<String>) -> Result<Feedback>;
+}
+
+/// Read and write access to the results of refresher link checks.
+#[async_trait]
+pub trait LinkRepository: Send + Sync {
+    /// Returns the result of the last check of the normalised URL.
+    /// Returns None if the link was never checked.
+    async fn get_link(&self, url: &str) -> Result<Option<LinkStatus>>;
+
+    /// Returns the results for the normalised URLs that were checked, in no particular order.
+    /// Used to add cached page titles to the refresher links.
+    async fn get_links(&self, urls: &[String]) -> Result<Vec<LinkStatus>>;
+
+    /// Saves the result of a link check, replacing the previous one.
+    async fn save_link(&self, link: &LinkStatus) -> Result<()>;
+}
+
+/// Generates a new unsubscribe token as a lower-case base58 encoded UUID.
+fn new_unsubscribe_token() -> String {
+    bs58::encode(uuid::Uuid::new_v4().as_bytes())
+        .into_string()
+        .to_lowercase()
+}
@@ -0,0 +1,289 @@
+//! Typed access to the parts of a Lambda function URL request and a minimal router.
+//!
+//! All extractors return an `ApiError`, so handlers can return `e.into_response()` as is.
+
+use crate::error::ApiError;
+use aws_lambda_events::{http::method::Method, lambda_function_urls::LambdaFunctionUrlRequest};
+use bitie_types::{ddb::fields, jwt::JwtUser, question::Question, topic::Topic};
+use serde::de::DeserializeOwned;
+use tracing::info;
+
+/// A path that matches any request path in the router.
+pub const ANY_PATH: &str = "*";
+
+/// Typed extractors for `LambdaFunctionUrlRequest`.
+pub trait RequestExt {
+    /// The HTTP method of the request.
+    fn method(&self) -> Result<Method, ApiError>;
+
+    /// The raw path of the request, e.g. `/q`, or an empty string if there is none.
+    fn path(&self) -> &str;
+
+    /// A trimmed query string param. Empty values are treated as missing.
+    fn query_param(&self, name: &str) -> Option<&str>;
+
+    /// An optional lower-case topic from the query string.
+    /// Returns an error if the topic is present, but is not in the list of valid topics.
+    fn topic(&self) -> Result<Option<String>, ApiError>;
+
+    /// Same as `topic()`, but the topic must be present.
+    fn required_topic(&self) -> Result<String, ApiError>;
+
+    /// An optional question ID from the query string. The ID is case sensitive.
+    /// Returns an error if the ID is present, but is not a valid base58 encoded UUID.
+    fn qid(&self) -> Result<Option<String>, ApiError>;
+
+    /// Same as `qid()`, but the ID must be present.
+    fn required_qid(&self) -> Result<String, ApiError>;
+
+    /// The body of the request as text. The body must be present and not blank.
+    fn text_body(&self) -> Result<&str, ApiError>;
+
+    /// The body of the request deserialized from JSON.
+    fn json_body<T: DeserializeOwned>(&self) -> Result<T, ApiError>;
+
+    /// The user from the JWT token, if the token is present and valid.
+    fn jwt_user(&self) -> Option<JwtUser>;
+
+    /// Same as `jwt_user()`, but returns 401 if there is no valid token.
+    fn required_jwt_user(&self) -> Result<JwtUser, ApiError>;
+}
+
+impl RequestExt for LambdaFunctionUrlRequest {
+    fn method(&self) -> Result<Method, ApiError> {
+        match &self.request_context.http.method {
+            Some(v) => {
+                Method::from_bytes(v.as_bytes()).map_err(|_| ApiError::Validation("Invalid HTTP method".to_string()))
+            }
+            None => Err(ApiError::Validation("Missing HTTP method. It's a bug.".to_string())),
+        }
+    }
+
+    fn path(&self) -> &str {
+        self.raw_path.as_deref().unwrap_or_default()
+    }
+
+    fn query_param(&self, name: &str) -> Option<&str> {
+        self.query_string_parameters
+            .get(name)
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty())
+    }
+
+    fn topic(&self) -> Result<Option<String>, ApiError> {
+        match self.query_param(fields::TOPIC) {
+            Some(v) => {
+                let v = v.to_ascii_lowercase();
+                if Topic::TOPICS.contains(&v.as_str()) {
+                    Ok(Some(v))
+                } else {
+                    Err(ApiError::Validation("Invalid topic".to_string()))
+                }
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn required_topic(&self) -> Result<String, ApiError> {
+        self.topic()?
+            .ok_or_else(|| ApiError::Validation("Missing topic in the query string".to_string()))
+    }
+
+    fn qid(&self) -> Result<Option<String>, ApiError> {
+        match self.query_param(fields::QID) {
+            Some(v) if Question::validate_qid(v) => Ok(Some(v.to_string())),
+            Some(_) => Err(ApiError::Validation("Invalid qid".to_string())),
+            None => Ok(None),
+        }
+    }
+
+    fn required_qid(&self) -> Result<String, ApiError> {
+        self.qid()?
+            .ok_or_else(|| ApiError::Validation("Missing qid in the query string".to_string()))
+    }
+
+    fn text_body(&self) -> Result<&str, ApiError> {
+        match self.body.as_deref() {
+            Some(v) if !v.trim().is_empty() => Ok(v),
+            _ => Err(ApiError::Validation("Missing HTTP body".to_string())),
+        }
+    }
+
+    fn json_body<T: DeserializeOwned>(&self) -> Result<T, ApiError> {
+        serde_json::from_str::<T>(self.text_body()?).map_err(|e| {
+            info!("Failed to parse the body: {:?}", e);
+            ApiError::Validation("Failed to parse the body".to_string())
+        })
+    }
+
+    fn jwt_user(&self) -> Option<JwtUser> {
+        crate::get_email_from_token(&self.headers)
+    }
+
+    fn required_jwt_user(&self) -> Result<JwtUser, ApiError> {
+        self.jwt_user().ok_or(ApiError::Unauthorized)
+    }
+}
+
+/// Maps method + path pairs to handler-defined actions.
+///
+/// ```ignore
+/// let router = Router::new()
+///     .route(Method::GET, ANY_PATH, Action::Get)
+///     .route(Method::PUT, ANY_PATH, Action::Save);
+/// let action = match router.resolve(&request) {
+///     Ok(v) => v,
+///     Err(e) => return e.into_response(),
+/// };
+/// ```
+#[derive(Debug, Clone)]
+pub struct Router<T> {
+    routes: Vec<(Method, &'static str, T)>,
+}
+
+impl<T: Copy> Default for Router<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Copy> Router<T> {
+    pub fn new() -> Self {
+        Self { routes: Vec::new() }
+    }
+
+    /// Adds a route. The path must match exactly, ignoring the trailing `/`, or be `ANY_PATH`.
+    /// The first matching route wins.
+    pub fn route(mut self, method: Method, path: &'static str, action: T) -> Self {
+        self.routes.push((method, path, action));
+        self
+    }
+
+    /// Returns the action for the request method and path.
+    /// Returns 404 if no route matches the path and 405 if the path matches, but the method does not.
+    pub fn resolve(&self, request: &LambdaFunctionUrlRequest) -> Result<T, ApiError> {
+        let method = request.method()?;
+        let path = request.path();
+        info!("{method} {path}");
+
+        let trimmed_path = path.trim_end_matches('/');
+        let mut path_matched = false;
+        for (route_method, route_path, action) in &self.routes {
+            if *route_path != ANY_PATH && route_path.trim_end_matches('/') != trimmed_path {
+                continue;
+            }
+            if *route_method == method {
+                return Ok(*action);
+            }
+            path_matched = true;
+        }
+
+        if path_matched {
+            Err(ApiError::MethodNotAllowed)
+        } else {
+            Err(ApiError::NotFound("Unknown path".to_string()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::request;
+    use serde::Deserialize;
+
+    const QID: &str = "89yZBXJBa9t2LB6xfj46Rm";
+
+    #[test]
+    fn test_method() {
+        assert_eq!(request("GET", "/", &[], None).method(), Ok(Method::GET));
+        assert_eq!(request("G E T", "/", &[], None).method().unwrap_err().status(), 400);
+    }
+
+    #[test]
+    fn test_topic() {
+        let r = request("GET", "/", &[("topic", " AWS ")], None);
+        assert_eq!(r.topic(), Ok(Some("aws".to_string())));
+        assert_eq!(r.required_topic(), Ok("aws".to_string()));
+
+        let r = request("GET", "/", &[("topic", "")], None);
+        assert_eq!(r.topic(), Ok(None));
+        assert_eq!(r.required_topic().unwrap_err().status(), 400);
+
+        let r = request("GET", "/", &[("topic", "unknown")], None);
+        assert_eq!(r.topic().unwrap_err().message(), "Invalid topic");
+    }
+
+    #[test]
+    fn test_qid() {
+        let r = request("GET", "/", &[("qid", QID)], None);
+        assert_eq!(r.required_qid(), Ok(QID.to_string()));
+
+        let r = request("GET", "/", &[], None);
+        assert_eq!(r.qid(), Ok(None));
+        assert_eq!(
+            r.required_qid().unwrap_err().message(),
+            "Missing qid in the query string"
+        );
+
+        let r = request("GET", "/", &[("qid", "abc")], None);
+        assert_eq!(r.qid().unwrap_err().message(), "Invalid qid");
+    }
+
+    #[test]
+    fn test_body() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Body {
+            a: u32,
+        }
+
+        let r = request("POST", "/", &[], Some(r#"{"a":1}"#));
+        assert_eq!(r.json_body::<Body>(), Ok(Body { a: 1 }));
+
+        let r = request("POST", "/", &[], Some("not json"));
+        assert_eq!(r.text_body(), Ok("not json"));
+        assert_eq!(r.json_body::<Body>().unwrap_err().status(), 400);
+
+        let r = request("POST", "/", &[], Some("  "));
+        assert_eq!(r.text_body().unwrap_err().message(), "Missing HTTP body");
+    }
+
+    #[test]
+    fn test_jwt_user() {
+        let r = request("GET", "/", &[], None);
+        assert!(r.jwt_user().is_none());
+        assert_eq!(r.required_jwt_user().unwrap_err().status(), 401);
+    }
+
+    #[test]
+    fn test_router() {
+        #[derive(Debug, Clone, Copy, PartialEq)]
+        enum Action {
+            Get,
+            Save,
+            List,
+        }
+
+        let router = Router::new()
+            .route(Method::GET, "/q", Action::Get)
+            .route(Method::PUT, "/q/", Action::Save)
+            .route(Method::GET, ANY_PATH, Action::List);
+
+        assert_eq!(router.resolve(&request("GET", "/q", &[], None)), Ok(Action::Get));
+        assert_eq!(router.resolve(&request("PUT", "/q", &[], None)), Ok(Action::Save));
+        assert_eq!(router.resolve(&request("GET", "/ql", &[], None)), Ok(Action::List));
+        assert_eq!(
+            router
+                .resolve(&request("DELETE", "/q", &[], None))
+                .unwrap_err()
+                .status(),
+            405
+        );
+
+        let router = Router::new().route(Method::GET, "/q", Action::Get);
+        assert_eq!(
+            router.resolve(&request("GET", "/u", &[], None)).unwrap_err().status(),
+            404
+        );
+    }
+}
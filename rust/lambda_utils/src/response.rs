@@ -0,0 +1,337 @@
+//! Response builder with caching headers, conditional requests and CORS.
+//!
+//! Handlers build the response with `ResponseBuilder` and `my_handler` passes it through `finalize`
+//! to add CORS headers and to replace the body with 304 if the client already has the same ETag.
+
+use crate::error::ApiError;
+use aws_lambda_events::{
+    http::{header, method::Method, HeaderMap, HeaderValue},
+    lambda_function_urls::{LambdaFunctionUrlRequest, LambdaFunctionUrlResponse},
+};
+use lambda_runtime::Error;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+
+/// A comma-separated list of origins allowed to call the API from the browser,
+/// e.g. `https://bitesized.info,http://localhost:5173`.
+/// No CORS headers are added if the var is not set.
+pub const CORS_ORIGINS_ENV_VAR: &str = "BITIE_CORS_ORIGINS";
+
+/// Request headers the front-end is allowed to send.
+const CORS_ALLOW_HEADERS: &str = "content-type, if-none-match, x-bitie-token";
+/// Methods used by the lambdas.
+const CORS_ALLOW_METHODS: &str = "GET, POST, PUT, OPTIONS";
+/// How long the browser can cache the preflight response, in seconds.
+const CORS_MAX_AGE: &str = "86400";
+
+/// Values for the `Cache-Control` header.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CacheControl {
+    /// Personalised or frequently changing responses.
+    NoStore,
+    /// Can be cached by the browser, but not by CloudFront.
+    Private { max_age: u32 },
+    /// Can be cached by the browser for `max_age` and by CloudFront for `s_maxage` seconds.
+    Public { max_age: u32, s_maxage: u32 },
+}
+
+impl CacheControl {
+    pub fn header_value(&self) -> String {
+        match self {
+            Self::NoStore => "no-store".to_string(),
+            Self::Private { max_age } => format!("private, max-age={max_age}"),
+            Self::Public { max_age, s_maxage } => format!("public, max-age={max_age}, s-maxage={s_maxage}"),
+        }
+    }
+}
+
+/// Builds a `LambdaFunctionUrlResponse` with optional caching headers.
+/// Use `text_response` and `json_response` for simple cases.
+#[derive(Debug)]
+pub struct ResponseBuilder {
+    status: i64,
+    headers: HeaderMap,
+    body: Option<String>,
+}
+
+impl ResponseBuilder {
+    /// A response with the given status and no body.
+    pub fn new(status: i64) -> Self {
+        Self {
+            status,
+            headers: HeaderMap::new(),
+            body: None,
+        }
+    }
+
+    /// Sets the body with `Content-Type=text/html`.
+    pub fn text(mut self, body: String) -> Self {
+        self.headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("text/html; charset=utf-8"),
+        );
+        self.body = Some(body);
+        self
+    }
+
+    /// Sets the body with the given `Content-Type`, e.g. `application/rss+xml; charset=utf-8`.
+    pub fn content(mut self, content_type: &'static str, body: String) -> Self {
+        self.headers
+            .insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+        self.body = Some(body);
+        self
+    }
+
+    /// Serializes the body with `Content-Type=application/json`.
+    pub fn json<T: Serialize>(mut self, body: &T) -> Result<Self, ApiError> {
+        let body = serde_json::to_string(body).map_err(|e| {
+            info!("Failed to serialize the response: {:?}", e);
+            ApiError::Internal("Failed to serialize the response".to_string())
+        })?;
+        self.headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json; charset=utf-8"),
+        );
+        self.body = Some(body);
+        Ok(self)
+    }
+
+    pub fn cache_control(mut self, cache_control: CacheControl) -> Self {
+        if let Ok(v) = HeaderValue::from_str(&cache_control.header_value()) {
+            self.headers.insert(header::CACHE_CONTROL, v);
+        }
+        self
+    }
+
+    /// Adds a request header name to `Vary`, e.g. the token header for responses that depend on the user.
+    pub fn vary(mut self, header_name: &'static str) -> Self {
+        self.headers.append(header::VARY, HeaderValue::from_static(header_name));
+        self
+    }
+
+    /// Sets `ETag` to a hash of the body. Does nothing if there is no body.
+    pub fn etag_from_body(mut self) -> Self {
+        if let Some(body) = &self.body {
+            if let Ok(v) = HeaderValue::from_str(&etag(body)) {
+                self.headers.insert(header::ETAG, v);
+            }
+        }
+        self
+    }
+
+    pub fn build(self) -> Result<LambdaFunctionUrlResponse, Error> {
+        Ok(LambdaFunctionUrlResponse {
+            status_code: self.status,
+            headers: self.headers,
+            cookies: Default::default(),
+            body: self.body,
+            is_base64_encoded: false,
+        })
+    }
+}
+
+/// Returns a strong ETag value for the content, e.g. `"1f2e..."`.
+pub fn etag(content: &str) -> String {
+    format!("\"{}\"", hex::encode(&Sha256::digest(content.as_bytes())[..16]))
+}
+
+/// Returns a response to a CORS preflight request.
+/// Returns None if the request is not a preflight.
+pub fn preflight(request: &LambdaFunctionUrlRequest) -> Option<LambdaFunctionUrlResponse> {
+    if request.request_context.http.method.as_deref() != Some(Method::OPTIONS.as_str()) {
+        return None;
+    }
+
+    let mut response = LambdaFunctionUrlResponse {
+        status_code: 204,
+        headers: HeaderMap::new(),
+        cookies: Default::default(),
+        body: None,
+        is_base64_encoded: false,
+    };
+    response.headers.insert(
+        header::ACCESS_CONTROL_ALLOW_METHODS,
+        HeaderValue::from_static(CORS_ALLOW_METHODS),
+    );
+    response.headers.insert(
+        header::ACCESS_CONTROL_ALLOW_HEADERS,
+        HeaderValue::from_static(CORS_ALLOW_HEADERS),
+    );
+    response
+        .headers
+        .insert(header::ACCESS_CONTROL_MAX_AGE, HeaderValue::from_static(CORS_MAX_AGE));
+
+    Some(finalize(&request.headers, response))
+}
+
+/// Adds CORS headers for allowed origins and converts the response into 304
+/// if it has an ETag matching `If-None-Match` of the request.
+pub fn finalize(request_headers: &HeaderMap, response: LambdaFunctionUrlResponse) -> LambdaFunctionUrlResponse {
+    let allow_list = std::env::var(CORS_ORIGINS_ENV_VAR).unwrap_or_default();
+    finalize_with_allow_list(request_headers, response, &allow_list)
+}
+
+fn finalize_with_allow_list(
+    request_headers: &HeaderMap,
+    mut response: LambdaFunctionUrlResponse,
+    allow_list: &str,
+) -> LambdaFunctionUrlResponse {
+    // CORS
+    if let Some(origin) = request_headers.get(header::ORIGIN) {
+        match origin.to_str() {
+            Ok(v) if is_allowed_origin(v, allow_list) => {
+                response
+                    .headers
+                    .insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin.clone());
+                response.headers.insert(
+                    header::ACCESS_CONTROL_EXPOSE_HEADERS,
+                    HeaderValue::from_static("etag, retry-after"),
+                );
+            }
+            _ => warn!("Origin not allowed: {:?}", origin),
+        }
+    }
+    // the response depends on the origin even if it was not allowed or not sent,
+    // otherwise a cached response without CORS headers can be served to an allowed origin
+    if allow_list.split(',').any(|v| !v.trim().is_empty()) {
+        response
+            .headers
+            .append(header::VARY, HeaderValue::from_static("origin"));
+    }
+
+    // conditional GET
+    if response.status_code == 200 {
+        let if_none_match = request_headers.get(header::IF_NONE_MATCH).and_then(|v| v.to_str().ok());
+        let etag = response.headers.get(header::ETAG).and_then(|v| v.to_str().ok());
+        if let (Some(if_none_match), Some(etag)) = (if_none_match, etag) {
+            if etag_matches(if_none_match, etag) {
+                info!("Not modified: {etag}");
+                response.status_code = 304;
+                response.body = None;
+                response.headers.remove(header::CONTENT_TYPE);
+            }
+        }
+    }
+
+    response
+}
+
+/// Returns true if the origin is in the comma-separated allow-list.
+fn is_allowed_origin(origin: &str, allow_list: &str) -> bool {
+    allow_list
+        .split(',')
+        .map(|v| v.trim().trim_end_matches('/'))
+        .any(|v| !v.is_empty() && v.eq_ignore_ascii_case(origin))
+}
+
+/// Returns true if `If-None-Match` contains the ETag or `*`.
+/// Weak validators are compared as strong ones, which is what the spec requires for `If-None-Match`.
+fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    let etag = etag.trim_start_matches("W/");
+    if_none_match
+        .split(',')
+        .map(|v| v.trim())
+        .any(|v| v == "*" || v.trim_start_matches("W/") == etag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALLOW_LIST: &str = "https://bitesized.info, http://localhost:5173/";
+
+    fn headers(pairs: &[(header::HeaderName, &'static str)]) -> HeaderMap {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.clone(), HeaderValue::from_static(v)))
+            .collect()
+    }
+
+    #[test]
+    fn test_builder() {
+        let response = ResponseBuilder::new(200)
+            .json(&vec![1, 2])
+            .unwrap()
+            .cache_control(CacheControl::Public {
+                max_age: 60,
+                s_maxage: 300,
+            })
+            .vary(crate::X_BITIE_TOKEN_HEADER)
+            .etag_from_body()
+            .build()
+            .unwrap();
+
+        assert_eq!(response.body.as_deref(), Some("[1,2]"));
+        assert_eq!(
+            response.headers.get(header::CACHE_CONTROL).unwrap(),
+            "public, max-age=60, s-maxage=300"
+        );
+        assert_eq!(response.headers.get(header::VARY).unwrap(), "x-bitie-token");
+        assert_eq!(response.headers.get(header::ETAG).unwrap(), etag("[1,2]").as_str());
+        assert_eq!(etag("[1,2]").len(), 34);
+    }
+
+    #[test]
+    fn test_not_modified() {
+        let response = || {
+            ResponseBuilder::new(200)
+                .text("abc".to_string())
+                .etag_from_body()
+                .build()
+                .unwrap()
+        };
+        let tag = etag("abc");
+
+        let request_headers: HeaderMap = [(header::IF_NONE_MATCH, HeaderValue::from_str(&tag).unwrap())]
+            .into_iter()
+            .collect();
+        let finalized = finalize_with_allow_list(&request_headers, response(), "");
+        assert_eq!(finalized.status_code, 304);
+        assert!(finalized.body.is_none());
+
+        let request_headers = headers(&[(header::IF_NONE_MATCH, "\"other\"")]);
+        let finalized = finalize_with_allow_list(&request_headers, response(), "");
+        assert_eq!(finalized.status_code, 200);
+        assert_eq!(finalized.body.as_deref(), Some("abc"));
+
+        assert!(etag_matches("*", "\"a\""));
+        assert!(etag_matches("\"b\", W/\"a\"", "\"a\""));
+        assert!(!etag_matches("\"b\"", "\"a\""));
+    }
+
+    #[test]
+    fn test_cors() {
+        let response = || ResponseBuilder::new(204).build().unwrap();
+
+        let finalized = finalize_with_allow_list(
+            &headers(&[(header::ORIGIN, "http://localhost:5173")]),
+            response(),
+            ALLOW_LIST,
+        );
+        assert_eq!(
+            finalized.headers.get(header::ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(),
+            "http://localhost:5173"
+        );
+        assert_eq!(finalized.headers.get(header::VARY).unwrap(), "origin");
+
+        let finalized = finalize_with_allow_list(
+            &headers(&[(header::ORIGIN, "https://example.com")]),
+            response(),
+            ALLOW_LIST,
+        );
+        assert!(finalized.headers.get(header::ACCESS_CONTROL_ALLOW_ORIGIN).is_none());
+        assert_eq!(finalized.headers.get(header::VARY).unwrap(), "origin");
+
+        // requests without Origin get the same Vary for the caches
+        let finalized = finalize_with_allow_list(&HeaderMap::new(), response(), ALLOW_LIST);
+        assert!(finalized.headers.get(header::ACCESS_CONTROL_ALLOW_ORIGIN).is_none());
+        assert_eq!(finalized.headers.get(header::VARY).unwrap(), "origin");
+
+        // no CORS, no Vary
+        let finalized = finalize_with_allow_list(&HeaderMap::new(), response(), "");
+        assert!(finalized.headers.is_empty());
+
+        assert!(!is_allowed_origin("https://bitesized.info", ""));
+    }
+}
@@ -0,0 +1,32 @@
+//! Fixtures shared by the lambda unit tests.
+//! Enabled with the `test-utils` feature in `[dev-dependencies]`.
+
+use aws_lambda_events::lambda_function_urls::LambdaFunctionUrlRequest;
+use bitie_types::question::Question;
+use std::str::FromStr;
+
+/// Builds a minimal Function URL request with the given method, path, query string and body.
+/// Set `headers` or `request_context.http.source_ip` on the result if the test needs them.
+pub fn request(method: &str, path: &str, query: &[(&str, &str)], body: Option<&str>) -> LambdaFunctionUrlRequest {
+    let query = query
+        .iter()
+        .map(|(k, v)| (k.to_string(), serde_json::Value::String(v.to_string())))
+        .collect::<serde_json::Map<_, _>>();
+    serde_json::from_value(serde_json::json!({
+        "rawPath": path,
+        "headers": {},
+        "queryStringParameters": query,
+        "requestContext": { "timeEpoch": 0, "http": { "method": method } },
+        "body": body,
+        "isBase64Encoded": false,
+    }))
+    .unwrap()
+}
+
+/// A draft question "What is 1+1?" with two answers, the second one correct.
+pub fn question(topic: &str, qid: &str) -> Question {
+    Question::from_str(&format!(
+        r#"{{"qid":"{qid}","topic":"{topic}","question":"What is 1+1?","answers":[{{"a":"1","e":null}},{{"a":"2","e":null,"c":true}}],"title":"Simple question","updated":null}}"#
+    ))
+    .unwrap()
+}
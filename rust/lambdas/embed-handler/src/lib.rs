@@ -0,0 +1,342 @@
+use aws_lambda_events::{
+    http::{header, method::Method},
+    lambda_function_urls::{LambdaFunctionUrlRequest, LambdaFunctionUrlResponse},
+};
+use bitie_types::{
+    embed::host_from_url,
+    question::{PublishStage, Question},
+    topic::Topic,
+    user::AnswerStatus,
+};
+use chrono::Utc;
+use lambda_runtime::{Error, LambdaEvent};
+use lambda_utils::{
+    error::ApiError,
+    rate_limit::{self, DdbRateLimiter, RateLimit, RateLimiter},
+    repository::{DdbRepository, QuestionRepository},
+    request::{RequestExt, Router},
+    response::{self, CacheControl, ResponseBuilder},
+};
+use tracing::{error, info};
+use widget::{OEmbed, SITE_HOST};
+
+mod widget;
+
+/// oEmbed query string params, see https://oembed.com/#section2.2
+const URL_PARAM: &str = "url";
+const FORMAT_PARAM: &str = "format";
+const MAX_WIDTH_PARAM: &str = "maxwidth";
+const MAX_HEIGHT_PARAM: &str = "maxheight";
+
+/// The entry point for the lambda runtime and the local dev server.
+pub async fn my_handler(event: LambdaEvent<LambdaFunctionUrlRequest>) -> Result<LambdaFunctionUrlResponse, Error> {
+    if let Some(v) = response::preflight(&event.payload) {
+        return Ok(v);
+    }
+
+    let request_headers = event.payload.headers.clone();
+    let repo = DdbRepository::from_env().await;
+    let limiter = DdbRateLimiter::from_env().await;
+    let response = handle_request(event.payload, &repo, &limiter).await?;
+    Ok(response::finalize(&request_headers, response))
+}
+
+/// The actions supported by this handler.
+#[derive(Debug, Clone, Copy)]
+enum Action {
+    /// Returns the widget page for an iframe and counts a view for the embedding site.
+    Widget,
+    /// Returns oEmbed JSON for a question URL.
+    OEmbed,
+}
+
+/// Processes the request against the given storage and rate limiter.
+/// It is separate from `my_handler` to be testable without DDB.
+async fn handle_request<R: QuestionRepository, L: RateLimiter>(
+    request: LambdaFunctionUrlRequest,
+    repo: &R,
+    limiter: &L,
+) -> Result<LambdaFunctionUrlResponse, Error> {
+    let router =
+        Router::new()
+            .route(Method::GET, "/embed", Action::Widget)
+            .route(Method::GET, "/oembed", Action::OEmbed);
+    let action = match router.resolve(&request) {
+        Ok(v) => v,
+        Err(e) => return e.into_response(),
+    };
+
+    match action {
+        Action::Widget => {
+            let (topic, qid) = match (request.required_topic(), request.required_qid()) {
+                (Ok(topic), Ok(qid)) => (topic, qid),
+                (Err(e), _) | (_, Err(e)) => return e.into_response(),
+            };
+
+            let question = match get_published_question(repo, &topic, &qid).await {
+                Ok(v) => v,
+                Err(e) => return e.into_response(),
+            };
+
+            // browsers send the URL of the page with the iframe as the referrer, usually only the origin
+            let host = request
+                .headers
+                .get(header::REFERER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(host_from_url)
+                .filter(|v| v != SITE_HOST);
+            info!("Embedded on {:?}", host);
+
+            // the referrer can be faked, so the views are limited per IP, but the widget is still shown
+            if let Some(host) = &host {
+                let rate_limit_keys = request
+                    .request_context
+                    .http
+                    .source_ip
+                    .iter()
+                    .map(|v| format!("embed/ip/{v}"))
+                    .collect::<Vec<String>>();
+                match rate_limit::enforce(limiter, &rate_limit_keys, &RateLimit::EMBED_STATS).await {
+                    Ok(()) => {
+                        if let Err(e) = repo
+                            .increment_embed_stats(&topic, &qid, host, &AnswerStatus::Asked(Utc::now()))
+                            .await
+                        {
+                            error!("Failed to update embed stats: {:?}", e);
+                        }
+                    }
+                    Err(_) => info!("Embed view not counted"),
+                }
+            }
+
+            // the page depends on the referrer and every view is counted
+            ResponseBuilder::new(200)
+                .text(widget::render_widget(&question, host.as_deref()))
+                .cache_control(CacheControl::NoStore)
+                .build()
+        }
+
+        Action::OEmbed => {
+            if let Some(v) = request.query_param(FORMAT_PARAM) {
+                if v != "json" {
+                    return ApiError::Validation("Only JSON format is supported".to_string()).into_response();
+                }
+            }
+
+            let (topic, qid) = match request.query_param(URL_PARAM).and_then(parse_question_url) {
+                Some(v) => v,
+                None => return ApiError::Validation("Invalid question URL".to_string()).into_response(),
+            };
+
+            let question = match get_published_question(repo, &topic, &qid).await {
+                Ok(v) => v,
+                Err(e) => return e.into_response(),
+            };
+
+            let max_width = request.query_param(MAX_WIDTH_PARAM).and_then(|v| v.parse::<u32>().ok());
+            let max_height = request
+                .query_param(MAX_HEIGHT_PARAM)
+                .and_then(|v| v.parse::<u32>().ok());
+
+            match ResponseBuilder::new(200).json(&OEmbed::for_question(&question, max_width, max_height)) {
+                Ok(v) => v
+                    .cache_control(CacheControl::Public {
+                        max_age: 3600,
+                        s_maxage: 3600,
+                    })
+                    .etag_from_body()
+                    .build(),
+                Err(e) => e.into_response(),
+            }
+        }
+    }
+}
+
+/// Returns the question if it is published. Drafts cannot be embedded.
+async fn get_published_question<R: QuestionRepository>(repo: &R, topic: &str, qid: &str) -> Result<Question, ApiError> {
+    match repo.get_question(topic, qid).await? {
+        Some(v) if v.stage == PublishStage::Published => Ok(v),
+        Some(_) => {
+            info!("Question {topic}/{qid} is not published");
+            Err(ApiError::NotFound("No question found".to_string()))
+        }
+        None => Err(ApiError::NotFound("No question found".to_string())),
+    }
+}
+
+/// Returns the topic and qid from a question URL,
+/// e.g. `https://bitesized.info/question?topic=aws&qid=89yZBXJBa9t2LB6xfj46Rm`.
+/// Returns None if the URL is not for a question on this site or the params are invalid.
+fn parse_question_url(url: &str) -> Option<(String, String)> {
+    if host_from_url(url)?.trim_start_matches("www.") != SITE_HOST {
+        return None;
+    }
+
+    let (path, query) = url.split_once('?')?;
+    if !path.trim_end_matches('/').ends_with("/question") {
+        return None;
+    }
+
+    let (mut topic, mut qid) = (None, None);
+    for (name, value) in query.split('#').next()?.split('&').filter_map(|v| v.split_once('=')) {
+        match name {
+            "topic" => topic = Some(value.to_ascii_lowercase()),
+            "qid" => qid = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    match (topic, qid) {
+        (Some(topic), Some(qid)) if Topic::TOPICS.contains(&topic.as_str()) && Question::validate_qid(&qid) => {
+            Some((topic, qid))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitie_types::embed::EmbedStats;
+    use lambda_utils::{rate_limit::MemoryRateLimiter, repository::MemoryRepository, test_utils};
+
+    const QID: &str = "89yZBXJBa9t2LB6xfj46Rm";
+    const DRAFT_QID: &str = "NgGdoZov4T6jV46ty4JUX6";
+
+    fn request(path: &str, query: &[(&str, &str)], referer: Option<&str>) -> LambdaFunctionUrlRequest {
+        let mut request = test_utils::request("GET", path, query, None);
+        request.request_context.http.source_ip = Some("1.2.3.4".to_string());
+        if let Some(referer) = referer {
+            request.headers.insert("referer", referer.parse().unwrap());
+        }
+        request
+    }
+
+    fn question(qid: &str, stage: PublishStage) -> Question {
+        test_utils::question("aws", qid).with_stage(stage)
+    }
+
+    fn repo() -> MemoryRepository {
+        MemoryRepository::with_questions(vec![
+            question(QID, PublishStage::Published),
+            question(DRAFT_QID, PublishStage::Draft),
+        ])
+    }
+
+    #[tokio::test]
+    async fn test_widget() {
+        let repo = repo();
+        let query = [("topic", "aws"), ("qid", QID)];
+
+        let response = handle_request(
+            request("/embed", &query, Some("https://Example.com/blog/post")),
+            &repo,
+            &MemoryRateLimiter::new(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status_code, 200);
+        assert_eq!(response.headers.get(header::CACHE_CONTROL).unwrap(), "no-store");
+        assert!(response.body.unwrap().contains(r#"data-embed="example.com""#));
+
+        // views on the site itself or without a referrer are not counted
+        for referer in [Some("https://bitesized.info/question"), None] {
+            let response = handle_request(request("/embed", &query, referer), &repo, &MemoryRateLimiter::new())
+                .await
+                .unwrap();
+            assert_eq!(response.status_code, 200);
+        }
+        assert_eq!(
+            repo.embed_stats(QID, "example.com"),
+            Some(EmbedStats {
+                views: 1,
+                ..Default::default()
+            })
+        );
+        assert!(repo.embed_stats(QID, SITE_HOST).is_none());
+
+        // views from the same IP are limited, but the widget is still shown
+        let limiter = MemoryRateLimiter::new();
+        for _ in 0..RateLimit::EMBED_STATS.capacity + 5 {
+            let response = handle_request(request("/embed", &query, Some("https://other.com/")), &repo, &limiter)
+                .await
+                .unwrap();
+            assert_eq!(response.status_code, 200);
+        }
+        assert_eq!(
+            repo.embed_stats(QID, "other.com").unwrap().views,
+            RateLimit::EMBED_STATS.capacity
+        );
+
+        // drafts cannot be embedded
+        let query = [("topic", "aws"), ("qid", DRAFT_QID)];
+        let response = handle_request(request("/embed", &query, None), &repo, &MemoryRateLimiter::new())
+            .await
+            .unwrap();
+        assert_eq!(response.status_code, 404);
+    }
+
+    #[tokio::test]
+    async fn test_oembed() {
+        let repo = repo();
+        let url = format!("https://bitesized.info/question?topic=aws&qid={QID}");
+
+        let response = handle_request(
+            request("/oembed", &[("url", &url), ("maxwidth", "300")], None),
+            &repo,
+            &MemoryRateLimiter::new(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status_code, 200);
+        let oembed = serde_json::from_str::<serde_json::Value>(&response.body.unwrap()).unwrap();
+        assert_eq!(oembed["type"], "rich");
+        assert_eq!(oembed["width"], 300);
+        assert!(oembed["html"].as_str().unwrap().contains("/embed?topic=aws&amp;qid="));
+
+        let response = handle_request(
+            request("/oembed", &[("url", &url), ("format", "xml")], None),
+            &repo,
+            &MemoryRateLimiter::new(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status_code, 400);
+
+        let url = format!("https://bitesized.info/question?topic=aws&qid={DRAFT_QID}");
+        let response = handle_request(
+            request("/oembed", &[("url", &url)], None),
+            &repo,
+            &MemoryRateLimiter::new(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status_code, 404);
+    }
+
+    #[test]
+    fn test_parse_question_url() {
+        let expected = Some(("aws".to_string(), QID.to_string()));
+        assert_eq!(
+            parse_question_url(&format!("https://bitesized.info/question?topic=aws&qid={QID}")),
+            expected
+        );
+        assert_eq!(
+            parse_question_url(&format!("https://www.bitesized.info/question/?qid={QID}&topic=AWS#top")),
+            expected
+        );
+        assert_eq!(
+            parse_question_url(&format!("https://example.com/question?topic=aws&qid={QID}")),
+            None
+        );
+        assert_eq!(
+            parse_question_url(&format!("https://bitesized.info/questions?topic=aws&qid={QID}")),
+            None
+        );
+        assert_eq!(
+            parse_question_url("https://bitesized.info/question?topic=aws&qid=123"),
+            None
+        );
+        assert_eq!(parse_question_url("https://bitesized.info/question"), None);
+    }
+}
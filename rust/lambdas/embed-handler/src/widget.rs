@@ -0,0 +1,207 @@
+//! The embeddable question widget and its oEmbed description.
+//!
+//! The widget is a self-contained HTML page meant to be loaded in an iframe on other sites.
+//! It is served from bitesized.info, so the answers are posted to the same-origin question API
+//! and the explanations are shown once the learner submits the answers.
+
+use bitie_types::{
+    embed::EMBED_PARAM,
+    markdown::escape_html,
+    question::{Question, QuestionFormat},
+    topic::Topic,
+};
+use serde::Serialize;
+
+/// The public URL of the site without the trailing `/`.
+pub(crate) const SITE_URL: &str = "https://bitesized.info";
+/// The host of the site, e.g. to ignore views of the widget on the site itself.
+pub(crate) const SITE_HOST: &str = "bitesized.info";
+
+/// The default size of the iframe in pixels.
+const DEFAULT_WIDTH: u32 = 600;
+const DEFAULT_HEIGHT: u32 = 480;
+
+/// oEmbed response for a question, see https://oembed.com/#section2.3
+#[derive(Serialize, Debug, PartialEq)]
+pub(crate) struct OEmbed {
+    pub version: &'static str,
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub title: String,
+    pub provider_name: &'static str,
+    pub provider_url: &'static str,
+    pub html: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl OEmbed {
+    /// Describes an iframe with the widget for the question.
+    /// The size is reduced to fit `max_width` and `max_height`, if any.
+    pub fn for_question(question: &Question, max_width: Option<u32>, max_height: Option<u32>) -> Self {
+        let width = max_width.map_or(DEFAULT_WIDTH, |v| v.min(DEFAULT_WIDTH));
+        let height = max_height.map_or(DEFAULT_HEIGHT, |v| v.min(DEFAULT_HEIGHT));
+        let title = escape_html(&question.title);
+
+        Self {
+            version: "1.0",
+            kind: "rich",
+            html: format!(
+                r#"<iframe src="{}" width="{width}" height="{height}" title="{title}" style="border:0" loading="lazy"></iframe>"#,
+                escape_html(&widget_url(&question.topic, &question.qid))
+            ),
+            title: question.title.clone(),
+            provider_name: "Bite-sized learning",
+            provider_url: SITE_URL,
+            width,
+            height,
+        }
+    }
+}
+
+/// Returns the URL of the widget page for the question.
+pub(crate) fn widget_url(topic: &str, qid: &str) -> String {
+    format!("{SITE_URL}/embed?topic={topic}&qid={qid}")
+}
+
+/// Renders the widget page for the question.
+/// `host` is the host of the embedding site. It is sent back with the answers to count them per site.
+pub(crate) fn render_widget(question: &Question, host: Option<&str>) -> String {
+    let question_url = format!("{SITE_URL}/question?topic={}&qid={}", question.topic, question.qid);
+    let question = question.clone().format(QuestionFormat::HtmlShort);
+
+    // a single correct answer is a choice of one
+    let (input_type, hint) = match question.correct {
+        1 => ("radio", "Select one answer".to_string()),
+        v => ("checkbox", format!("Select {v} answers")),
+    };
+
+    let answers = question
+        .answers
+        .iter()
+        .enumerate()
+        .map(|(i, v)| {
+            format!(
+                r#"<li><label><input type="{input_type}" name="answer" value="{i}">{}</label></li>"#,
+                v.text()
+            )
+        })
+        .collect::<String>();
+
+    let title = escape_html(&question.title);
+    let topic_name = escape_html(Topic::into_name(&question.topic));
+    let embed = escape_html(host.unwrap_or_default());
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<meta name="robots" content="noindex">
+<title>{title} | Bite-sized learning</title>
+<base target="_blank">
+<style>
+body {{ margin: 0; padding: 1rem; font-family: system-ui, sans-serif; font-size: 16px; color: #0f172a; }}
+h1 {{ font-size: 1.2rem; margin: 0 0 .5rem; }}
+ol {{ list-style: none; padding: 0; }}
+li {{ margin: .5rem 0; padding: .5rem; border: 1px solid #cbd5e1; border-radius: 4px; }}
+li p {{ display: inline; margin: 0; }}
+li.correct {{ border-color: #22c55e; background: #f0fdf4; }}
+li.incorrect.selected {{ border-color: #ef4444; background: #fef2f2; }}
+.explanation {{ margin-top: .5rem; font-size: .9rem; }}
+.hint, .footer, #bitie-status {{ font-size: .85rem; color: #475569; }}
+pre {{ overflow-x: auto; }}
+</style>
+</head>
+<body>
+<form id="bitie-form" data-topic="{topic}" data-qid="{qid}" data-{EMBED_PARAM}="{embed}">
+<h1>{title}</h1>
+{question_html}
+<p class="hint">{hint}</p>
+<ol id="bitie-answers">{answers}</ol>
+<button type="submit">Submit</button>
+<p id="bitie-status" role="status"></p>
+<p class="footer"><a href="{question_url}">{topic_name} question at bitesized.info</a></p>
+</form>
+<script>
+(() => {{
+  const form = document.getElementById("bitie-form");
+  const status = document.getElementById("bitie-status");
+  form.addEventListener("submit", async (event) => {{
+    event.preventDefault();
+    const answers = [...form.querySelectorAll("input:checked")].map((v) => v.value).join(",");
+    const params = new URLSearchParams({{ topic: form.dataset.topic, qid: form.dataset.qid, answers }});
+    if (form.dataset.{EMBED_PARAM}) params.set("{EMBED_PARAM}", form.dataset.{EMBED_PARAM});
+    try {{
+      const response = await fetch("/q?" + params.toString());
+      if (!response.ok) throw new Error(response.status);
+      const question = await response.json();
+      document.getElementById("bitie-answers").innerHTML = question.answers
+        .map((v) => `<li class="${{v.c ? "correct" : "incorrect"}}${{v.sel ? " selected" : ""}}">${{v.a}}${{v.e ? `<div class="explanation">${{v.e}}</div>` : ""}}</li>`)
+        .join("");
+      form.querySelector("button").remove();
+      status.textContent = "";
+    }} catch (e) {{
+      status.textContent = "Something went wrong. Try again later.";
+    }}
+  }});
+}})();
+</script>
+</body>
+</html>
+"#,
+        topic = escape_html(&question.topic),
+        qid = escape_html(&question.qid),
+        question_url = escape_html(&question_url),
+        question_html = question.question,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn question() -> Question {
+        Question::from_str(
+            r#"{"qid":"89yZBXJBa9t2LB6xfj46Rm","topic":"aws","question":"What is **1+1**?","answers":[{"a":"1","e":"One"},{"a":"2","e":"Two","c":true}],"title":"Simple & easy","updated":null}"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_render_widget() {
+        let html = render_widget(&question(), Some("example.com"));
+
+        assert!(html.contains("<title>Simple &amp; easy | Bite-sized learning</title>"));
+        assert!(html.contains(r#"data-topic="aws" data-qid="89yZBXJBa9t2LB6xfj46Rm" data-embed="example.com""#));
+        assert!(html.contains("<strong>1+1</strong>"));
+        assert!(html.contains(r#"<input type="radio" name="answer" value="1"><p>2</p>"#));
+        assert!(html.contains("Select one answer"));
+        // explanations and correct answers are only returned by the question API
+        assert!(!html.contains("Two"));
+        assert!(html.contains("params.set(\"embed\", form.dataset.embed)"));
+
+        let html = render_widget(&question(), None);
+        assert!(html.contains(r#"data-embed="""#));
+    }
+
+    #[test]
+    fn test_oembed() {
+        let oembed = OEmbed::for_question(&question(), Some(400), None);
+        assert_eq!(oembed.width, 400);
+        assert_eq!(oembed.height, DEFAULT_HEIGHT);
+        assert_eq!(
+            oembed.html,
+            r#"<iframe src="https://bitesized.info/embed?topic=aws&amp;qid=89yZBXJBa9t2LB6xfj46Rm" width="400" height="480" title="Simple &amp; easy" style="border:0" loading="lazy"></iframe>"#
+        );
+
+        let json = serde_json::to_value(&oembed).unwrap();
+        assert_eq!(json["type"], "rich");
+        assert_eq!(json["title"], "Simple & easy");
+
+        let oembed = OEmbed::for_question(&question(), Some(2000), Some(2000));
+        assert_eq!((oembed.width, oembed.height), (DEFAULT_WIDTH, DEFAULT_HEIGHT));
+    }
+}
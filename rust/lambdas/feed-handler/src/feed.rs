@@ -0,0 +1,184 @@
+//! Renders the sitemap and the topic feeds as XML.
+//!
+//! The questions come from the topic index, most recently updated first.
+//! Feed items need the question text for the excerpt, so they are full questions.
+
+use bitie_types::{markdown::md_to_plain_text, question::Question, topic::Topic};
+use chrono::{DateTime, Utc};
+
+/// The public URL of the site without the trailing `/`.
+const SITE_URL: &str = "https://bitesized.info";
+
+/// The max length of the excerpt in feed items in characters.
+const MAX_EXCERPT_LENGTH: usize = 300;
+
+/// Returns the URL of the question page.
+fn question_url(question: &Question) -> String {
+    format!("{SITE_URL}/question?topic={}&qid={}", question.topic, question.qid)
+}
+
+/// Returns the URL of the list of questions for the topic.
+fn topic_url(topic: &str) -> String {
+    format!("{SITE_URL}/questions?topic={topic}")
+}
+
+/// Returns the most recent `updated` date of the questions, if any.
+fn last_updated(questions: &[Question]) -> Option<DateTime<Utc>> {
+    questions.iter().filter_map(|v| v.updated).max()
+}
+
+/// Renders `sitemap.xml` with the topic pages and all published questions.
+/// `topics` is a list of topics with their published questions.
+pub(crate) fn render_sitemap(topics: &[(&str, Vec<Question>)]) -> String {
+    let mut urls = vec![sitemap_url(&format!("{SITE_URL}/"), None)];
+
+    for (topic, questions) in topics {
+        urls.push(sitemap_url(&topic_url(topic), last_updated(questions)));
+        urls.extend(questions.iter().map(|v| sitemap_url(&question_url(v), v.updated)));
+    }
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n{}</urlset>\n",
+        urls.concat()
+    )
+}
+
+/// A single `<url>` element of the sitemap.
+fn sitemap_url(loc: &str, lastmod: Option<DateTime<Utc>>) -> String {
+    let lastmod = match lastmod {
+        Some(v) => format!("<lastmod>{}</lastmod>", v.format("%Y-%m-%d")),
+        None => String::new(),
+    };
+    format!("<url><loc>{}</loc>{lastmod}</url>\n", escape_xml(loc))
+}
+
+/// Renders an RSS 2.0 feed of the questions in the topic.
+pub(crate) fn render_rss(topic: &str, questions: &[Question]) -> String {
+    let topic_name = escape_xml(Topic::into_name(topic));
+    let link = escape_xml(&topic_url(topic));
+    let last_build_date = match last_updated(questions) {
+        Some(v) => format!("<lastBuildDate>{}</lastBuildDate>\n", v.to_rfc2822()),
+        None => String::new(),
+    };
+
+    let items = questions
+        .iter()
+        .map(|v| {
+            let url = escape_xml(&question_url(v));
+            let pub_date = match v.updated {
+                Some(v) => format!("<pubDate>{}</pubDate>", v.to_rfc2822()),
+                None => String::new(),
+            };
+            format!(
+                "<item><title>{}</title><link>{url}</link><guid isPermaLink=\"true\">{url}</guid>{pub_date}<description>{}</description></item>\n",
+                escape_xml(&v.title),
+                escape_xml(&md_to_plain_text(&v.question, MAX_EXCERPT_LENGTH)),
+            )
+        })
+        .collect::<String>();
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<rss version=\"2.0\">\n<channel>\n<title>{topic_name} questions | Bite-sized learning</title>\n<link>{link}</link>\n<description>New {topic_name} questions at bitesized.info</description>\n{last_build_date}{items}</channel>\n</rss>\n"
+    )
+}
+
+/// Renders an Atom feed of the questions in the topic.
+pub(crate) fn render_atom(topic: &str, questions: &[Question]) -> String {
+    let topic_name = escape_xml(Topic::into_name(topic));
+    let link = escape_xml(&topic_url(topic));
+    // Atom requires `updated`, so an empty feed uses the start of the epoch
+    let updated = last_updated(questions).unwrap_or_default().to_rfc3339();
+
+    let entries = questions
+        .iter()
+        .map(|v| {
+            let url = escape_xml(&question_url(v));
+            format!(
+                "<entry><title>{}</title><link href=\"{url}\"/><id>{url}</id><updated>{}</updated><summary>{}</summary></entry>\n",
+                escape_xml(&v.title),
+                v.updated.unwrap_or_default().to_rfc3339(),
+                escape_xml(&md_to_plain_text(&v.question, MAX_EXCERPT_LENGTH)),
+            )
+        })
+        .collect::<String>();
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<feed xmlns=\"http://www.w3.org/2005/Atom\">\n<title>{topic_name} questions | Bite-sized learning</title>\n<link href=\"{link}\"/>\n<id>{link}</id>\n<updated>{updated}</updated>\n<author><name>bitesized.info</name></author>\n{entries}</feed>\n"
+    )
+}
+
+/// Escapes the text for use inside XML elements and attributes.
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len() + text.len() / 8);
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn question(qid: &str, title: &str, updated: &str) -> Question {
+        Question::from_str(&format!(
+            r#"{{"qid":"{qid}","topic":"aws","question":"What is **1+1** & <b>why</b>?","answers":[{{"a":"1","e":null}},{{"a":"2","e":null,"c":true}}],"title":"{title}","updated":"{updated}"}}"#
+        ))
+        .unwrap()
+    }
+
+    fn questions() -> Vec<Question> {
+        vec![
+            question("NgGdoZov4T6jV46ty4JUX6", "Lambda & S3", "2024-11-02T10:00:00Z"),
+            question("89yZBXJBa9t2LB6xfj46Rm", "Simple question", "2024-10-31T08:39:17Z"),
+        ]
+    }
+
+    #[test]
+    fn test_sitemap() {
+        let sitemap = render_sitemap(&[("aws", questions()), ("rust", Vec::new())]);
+
+        assert!(sitemap.starts_with("<?xml"));
+        assert!(sitemap.contains("<url><loc>https://bitesized.info/</loc></url>"));
+        assert!(sitemap
+            .contains("<url><loc>https://bitesized.info/questions?topic=aws</loc><lastmod>2024-11-02</lastmod></url>"));
+        assert!(sitemap.contains("<url><loc>https://bitesized.info/question?topic=aws&amp;qid=89yZBXJBa9t2LB6xfj46Rm</loc><lastmod>2024-10-31</lastmod></url>"));
+        assert!(sitemap.contains("<url><loc>https://bitesized.info/questions?topic=rust</loc></url>"));
+        assert_eq!(sitemap.matches("<url>").count(), 5);
+    }
+
+    #[test]
+    fn test_rss() {
+        let rss = render_rss("aws", &questions());
+
+        assert!(rss.contains("<title>AWS questions | Bite-sized learning</title>"));
+        assert!(rss.contains("<lastBuildDate>Sat, 2 Nov 2024 10:00:00 +0000</lastBuildDate>"));
+        assert!(rss.contains("<item><title>Lambda &amp; S3</title>"));
+        assert!(rss.contains("<pubDate>Thu, 31 Oct 2024 08:39:17 +0000</pubDate>"));
+        assert!(rss.contains("<description>What is 1+1 &amp; why?</description>"));
+        assert_eq!(rss.matches("<item>").count(), 2);
+    }
+
+    #[test]
+    fn test_atom() {
+        let atom = render_atom("aws", &questions());
+
+        assert!(atom.contains("<updated>2024-11-02T10:00:00+00:00</updated>\n<author>"));
+        assert!(
+            atom.contains(r#"<link href="https://bitesized.info/question?topic=aws&amp;qid=NgGdoZov4T6jV46ty4JUX6"/>"#)
+        );
+        assert!(atom.contains("<summary>What is 1+1 &amp; why?</summary>"));
+
+        let atom = render_atom("aws", &[]);
+        assert!(atom.contains("<updated>1970-01-01T00:00:00+00:00</updated>"));
+        assert!(!atom.contains("<entry>"));
+    }
+}
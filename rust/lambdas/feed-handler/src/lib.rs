@@ -0,0 +1,201 @@
+use aws_lambda_events::{
+    http::method::Method,
+    lambda_function_urls::{LambdaFunctionUrlRequest, LambdaFunctionUrlResponse},
+};
+use bitie_types::{question::Question, topic::Topic};
+use lambda_runtime::{Error, LambdaEvent};
+use lambda_utils::{
+    error::ApiError,
+    repository::{DdbRepository, QuestionRepository},
+    request::{RequestExt, Router},
+    response::{self, CacheControl, ResponseBuilder},
+};
+use tracing::{info, warn};
+
+mod feed;
+
+/// The max number of questions in a topic feed.
+/// Each item is a separate DDB read to get the question text for the excerpt.
+const MAX_FEED_ITEMS: usize = 20;
+
+/// New questions are not urgent, so the responses are cached for an hour.
+const CACHE_CONTROL: CacheControl = CacheControl::Public {
+    max_age: 3600,
+    s_maxage: 3600,
+};
+
+/// The entry point for the lambda runtime and the local dev server.
+pub async fn my_handler(event: LambdaEvent<LambdaFunctionUrlRequest>) -> Result<LambdaFunctionUrlResponse, Error> {
+    if let Some(v) = response::preflight(&event.payload) {
+        return Ok(v);
+    }
+
+    let request_headers = event.payload.headers.clone();
+    let repo = DdbRepository::from_env().await;
+    let response = handle_request(event.payload, &repo).await?;
+    Ok(response::finalize(&request_headers, response))
+}
+
+/// The actions supported by this handler.
+#[derive(Debug, Clone, Copy)]
+enum Action {
+    /// All published questions in all topics.
+    Sitemap,
+    /// The latest questions in the topic as RSS 2.0.
+    Rss,
+    /// The latest questions in the topic as Atom.
+    Atom,
+}
+
+/// Processes the request against the given storage.
+/// It is separate from `my_handler` to be testable without DDB.
+async fn handle_request<R: QuestionRepository>(
+    request: LambdaFunctionUrlRequest,
+    repo: &R,
+) -> Result<LambdaFunctionUrlResponse, Error> {
+    let router = Router::new()
+        .route(Method::GET, "/sitemap.xml", Action::Sitemap)
+        .route(Method::GET, "/rss.xml", Action::Rss)
+        .route(Method::GET, "/atom.xml", Action::Atom);
+    let action = match router.resolve(&request) {
+        Ok(v) => v,
+        Err(e) => return e.into_response(),
+    };
+
+    let (content_type, body) = match action {
+        Action::Sitemap => {
+            let mut topics = Vec::with_capacity(Topic::TOPICS.len());
+            for topic in Topic::TOPICS {
+                // a partial sitemap would be cached, so any error fails the request
+                match repo.get_published_questions_by_topic(topic).await {
+                    Ok(v) => topics.push((topic, v)),
+                    Err(e) => return ApiError::from(e).into_response(),
+                }
+            }
+            ("application/xml; charset=utf-8", feed::render_sitemap(&topics))
+        }
+
+        Action::Rss | Action::Atom => {
+            let topic = match request.required_topic() {
+                Ok(v) => v,
+                Err(e) => return e.into_response(),
+            };
+
+            let questions = match get_feed_questions(repo, &topic).await {
+                Ok(v) => v,
+                Err(e) => return e.into_response(),
+            };
+
+            match action {
+                Action::Rss => (
+                    "application/rss+xml; charset=utf-8",
+                    feed::render_rss(&topic, &questions),
+                ),
+                _ => (
+                    "application/atom+xml; charset=utf-8",
+                    feed::render_atom(&topic, &questions),
+                ),
+            }
+        }
+    };
+
+    ResponseBuilder::new(200)
+        .content(content_type, body)
+        .cache_control(CACHE_CONTROL)
+        .etag_from_body()
+        .build()
+}
+
+/// Returns up to `MAX_FEED_ITEMS` most recently updated published questions in the topic.
+/// The index has no question text, so the full questions are fetched one by one.
+async fn get_feed_questions<R: QuestionRepository>(repo: &R, topic: &str) -> Result<Vec<Question>, ApiError> {
+    let list = repo.get_published_questions_by_topic(topic).await?;
+    info!("Published questions in {topic}: {}", list.len());
+
+    let mut questions = Vec::with_capacity(list.len().min(MAX_FEED_ITEMS));
+    for item in list.into_iter().take(MAX_FEED_ITEMS) {
+        match repo.get_question(topic, &item.qid).await? {
+            Some(v) => questions.push(v),
+            // the question may have been deleted since the index was read
+            None => warn!("Question not found: {topic}/{}", item.qid),
+        }
+    }
+
+    Ok(questions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_lambda_events::http::header;
+    use bitie_types::question::PublishStage;
+    use lambda_utils::{repository::MemoryRepository, test_utils};
+
+    fn request(path: &str, query: &[(&str, &str)]) -> LambdaFunctionUrlRequest {
+        test_utils::request("GET", path, query, None)
+    }
+
+    fn question(qid: &str, title: &str, stage: PublishStage) -> Question {
+        let mut question = test_utils::question("aws", qid).with_stage(stage);
+        question.title = title.to_string();
+        question.updated = Some("2024-10-31T08:39:17Z".parse().unwrap());
+        question
+    }
+
+    fn repo() -> MemoryRepository {
+        MemoryRepository::with_questions(vec![
+            question("89yZBXJBa9t2LB6xfj46Rm", "Published question", PublishStage::Published),
+            question("NgGdoZov4T6jV46ty4JUX6", "Draft question", PublishStage::Draft),
+        ])
+    }
+
+    #[tokio::test]
+    async fn test_sitemap() {
+        let response = handle_request(request("/sitemap.xml", &[]), &repo()).await.unwrap();
+        assert_eq!(response.status_code, 200);
+        assert_eq!(
+            response.headers.get(header::CONTENT_TYPE).unwrap(),
+            "application/xml; charset=utf-8"
+        );
+        assert!(response.headers.contains_key(header::ETAG));
+
+        let body = response.body.unwrap();
+        assert!(body.contains("qid=89yZBXJBa9t2LB6xfj46Rm"));
+        assert!(!body.contains("NgGdoZov4T6jV46ty4JUX6"));
+        assert!(body.contains("/questions?topic=rust"));
+    }
+
+    #[tokio::test]
+    async fn test_feeds() {
+        let repo = repo();
+
+        let response = handle_request(request("/rss.xml", &[("topic", "aws")]), &repo)
+            .await
+            .unwrap();
+        assert_eq!(response.status_code, 200);
+        let body = response.body.unwrap();
+        assert!(body.contains("<title>Published question</title>"));
+        assert!(body.contains("<description>What is 1+1?</description>"));
+        assert!(!body.contains("Draft question"));
+
+        let response = handle_request(request("/atom.xml", &[("topic", "aws")]), &repo)
+            .await
+            .unwrap();
+        assert_eq!(
+            response.headers.get(header::CONTENT_TYPE).unwrap(),
+            "application/atom+xml; charset=utf-8"
+        );
+        assert!(response.body.unwrap().contains("<summary>What is 1+1?</summary>"));
+
+        // the topic is required and must be valid
+        let response = handle_request(request("/rss.xml", &[]), &repo).await.unwrap();
+        assert_eq!(response.status_code, 400);
+        let response = handle_request(request("/atom.xml", &[("topic", "cobol")]), &repo)
+            .await
+            .unwrap();
+        assert_eq!(response.status_code, 400);
+
+        let response = handle_request(request("/feed.xml", &[]), &repo).await.unwrap();
+        assert_eq!(response.status_code, 404);
+    }
+}
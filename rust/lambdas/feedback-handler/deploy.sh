@@ -16,5 +16,16 @@ rm proxy.zip
 # aarch64-unknown-linux-gnu
 # aarch64-unknown-linux-musl
 
+# new tickets are emailed to BITIE_MODS_EMAIL, if set
+# --environment replaces all variables, so include the existing ones
+# aws lambda update-function-configuration --region $region --function-name $lambda --environment "Variables={BITIE_MODS_EMAIL=...}"
+
 # permissions script
-# aws lambda add-permission \--statement-id "AllowCloudFrontServicePrincipal" \--action "lambda:InvokeFunctionUrl" \--principal "cloudfront.amazonaws.com" \--source-arn "arn:aws:cloudfront::512295225992:distribution/E1EOR95K1Z2GQD" \--region "us-east-1" \--function-name feedback-handler
\ No newline at end of file
+# aws lambda add-permission \--statement-id "AllowCloudFrontServicePrincipal" \--action "lambda:InvokeFunctionUrl" \--principal "cloudfront.amazonaws.com" \--source-arn "arn:aws:cloudfront::512295225992:distribution/E1EOR95K1Z2GQD" \--region "us-east-1" \--function-name feedback-handler
+# digest script - the same crate has a scheduled `feedback-digest` binary for deferred author digests
+# RUSTFLAGS='-C target-feature=+crt-static' cargo build --release --target $target --package $crate --bin feedback-digest
+# cp ./target/$target/release/feedback-digest ./bootstrap && zip proxy.zip bootstrap && rm bootstrap
+# aws lambda update-function-code --region $region --function-name feedback-digest --zip-file fileb://proxy.zip
+# aws events put-rule --name feedback-digest-hourly --schedule-expression "rate(1 hour)" --region "us-east-1"
+# aws lambda add-permission \--statement-id "AllowEventBridgeSchedule" \--action "lambda:InvokeFunction" \--principal "events.amazonaws.com" \--source-arn "arn:aws:events:us-east-1:512295225992:rule/feedback-digest-hourly" \--region "us-east-1" \--function-name feedback-digest
+# aws events put-targets --rule feedback-digest-hourly --targets "Id"="feedback-digest","Arn"="arn:aws:lambda:us-east-1:512295225992:function:feedback-digest" --region "us-east-1"
@@ -0,0 +1,211 @@
+//! Notifies question authors about new feedback on their questions.
+//!
+//! Feedback that arrives within `DIGEST_WINDOW_SECS` of the last notification is not sent straight away.
+//! It is included in the next digest, which lists all open feedback submitted since the previous one.
+//! The deferred digests are sent by `flush_digests` on a schedule, so they do not wait for more feedback.
+
+use bitie_types::{
+    feedback::{Feedback, FeedbackStatus},
+    question::Question,
+};
+use chrono::{DateTime, Duration, Utc};
+use lambda_utils::repository::{FeedbackRepository, QuestionRepository, UserRepository};
+use std::collections::{BTreeSet, HashMap};
+use tracing::{error, info, warn};
+
+/// The min time between two digests sent to the same author.
+pub(crate) const DIGEST_WINDOW_SECS: i64 = 3600;
+
+/// The page where authors can turn off the notifications.
+const OPT_OUT_URL: &str = "https://bitesized.info/subscription";
+
+/// Sends a digest of new feedback to the author of the question, unless they opted out
+/// or were notified within the digest window.
+/// All errors are logged inside the function because the feedback is already saved.
+pub(crate) async fn notify_author<R>(repo: &R, question: &Question, feedback: &Feedback)
+where
+    R: QuestionRepository + UserRepository + FeedbackRepository,
+{
+    let author = match &question.author {
+        Some(v) => v,
+        None => {
+            warn!("No author for {}/{}", feedback.topic, feedback.qid);
+            return;
+        }
+    };
+
+    // authors do not need to be told about their own feedback
+    if feedback.submitter.as_deref() == Some(author.as_str()) {
+        info!("Feedback by the author");
+        return;
+    }
+
+    send_digest(repo, author, Utc::now()).await;
+}
+
+/// Sends the digests deferred by `notify_author` to all authors with open feedback
+/// that arrived after their last notification. Called by the scheduler.
+/// All errors are logged inside the function so that one author does not block the others.
+pub(crate) async fn flush_digests<R>(repo: &R, now: DateTime<Utc>)
+where
+    R: QuestionRepository + UserRepository + FeedbackRepository,
+{
+    let open = match repo.get_feedback_by_status(FeedbackStatus::Open).await {
+        Ok(v) => v,
+        Err(e) => {
+            error!("Failed to get open feedback: {:?}", e);
+            return;
+        }
+    };
+
+    // several feedback items may be about the same question or the same author
+    let questions = open
+        .iter()
+        .map(|v| (v.topic.as_str(), v.qid.as_str()))
+        .collect::<BTreeSet<_>>();
+    let mut authors = BTreeSet::new();
+    for (topic, qid) in questions {
+        match repo.get_question(topic, qid).await {
+            Ok(Some(Question { author: Some(v), .. })) => {
+                authors.insert(v);
+            }
+            Ok(_) => warn!("No author for {topic}/{qid}"),
+            Err(e) => error!("Failed to get question {topic}/{qid}: {:?}", e),
+        }
+    }
+
+    info!("Open feedback for {} authors", authors.len());
+    for author in authors {
+        send_digest(repo, &author, now).await;
+    }
+}
+
+/// Sends a digest of open feedback on the author's questions submitted since their last notification,
+/// unless they opted out or were notified within the digest window.
+async fn send_digest<R>(repo: &R, author: &str, now: DateTime<Utc>)
+where
+    R: QuestionRepository + UserRepository + FeedbackRepository,
+{
+    let user = match repo.get_user_by_email_hash(author).await {
+        Ok(Some(v)) => v,
+        Ok(None) => {
+            warn!("No user record for author {author}");
+            return;
+        }
+        Err(e) => {
+            error!("Failed to get author {author}: {:?}", e);
+            return;
+        }
+    };
+
+    if user.feedback_opt_out == Some(true) {
+        info!("Author {author} opted out of feedback notifications");
+        return;
+    }
+
+    if let Some(v) = user.feedback_notified {
+        if now - v < Duration::seconds(DIGEST_WINDOW_SECS) {
+            info!("Author {author} was notified at {v}, deferred to the next digest");
+            return;
+        }
+    }
+
+    // collect all open feedback on the author's questions since the last digest
+    let questions = match repo.get_all_questions_by_author(author).await {
+        Ok(v) => v,
+        Err(e) => {
+            error!("Failed to get questions by {author}: {:?}", e);
+            return;
+        }
+    };
+    let mut items = Vec::new();
+    for question in &questions {
+        match repo.get_feedback_for_question(&question.topic, &question.qid).await {
+            Ok(v) => items.extend(v.into_iter().filter(|v| {
+                v.status == FeedbackStatus::Open
+                    && v.submitter.as_deref() != Some(author)
+                    && user.feedback_notified.is_none_or(|notified| v.created > notified)
+            })),
+            Err(e) => error!(
+                "Failed to get feedback for {}/{}: {:?}",
+                question.topic, question.qid, e
+            ),
+        }
+    }
+
+    // expected for most authors when called by the scheduler
+    if items.is_empty() {
+        info!("No new feedback for {author}");
+        return;
+    }
+
+    // another request may be sending the same digest
+    match repo
+        .update_feedback_notified(&user.email, now, user.feedback_notified)
+        .await
+    {
+        Ok(true) => {}
+        Ok(false) => return,
+        Err(e) => {
+            error!("Failed to update feedback notification time for {author}: {:?}", e);
+            return;
+        }
+    }
+
+    let titles = questions
+        .iter()
+        .map(|v| (v.qid.as_str(), v.title.as_str()))
+        .collect::<HashMap<&str, &str>>();
+    let (subject, body) = render_digest(&items, &titles);
+
+    lambda_utils::email::send_text_email(&user.email, &subject, &body).await;
+}
+
+/// Returns the subject and the plain text body of the digest email.
+/// `titles` maps qids to question titles.
+fn render_digest(items: &[Feedback], titles: &HashMap<&str, &str>) -> (String, String) {
+    let subject = match items.len() {
+        1 => "New feedback on your question".to_string(),
+        v => format!("{v} new feedback messages on your questions"),
+    };
+
+    let items = items
+        .iter()
+        .map(|v| {
+            let title = titles.get(v.qid.as_str()).copied().unwrap_or(Question::DEFAULT_TITLE);
+            format!(
+                "{title}\nhttps://bitesized.info/question?topic={}&qid={}\n\n{}",
+                v.topic, v.qid, v.text
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n\n---\n\n");
+
+    let body = format!(
+        "Hi,\n\nLearners left feedback on your questions at bitesized.info:\n\n---\n\n{items}\n\n---\n\nYou can update the questions by following the links above.\n\nTo stop these emails, turn off feedback notifications at {OPT_OUT_URL}\n"
+    );
+
+    (subject, body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_digest() {
+        let f1 = Feedback::new("aws", "89yZBXJBa9t2LB6xfj46Rm", None, "", "Typo in the question");
+        let f2 = Feedback::new("aws", "NgGdoZov4T6jV46ty4JUX6", None, "", "Wrong answer");
+        let titles = HashMap::from([("89yZBXJBa9t2LB6xfj46Rm", "Simple question")]);
+
+        let (subject, body) = render_digest(std::slice::from_ref(&f1), &titles);
+        assert_eq!(subject, "New feedback on your question");
+        assert!(body.contains("Simple question\nhttps://bitesized.info/question?topic=aws&qid=89yZBXJBa9t2LB6xfj46Rm"));
+        assert!(body.contains("Typo in the question"));
+        assert!(body.contains(OPT_OUT_URL));
+
+        let (subject, body) = render_digest(&[f1, f2], &titles);
+        assert_eq!(subject, "2 new feedback messages on your questions");
+        assert!(body.contains(&format!("{}\nhttps://", Question::DEFAULT_TITLE)));
+    }
+}
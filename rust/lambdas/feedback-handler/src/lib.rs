@@ -0,0 +1,513 @@
+use aws_lambda_events::{
+    http::method::Method,
+    lambda_function_urls::{LambdaFunctionUrlRequest, LambdaFunctionUrlResponse},
+};
+use bitie_types::{
+    feedback::{Feedback, FeedbackStatus, MAX_FEEDBACK_LENGTH},
+    jwt::JwtUser,
+    topic::Topic,
+};
+use lambda_runtime::{Error, LambdaEvent};
+use lambda_utils::{
+    error::ApiError,
+    rate_limit::{self, DdbRateLimiter, RateLimit, RateLimiter},
+    repository::{DdbRepository, FeedbackRepository, QuestionRepository, UserRepository},
+    request::{RequestExt, Router, ANY_PATH},
+    response,
+};
+use std::str::FromStr;
+use tracing::{error, info, warn};
+
+mod digest;
+
+/// The query string param with the feedback ID.
+const FID_PARAM: &str = "fid";
+/// The query string param with the feedback status to list.
+const STATUS_PARAM: &str = "status";
+
+/// The entry point for the lambda runtime and the local dev server.
+pub async fn my_handler(event: LambdaEvent<LambdaFunctionUrlRequest>) -> Result<LambdaFunctionUrlResponse, Error> {
+    if let Some(v) = response::preflight(&event.payload) {
+        return Ok(v);
+    }
+
+    let request_headers = event.payload.headers.clone();
+    let repo = DdbRepository::from_env().await;
+    let limiter = DdbRateLimiter::from_env().await;
+    let response = handle_request(event.payload, &repo, &limiter).await?;
+    Ok(response::finalize(&request_headers, response))
+}
+
+/// The entry point for the scheduled lambda that sends the author digests deferred by the digest window.
+/// The scheduler event carries no useful data.
+pub async fn digest_handler(_event: LambdaEvent<serde_json::Value>) -> Result<(), Error> {
+    let repo = DdbRepository::from_env().await;
+    digest::flush_digests(&repo, chrono::Utc::now()).await;
+    Ok(())
+}
+
+/// The actions supported by this handler.
+#[derive(Debug, Clone, Copy)]
+enum Action {
+    /// Saves new feedback from any user and notifies the mods.
+    Submit,
+    /// Lists feedback for a question to its author or mods, or all feedback by status to mods.
+    List,
+    /// Resolves feedback with an optional reply. Mods only.
+    Resolve,
+}
+
+/// Processes the request against the given storage and rate limiter.
+/// It is separate from `my_handler` to be testable without DDB.
+async fn handle_request<R, L>(
+    request: LambdaFunctionUrlRequest,
+    repo: &R,
+    limiter: &L,
+) -> Result<LambdaFunctionUrlResponse, Error>
+where
+    R: QuestionRepository + UserRepository + FeedbackRepository,
+    L: RateLimiter,
+{
+    let router = Router::new()
+        .route(Method::POST, ANY_PATH, Action::Submit)
+        .route(Method::GET, ANY_PATH, Action::List)
+        .route(Method::PUT, ANY_PATH, Action::Resolve);
+    let action = match router.resolve(&request) {
+        Ok(v) => v,
+        Err(e) => return e.into_response(),
+    };
+
+    // the user may be authenticated with an email inside the token
+    let jwt_user = request.jwt_user();
+
+    match action {
+        Action::Submit => submit_feedback(request, jwt_user, repo, limiter).await,
+        Action::List => match list_feedback(&request, repo, jwt_user).await {
+            Ok(v) => lambda_utils::json_response(Some(&v), 200),
+            Err(e) => e.into_response(),
+        },
+        Action::Resolve => match resolve_feedback(&request, repo, jwt_user).await {
+            Ok(v) => lambda_utils::json_response(Some(&v), 200),
+            Err(e) => e.into_response(),
+        },
+    }
+}
+
+/// Validates and saves the feedback on an existing question, then emails it to the mods and the question author.
+async fn submit_feedback<R, L>(
+    request: LambdaFunctionUrlRequest,
+    jwt_user: Option<JwtUser>,
+    repo: &R,
+    limiter: &L,
+) -> Result<LambdaFunctionUrlResponse, Error>
+where
+    R: QuestionRepository + UserRepository + FeedbackRepository,
+    L: RateLimiter,
+{
+    let user_email = jwt_user.as_ref().map(|v| v.email.clone()).unwrap_or_default();
+
+    let user_ip = match &request.request_context.http.source_ip {
+        Some(v) => v.clone(),
+        None => {
+            warn!("Missing source IP");
+            "".to_string()
+        }
+    };
+
+    info!("Submitter: {user_email} / {user_ip}");
+
+    let topic_id = match request.required_topic() {
+        Ok(v) => v,
+        Err(e) => return e.into_response(),
+    };
+    let topic_name = Topic::into_name(&topic_id);
+
+    let qid = match request.required_qid() {
+        Ok(v) => v,
+        Err(e) => return e.into_response(),
+    };
+
+    let feedback_text = match request.text_body() {
+        Ok(v) => {
+            let v = v.trim();
+            if v.chars().count() < 10 {
+                return ApiError::Validation("Feedback text too short".to_string()).into_response();
+            }
+            if v.chars().count() > MAX_FEEDBACK_LENGTH {
+                return ApiError::Validation("Feedback text too long".to_string()).into_response();
+            }
+            v.to_string()
+        }
+
+        Err(e) => return e.into_response(),
+    };
+
+    // the feedback is stored and emailed, so limit it per IP and per user to prevent flooding the inbox
+    let mut rate_limit_keys = Vec::new();
+    if !user_ip.is_empty() {
+        rate_limit_keys.push(format!("feedback/ip/{user_ip}"));
+    }
+    if let Some(v) = &jwt_user {
+        rate_limit_keys.push(format!("feedback/user/{}", v.email_hash));
+    }
+    if let Err(e) = rate_limit::enforce(limiter, &rate_limit_keys, &RateLimit::FEEDBACK).await {
+        return e.into_response();
+    }
+
+    // tickets for unknown questions could not be listed or resolved
+    let question = match repo.get_question(&topic_id, &qid).await {
+        Ok(Some(v)) => v,
+        Ok(None) => return ApiError::NotFound("No question found".to_string()).into_response(),
+        Err(e) => return ApiError::from(e).into_response(),
+    };
+
+    let feedback = Feedback::new(
+        &topic_id,
+        &qid,
+        jwt_user.as_ref().map(|v| v.email_hash.clone()),
+        &user_ip,
+        &feedback_text,
+    );
+    if let Err(e) = repo.save_feedback(&feedback).await {
+        return ApiError::from(e).into_response();
+    }
+
+    let subject = format!("Feedback for {topic_name}/{qid}");
+    let question_url = format!("https://bitesized.info/question?topic={topic_id}&qid={qid}");
+    let body = format!(
+        "{question_url}\n\nFeedback ID: {}\nSubmitter: {user_email} / {user_ip}\n\n\n{feedback_text}",
+        feedback.fid
+    );
+
+    lambda_utils::email::send_text_email(&lambda_utils::email::mods_email(), &subject, &body).await;
+    digest::notify_author(repo, &question, &feedback).await;
+
+    lambda_utils::text_response(None, 204)
+}
+
+/// Returns feedback for the question in `qid` param to the question author or a mod.
+/// Without `qid` returns all feedback with the status from `status` param (open by default) to mods only.
+/// Authors do not see who submitted the feedback.
+async fn list_feedback<R>(
+    request: &LambdaFunctionUrlRequest,
+    repo: &R,
+    jwt_user: Option<JwtUser>,
+) -> Result<Vec<Feedback>, ApiError>
+where
+    R: QuestionRepository + UserRepository + FeedbackRepository,
+{
+    let jwt_user = jwt_user.ok_or(ApiError::Unauthorized)?;
+    let is_mod = is_mod(repo, &jwt_user).await?;
+
+    let qid = match request.qid()? {
+        Some(v) => v,
+        None => {
+            if !is_mod {
+                return Err(ApiError::Forbidden("Only mods can list all feedback".to_string()));
+            }
+
+            let status = match request.query_param(STATUS_PARAM).map(FeedbackStatus::from_str) {
+                Some(Ok(v)) => v,
+                Some(Err(e)) => return Err(ApiError::Validation(e)),
+                None => FeedbackStatus::Open,
+            };
+
+            return Ok(repo.get_feedback_by_status(status).await?);
+        }
+    };
+    let topic = request.required_topic()?;
+
+    let question = match repo.get_question(&topic, &qid).await? {
+        Some(v) => v,
+        None => return Err(ApiError::NotFound("No question found".to_string())),
+    };
+    let is_author = question.author.as_deref() == Some(jwt_user.email_hash.as_str());
+    if !is_mod && !is_author {
+        return Err(ApiError::Forbidden(
+            "The question was created by another user".to_string(),
+        ));
+    }
+
+    let feedback = repo.get_feedback_for_question(&topic, &qid).await?;
+    let feedback = if is_mod {
+        feedback
+    } else {
+        feedback.into_iter().map(|v| v.without_submitter()).collect()
+    };
+    info!("Returning {} feedback records for {topic}/{qid}", feedback.len());
+
+    Ok(feedback)
+}
+
+/// Resolves the feedback in `qid` + `fid` params with the body as the optional reply. Mods only.
+async fn resolve_feedback<R>(
+    request: &LambdaFunctionUrlRequest,
+    repo: &R,
+    jwt_user: Option<JwtUser>,
+) -> Result<Feedback, ApiError>
+where
+    R: UserRepository + FeedbackRepository,
+{
+    let jwt_user = jwt_user.ok_or(ApiError::Unauthorized)?;
+    if !is_mod(repo, &jwt_user).await? {
+        return Err(ApiError::Forbidden("Only mods can resolve feedback".to_string()));
+    }
+
+    let qid = request.required_qid()?;
+    let fid = match request.query_param(FID_PARAM) {
+        Some(v) if Feedback::validate_fid(v) => v.to_string(),
+        Some(v) => {
+            warn!("Invalid fid: {v}");
+            return Err(ApiError::Validation("Invalid fid in the query string".to_string()));
+        }
+        None => return Err(ApiError::Validation("Missing fid in the query string".to_string())),
+    };
+
+    let reply = match request.body.as_deref().map(|v| v.trim()) {
+        Some(v) if v.chars().count() > MAX_FEEDBACK_LENGTH => {
+            return Err(ApiError::Validation("Reply text too long".to_string()))
+        }
+        Some(v) if !v.is_empty() => Some(v.to_string()),
+        _ => None,
+    };
+
+    let feedback = repo.resolve_feedback(&qid, &fid, reply).await?;
+    info!("Feedback {qid}/{fid} resolved by {}", jwt_user.email);
+
+    Ok(feedback)
+}
+
+/// Returns true if the user record has the mod flag.
+async fn is_mod<R: UserRepository>(repo: &R, jwt_user: &JwtUser) -> Result<bool, ApiError> {
+    match repo.get_user(&jwt_user.email).await {
+        Ok(v) => Ok(v.and_then(|v| v.is_mod).unwrap_or_default()),
+        Err(e) => {
+            error!("Failed to get user {}: {:?}", jwt_user.email, e);
+            Err(ApiError::from(e))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitie_types::user::User;
+    use lambda_utils::{
+        email::{EMAIL_SINK_CONSOLE, EMAIL_SINK_ENV_VAR},
+        rate_limit::MemoryRateLimiter,
+        repository::MemoryRepository,
+        test_utils,
+    };
+
+    const QID: &str = "89yZBXJBa9t2LB6xfj46Rm";
+
+    /// Builds a feedback request with the given method, query and body.
+    fn request(method: &str, source_ip: &str, query: &[(&str, &str)], body: Option<&str>) -> LambdaFunctionUrlRequest {
+        let mut request = test_utils::request(method, "/qf", query, body);
+        request.request_context.http.source_ip = Some(source_ip.to_string());
+        request
+    }
+
+    /// Builds a feedback submission from the given IP.
+    fn submission(source_ip: &str) -> LambdaFunctionUrlRequest {
+        request(
+            "POST",
+            source_ip,
+            &[("topic", "aws"), ("qid", QID)],
+            Some("The answer is wrong"),
+        )
+    }
+
+    fn jwt_user(email: &str, email_hash: &str) -> Option<JwtUser> {
+        Some(JwtUser {
+            email: email.to_string(),
+            email_hash: email_hash.to_string(),
+        })
+    }
+
+    /// A repo with one question by `author` and a mod user `mod@b.c`.
+    fn repo() -> MemoryRepository {
+        let question = test_utils::question("aws", QID).with_author("author");
+        let repo = MemoryRepository::with_questions(vec![question]);
+        repo.insert_user(User {
+            email: "mod@b.c".to_string(),
+            email_hash: "mod".to_string(),
+            topics: Vec::new(),
+            questions: Vec::new(),
+            unsubscribe: String::new(),
+            updated: None,
+            is_mod: Some(true),
+            feedback_opt_out: None,
+            feedback_notified: None,
+            links_opt_out: None,
+        });
+        repo
+    }
+
+    /// Same as `repo()` plus the author's user record.
+    async fn repo_with_author(opt_out: Option<bool>) -> MemoryRepository {
+        let repo = repo();
+        repo.create_user("a@b.c", "author").await.unwrap();
+        if let Some(v) = opt_out {
+            repo.update_feedback_opt_out("a@b.c", v).await.unwrap();
+        }
+        repo
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit() {
+        // do not send real emails
+        std::env::set_var(EMAIL_SINK_ENV_VAR, EMAIL_SINK_CONSOLE);
+
+        let repo = repo();
+        let limiter = MemoryRateLimiter::new();
+        for _ in 0..RateLimit::FEEDBACK.capacity {
+            let response = handle_request(submission("1.2.3.4"), &repo, &limiter).await.unwrap();
+            assert_eq!(response.status_code, 204);
+        }
+
+        let response = handle_request(submission("1.2.3.4"), &repo, &limiter).await.unwrap();
+        assert_eq!(response.status_code, 429);
+        assert!(response.headers.get("Retry-After").is_some());
+
+        let response = handle_request(submission("5.6.7.8"), &repo, &limiter).await.unwrap();
+        assert_eq!(response.status_code, 204);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_question() {
+        std::env::set_var(EMAIL_SINK_ENV_VAR, EMAIL_SINK_CONSOLE);
+
+        let repo = repo();
+        let unknown = request(
+            "POST",
+            "1.2.3.4",
+            &[("topic", "aws"), ("qid", "NgGdoZov4T6jV46ty4JUX6")],
+            Some("The answer is wrong"),
+        );
+        let response = handle_request(unknown, &repo, &MemoryRateLimiter::new()).await.unwrap();
+        assert_eq!(response.status_code, 404);
+
+        let all_open = request("GET", "", &[], None);
+        assert!(list_feedback(&all_open, &repo, jwt_user("mod@b.c", "mod"))
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_feedback_tickets() {
+        std::env::set_var(EMAIL_SINK_ENV_VAR, EMAIL_SINK_CONSOLE);
+
+        let repo = repo();
+        let response = handle_request(submission("1.2.3.4"), &repo, &MemoryRateLimiter::new())
+            .await
+            .unwrap();
+        assert_eq!(response.status_code, 204);
+
+        let by_question = request("GET", "", &[("topic", "aws"), ("qid", QID)], None);
+        let all_open = request("GET", "", &[], None);
+
+        // anonymous users and other users cannot see the feedback
+        let e = list_feedback(&by_question, &repo, None).await.unwrap_err();
+        assert_eq!(e.status(), 401);
+        let e = list_feedback(&by_question, &repo, jwt_user("x@b.c", "x"))
+            .await
+            .unwrap_err();
+        assert_eq!(e.status(), 403);
+        let e = list_feedback(&all_open, &repo, jwt_user("a@b.c", "author"))
+            .await
+            .unwrap_err();
+        assert_eq!(e.status(), 403);
+
+        // the author sees the text, but not the submitter
+        let feedback = list_feedback(&by_question, &repo, jwt_user("a@b.c", "author"))
+            .await
+            .unwrap();
+        assert_eq!(feedback.len(), 1);
+        assert_eq!(feedback[0].text, "The answer is wrong");
+        assert!(feedback[0].ip_hash.is_none());
+
+        // mods see everything
+        let feedback = list_feedback(&all_open, &repo, jwt_user("mod@b.c", "mod"))
+            .await
+            .unwrap();
+        assert_eq!(feedback.len(), 1);
+        assert!(feedback[0].ip_hash.is_some());
+
+        // only mods can resolve
+        let fid = feedback[0].fid.as_str();
+        let resolve = request("PUT", "", &[("qid", QID), ("fid", fid)], Some("Fixed, thanks"));
+        let e = resolve_feedback(&resolve, &repo, jwt_user("a@b.c", "author"))
+            .await
+            .unwrap_err();
+        assert_eq!(e.status(), 403);
+
+        let feedback = resolve_feedback(&resolve, &repo, jwt_user("mod@b.c", "mod"))
+            .await
+            .unwrap();
+        assert_eq!(feedback.status, FeedbackStatus::Resolved);
+        assert_eq!(feedback.reply.as_deref(), Some("Fixed, thanks"));
+        assert!(list_feedback(&all_open, &repo, jwt_user("mod@b.c", "mod"))
+            .await
+            .unwrap()
+            .is_empty());
+
+        let missing = request("PUT", "", &[("qid", QID), ("fid", "NgGdoZov4T6jV46ty4JUX6")], None);
+        let e = resolve_feedback(&missing, &repo, jwt_user("mod@b.c", "mod"))
+            .await
+            .unwrap_err();
+        assert_eq!(e.status(), 404);
+    }
+
+    #[tokio::test]
+    async fn test_author_digest() {
+        std::env::set_var(EMAIL_SINK_ENV_VAR, EMAIL_SINK_CONSOLE);
+
+        let repo = repo_with_author(None).await;
+        let limiter = MemoryRateLimiter::new();
+
+        // the first feedback is sent straight away
+        handle_request(submission("1.2.3.4"), &repo, &limiter).await.unwrap();
+        let notified = repo.get_user("a@b.c").await.unwrap().unwrap().feedback_notified;
+        assert!(notified.is_some());
+
+        // the next one waits for the digest window to pass
+        handle_request(submission("1.2.3.4"), &repo, &limiter).await.unwrap();
+        let user = repo.get_user("a@b.c").await.unwrap().unwrap();
+        assert_eq!(user.feedback_notified, notified);
+
+        // the scheduler sends the deferred feedback once the window has passed
+        let now = chrono::Utc::now();
+        digest::flush_digests(&repo, now).await;
+        assert_eq!(
+            repo.get_user("a@b.c").await.unwrap().unwrap().feedback_notified,
+            notified
+        );
+        let later = now + chrono::Duration::seconds(digest::DIGEST_WINDOW_SECS + 1);
+        digest::flush_digests(&repo, later).await;
+        assert_eq!(
+            repo.get_user("a@b.c").await.unwrap().unwrap().feedback_notified,
+            Some(later)
+        );
+
+        // nothing is left to send after that
+        let even_later = later + chrono::Duration::seconds(digest::DIGEST_WINDOW_SECS + 1);
+        digest::flush_digests(&repo, even_later).await;
+        assert_eq!(
+            repo.get_user("a@b.c").await.unwrap().unwrap().feedback_notified,
+            Some(later)
+        );
+
+        // no notifications after opting out
+        let repo = repo_with_author(Some(true)).await;
+        handle_request(submission("1.2.3.4"), &repo, &limiter).await.unwrap();
+        assert!(repo
+            .get_user("a@b.c")
+            .await
+            .unwrap()
+            .unwrap()
+            .feedback_notified
+            .is_none());
+    }
+}
@@ -0,0 +1,220 @@
+//! Social card images for questions shared on social media.
+//!
+//! The cards are rendered as SVG from a template and rasterised to PNG because social networks
+//! do not display SVG images. The font is bundled with the lambda, glyphs missing from it are not rendered.
+//! They are stored in the assets bucket next to index.html and served by CloudFront.
+//! The key includes the `updated` timestamp, so a new card is rendered when the question changes.
+
+use bitie_types::{markdown::escape_html, question::Question, topic::Topic};
+use lambda_runtime::Error;
+use resvg::{
+    tiny_skia::{Pixmap, Transform},
+    usvg::{fontdb::Database, Options, Tree},
+};
+use std::sync::{Arc, LazyLock};
+use tracing::error;
+
+/// The card size recommended for `og:image`.
+pub(crate) const CARD_WIDTH: u32 = 1200;
+pub(crate) const CARD_HEIGHT: u32 = 630;
+
+/// The S3 key prefix and the URL path for all cards.
+const CARDS_PREFIX: &str = "og-cards";
+/// The assets bucket is served from the root of the site.
+const SITE_URL: &str = "https://bitesized.info/";
+
+/// The title is wrapped at this many characters per line.
+const TITLE_LINE_LENGTH: usize = 32;
+/// Longer titles are truncated with `…`.
+const TITLE_MAX_LINES: usize = 4;
+
+/// The font used for all the text on the card.
+const FONT_FAMILY: &str = "Roboto Condensed";
+
+/// The fonts are loaded once and kept between warm invocations of the lambda.
+static FONTS: LazyLock<Arc<Database>> = LazyLock::new(|| {
+    let mut fonts = Database::new();
+    fonts.load_font_data(include_bytes!("../fonts/roboto-condensed-regular.ttf").to_vec());
+    fonts.set_sans_serif_family(FONT_FAMILY);
+    Arc::new(fonts)
+});
+
+/// Returns the S3 key for the card of the question, e.g. `og-cards/aws/89yZBXJBa9t2LB6xfj46Rm-1730363957.png`.
+pub(crate) fn card_key(question: &Question) -> String {
+    let updated = question.updated.map(|v| v.timestamp()).unwrap_or_default();
+    format!("{CARDS_PREFIX}/{}/{}-{updated}.png", question.topic, question.qid)
+}
+
+/// Returns the public URL of the card stored under the key.
+pub(crate) fn card_url(key: &str) -> String {
+    [SITE_URL, key].concat()
+}
+
+/// Renders the card as a PNG image.
+pub(crate) fn render_png(question: &Question) -> Result<Vec<u8>, Error> {
+    let options = Options {
+        font_family: FONT_FAMILY.to_string(),
+        fontdb: FONTS.clone(),
+        ..Options::default()
+    };
+    let tree = Tree::from_str(&render_card(question), &options).map_err(|e| {
+        error!(
+            "Failed to parse the card for {}/{}: {:?}",
+            question.topic, question.qid, e
+        );
+        Error::from("Failed to parse the card")
+    })?;
+
+    let mut pixmap = Pixmap::new(CARD_WIDTH, CARD_HEIGHT).ok_or_else(|| Error::from("Invalid card size"))?;
+    resvg::render(&tree, Transform::default(), &mut pixmap.as_mut());
+
+    pixmap.encode_png().map_err(|e| {
+        error!(
+            "Failed to encode the card for {}/{}: {:?}",
+            question.topic, question.qid, e
+        );
+        Error::from("Failed to encode the card")
+    })
+}
+
+/// Renders the card as SVG with the question title, the topic name and the contributor name, if any.
+fn render_card(question: &Question) -> String {
+    let topic_name = escape_html(Topic::into_name(&question.topic));
+
+    // one <tspan> per line of the title
+    let title = wrap_text(&question.title, TITLE_LINE_LENGTH, TITLE_MAX_LINES)
+        .iter()
+        .enumerate()
+        .map(|(i, v)| {
+            let dy = if i == 0 { 0 } else { 76 };
+            format!(r#"<tspan x="80" dy="{dy}">{}</tspan>"#, escape_html(v))
+        })
+        .collect::<String>();
+
+    let contributor = match question
+        .contributor
+        .as_ref()
+        .and_then(|v| v.name.as_deref())
+        .map(|v| v.trim())
+    {
+        Some(v) if !v.is_empty() => format!(
+            r##"<text x="80" y="560" font-size="30" fill="#cbd5e1">Contributed by {}</text>"##,
+            escape_html(v)
+        ),
+        _ => String::new(),
+    };
+
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{CARD_WIDTH}" height="{CARD_HEIGHT}" viewBox="0 0 {CARD_WIDTH} {CARD_HEIGHT}" font-family="{FONT_FAMILY}">
+<rect width="100%" height="100%" fill="#0f172a"/>
+<rect x="0" y="0" width="16" height="{CARD_HEIGHT}" fill="#22c55e"/>
+<text x="80" y="110" font-size="36" fill="#22c55e">{topic_name}</text>
+<text x="80" y="220" font-size="64" font-weight="bold" fill="#f8fafc">{title}</text>
+{contributor}
+<text x="1120" y="560" font-size="30" fill="#94a3b8" text-anchor="end">bitesized.info</text>
+</svg>
+"##
+    )
+}
+
+/// Splits the text into lines of up to `line_length` characters at word boundaries.
+/// Words longer than a line are split. Lines beyond `max_lines` are dropped and the last line ends with `…`.
+fn wrap_text(text: &str, line_length: usize, max_lines: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+
+    for word in text.split_whitespace() {
+        let mut word = word.to_string();
+        loop {
+            let line_chars = line.chars().count();
+            let word_chars = word.chars().count();
+            let separator = if line.is_empty() { 0 } else { 1 };
+
+            if line_chars + separator + word_chars <= line_length {
+                if separator == 1 {
+                    line.push(' ');
+                }
+                line.push_str(&word);
+                break;
+            }
+
+            if line.is_empty() {
+                // the word is longer than a line
+                lines.push(word.chars().take(line_length).collect());
+                word = word.chars().skip(line_length).collect();
+            } else {
+                lines.push(std::mem::take(&mut line));
+            }
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+
+    if lines.len() > max_lines {
+        lines.truncate(max_lines);
+        if let Some(last) = lines.last_mut() {
+            let kept = last.chars().take(line_length.saturating_sub(1)).collect::<String>();
+            *last = [kept.trim_end(), "…"].concat();
+        }
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_wrap_text() {
+        assert_eq!(
+            wrap_text("What is the answer?", 10, 3),
+            vec!["What is", "the", "answer?"]
+        );
+        assert_eq!(wrap_text("abcdefghijkl mn", 5, 5), vec!["abcde", "fghij", "kl mn"]);
+        assert_eq!(wrap_text("one two three four", 5, 2), vec!["one", "two…"]);
+        assert!(wrap_text("  ", 5, 2).is_empty());
+    }
+
+    #[test]
+    fn test_render_card() {
+        let question = Question::from_str(
+            r#"{"qid":"89yZBXJBa9t2LB6xfj46Rm","topic":"aws","question":"What is 1+1?","answers":[{"a":"1","e":null},{"a":"2","e":null,"c":true}],"title":"<Lambda> & S3","updated":"2024-10-31T08:39:17Z","contributor":{"name":"Jane & Co"}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            card_key(&question),
+            "og-cards/aws/89yZBXJBa9t2LB6xfj46Rm-1730363957.png"
+        );
+        assert_eq!(
+            card_url(&card_key(&question)),
+            "https://bitesized.info/og-cards/aws/89yZBXJBa9t2LB6xfj46Rm-1730363957.png"
+        );
+
+        let svg = render_card(&question);
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.contains(r#"<tspan x="80" dy="0">&lt;Lambda&gt; &amp; S3</tspan>"#));
+        assert!(svg.contains(">AWS</text>"));
+        assert!(svg.contains("Contributed by Jane &amp; Co"));
+
+        // the text is rendered with the bundled font
+        assert_eq!(FONTS.faces().count(), 1);
+        assert_eq!(FONTS.faces().next().unwrap().families[0].0, FONT_FAMILY);
+        let png = render_png(&question).unwrap();
+        assert!(png.starts_with(b"\x89PNG"));
+        let pixmap = Pixmap::decode_png(&png).unwrap();
+        assert_eq!((pixmap.width(), pixmap.height()), (CARD_WIDTH, CARD_HEIGHT));
+        let text_pixels = pixmap
+            .pixels()
+            .iter()
+            .filter(|v| v.red() > 200 && v.green() > 200 && v.blue() > 200)
+            .count();
+        assert!(
+            text_pixels > 1000,
+            "the title should be rendered in white, got {text_pixels} pixels"
+        );
+    }
+}
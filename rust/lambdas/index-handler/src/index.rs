@@ -1,27 +1,145 @@
-use aws_sdk_s3::Client;
+use aws_sdk_s3::{primitives::ByteStream, Client};
 use lambda_runtime::Error;
-use tracing::error;
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use tokio::sync::OnceCell;
+use tracing::{error, info, warn};
 
-/// Reads the index.html file from S3 and returns it as a string.
+/// Returns the name of the bucket with index.html and other assets.
+fn bucket_name() -> String {
+    std::env::var("BUCKET_NAME").unwrap_or_else(|_e| "bitesized.info-assets".to_string())
+}
+
+/// How often the cached index.html is revalidated against S3, in seconds.
+const CACHE_SECS_ENV_VAR: &str = "INDEX_CACHE_SECS";
+const DEFAULT_CACHE_SECS: u64 = 60;
+
+/// The S3 client is reused by warm invocations of the lambda.
+static S3_CLIENT: OnceCell<Client> = OnceCell::const_new();
+
+/// The last good copy of index.html, kept between warm invocations of the lambda.
+static INDEX_CACHE: Mutex<Option<CachedIndex>> = Mutex::new(None);
+
+/// A copy of index.html with its S3 ETag.
+#[derive(Debug, Clone)]
+struct CachedIndex {
+    html: String,
+    etag: Option<String>,
+    /// When the copy was last downloaded or revalidated.
+    checked: Instant,
+}
+
+impl CachedIndex {
+    /// Returns true if the copy can be used without checking S3.
+    fn is_fresh(&self, max_age: Duration) -> bool {
+        self.checked.elapsed() < max_age
+    }
+}
+
+/// The result of a conditional GET of index.html.
+enum Fetched {
+    Modified { html: String, etag: Option<String> },
+    NotModified,
+}
+
+/// Returns a shared S3 client, creating it on the first call.
+async fn s3_client() -> &'static Client {
+    S3_CLIENT
+        .get_or_init(|| async {
+            let config = aws_config::load_from_env().await;
+            Client::new(&config)
+        })
+        .await
+}
+
+/// Returns the revalidation interval from `INDEX_CACHE_SECS` or the default.
+fn cache_max_age(value: Option<String>) -> Duration {
+    let secs = match value.map(|v| v.trim().parse::<u64>()) {
+        Some(Ok(v)) => v,
+        Some(Err(e)) => {
+            warn!("Invalid {CACHE_SECS_ENV_VAR}: {:?}", e);
+            DEFAULT_CACHE_SECS
+        }
+        None => DEFAULT_CACHE_SECS,
+    };
+    Duration::from_secs(secs)
+}
+
+/// Returns index.html from the in-memory cache, revalidating it against S3 with its ETag
+/// once the cached copy is older than `INDEX_CACHE_SECS`.
+/// The last good copy is returned if S3 fails.
 /// All errors are logged. Should not panic.
-pub(crate) async fn get_index_from_s3() -> Result<String, Error> {
-    let config = aws_config::load_from_env().await;
-    let client = Client::new(&config);
+pub(crate) async fn get_index() -> Result<String, Error> {
+    let cached = INDEX_CACHE.lock().expect("Poisoned mutex. It's a bug.").clone();
+
+    let etag = match &cached {
+        Some(v) if v.is_fresh(cache_max_age(std::env::var(CACHE_SECS_ENV_VAR).ok())) => {
+            info!("Cached index.html");
+            return Ok(v.html.clone());
+        }
+        Some(v) => v.etag.clone(),
+        None => None,
+    };
+
+    let html = match (get_index_from_s3(etag.as_deref()).await, cached) {
+        (Ok(Fetched::Modified { html, etag }), _) => {
+            info!("index.html updated, ETag: {:?}", etag);
+            html_to_cache(html, etag)
+        }
+        (Ok(Fetched::NotModified), Some(cached)) => {
+            info!("index.html not modified");
+            html_to_cache(cached.html, cached.etag)
+        }
+        // S3 is checked again after the same interval to avoid retrying on every request
+        (Err(_), Some(cached)) => {
+            warn!("Using the last good copy of index.html");
+            html_to_cache(cached.html, cached.etag)
+        }
+        (Ok(Fetched::NotModified), None) => {
+            error!("index.html not modified without a cached copy. It's a bug.");
+            return Err(Error::from("No cached copy of index.html"));
+        }
+        // the error is logged inside
+        (Err(e), None) => return Err(e),
+    };
+
+    Ok(html)
+}
 
-    let bucket = std::env::var("BUCKET_NAME").unwrap_or_else(|_e| "bitesized.info-assets".to_string());
-    let response = match client
+/// Stores index.html in the cache as checked now and returns it.
+fn html_to_cache(html: String, etag: Option<String>) -> String {
+    let mut cache = INDEX_CACHE.lock().expect("Poisoned mutex. It's a bug.");
+    *cache = Some(CachedIndex {
+        html: html.clone(),
+        etag,
+        checked: Instant::now(),
+    });
+    html
+}
+
+/// Reads the index.html file from S3 unless its ETag matches `if_none_match`.
+/// All errors are logged. Should not panic.
+async fn get_index_from_s3(if_none_match: Option<&str>) -> Result<Fetched, Error> {
+    let response = match s3_client()
+        .await
         .get_object()
-        .bucket(bucket)
+        .bucket(bucket_name())
         .key("index.html".to_string())
+        .set_if_none_match(if_none_match.map(|v| v.to_string()))
         .send()
         .await
     {
         Ok(v) => v,
+        // S3 returns 304 as an error
+        Err(e) if e.raw_response().is_some_and(|v| v.status().as_u16() == 304) => return Ok(Fetched::NotModified),
         Err(e) => {
             error!("Failed to get index.html from S3: {:?}", e);
             return Err(Error::from("Failed to get index.html from S3"));
         }
     };
+    let etag = response.e_tag.clone();
     let body = match response.body.collect().await {
         Ok(v) => v,
         Err(e) => {
@@ -30,7 +148,7 @@ pub(crate) async fn get_index_from_s3() -> Result<String, Error> {
         }
     };
 
-    let body = match String::from_utf8(body.to_vec()) {
+    let html = match String::from_utf8(body.to_vec()) {
         Ok(v) => v,
         Err(e) => {
             error!("Failed to convert index.html bytes into a UTF-8 string: {:?}", e);
@@ -38,5 +156,82 @@ pub(crate) async fn get_index_from_s3() -> Result<String, Error> {
         }
     };
 
-    Ok(body)
+    Ok(Fetched::Modified { html, etag })
+}
+
+/// Uploads the object to the assets bucket unless it is already there.
+/// The body is only rendered if the object is missing.
+/// All errors are logged. Should not panic.
+pub(crate) async fn put_if_missing(
+    key: &str,
+    content_type: &str,
+    body: impl FnOnce() -> Result<Vec<u8>, Error>,
+) -> Result<(), Error> {
+    let client = s3_client().await;
+    let bucket = bucket_name();
+
+    match client.head_object().bucket(&bucket).key(key).send().await {
+        Ok(_) => {
+            info!("{key} already exists");
+            return Ok(());
+        }
+        Err(e) if e.as_service_error().is_some_and(|e| e.is_not_found()) => info!("{key} not found"),
+        Err(e) => {
+            error!("Failed to check if {key} exists: {:?}", e);
+            return Err(Error::from("Failed to check the object in S3"));
+        }
+    }
+
+    match client
+        .put_object()
+        .bucket(&bucket)
+        .key(key)
+        .content_type(content_type)
+        .body(ByteStream::from(body()?))
+        .send()
+        .await
+    {
+        Ok(_) => {
+            info!("{key} uploaded");
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to upload {key}: {:?}", e);
+            Err(Error::from("Failed to upload the object to S3"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_max_age() {
+        assert_eq!(cache_max_age(None), Duration::from_secs(DEFAULT_CACHE_SECS));
+        assert_eq!(cache_max_age(Some(" 300".to_string())), Duration::from_secs(300));
+        assert_eq!(cache_max_age(Some("0".to_string())), Duration::ZERO);
+        assert_eq!(
+            cache_max_age(Some("1m".to_string())),
+            Duration::from_secs(DEFAULT_CACHE_SECS)
+        );
+    }
+
+    #[test]
+    fn test_is_fresh() {
+        let cached = CachedIndex {
+            html: String::new(),
+            etag: None,
+            checked: Instant::now(),
+        };
+        assert!(cached.is_fresh(Duration::from_secs(60)));
+        // zero disables the cache
+        assert!(!cached.is_fresh(Duration::ZERO));
+
+        let cached = CachedIndex {
+            checked: Instant::now() - Duration::from_secs(61),
+            ..cached
+        };
+        assert!(!cached.is_fresh(Duration::from_secs(60)));
+    }
 }
@@ -1,11 +1,22 @@
 use aws_lambda_events::lambda_function_urls::{LambdaFunctionUrlRequest, LambdaFunctionUrlResponse};
-use bitie_types::{ddb::fields, topic::Topic};
-use index::get_index_from_s3;
+use bitie_types::{
+    ddb::fields,
+    question::{PublishStage, Question},
+};
+use index::{get_index, put_if_missing};
 use lambda_runtime::{service_fn, Error, LambdaEvent, Runtime};
+use lambda_utils::repository::{DdbRepository, QuestionRepository};
+use og::OgTags;
 use tracing::{error, info};
 use tracing_subscriber::filter::LevelFilter;
 
+mod card;
 mod index;
+mod og;
+mod ssr;
+
+/// The SPA route for a single question.
+const QUESTION_PATH: &str = "/question";
 
 #[tokio::main]
 async fn main() -> Result<(), Error> {
@@ -30,8 +41,8 @@ pub(crate) async fn my_handler(
     let path = event.payload.raw_path.clone().unwrap_or("".to_string());
     info!("Path: {}", path);
 
-    // get index.html from S3
-    let index_html = get_index_from_s3().await?;
+    // get index.html from the warm cache or S3
+    let index_html = get_index().await?;
 
     let topic = match event.payload.query_string_parameters.get(fields::TOPIC) {
         Some(v) => v.trim().to_ascii_lowercase(),
@@ -43,88 +54,60 @@ pub(crate) async fn my_handler(
     };
     info!("Topic: {:?}", topic);
 
-    // return index.html with OG tags updated
-    lambda_utils::text_response(Some(replace_with_regex(index_html, &topic)), 200)
-}
-
-/// Replaces Title and ogImage in the HTML, if the new values are not empty strings.
-/// Otherwise keeps the existing values.
-fn replace_with_regex(index_html: String, topic: &str) -> String {
-    // get the user-friendly topic name
-    let topic_name = match Topic::TOPICS.iter().position(|v| v == &topic) {
-        Some(v) => Topic::TOPIC_NAMES[v],
-        None => {
-            // log the error, return the original HTML and let the frontend handle it
-            info!("Invalid topic: {:?}", topic);
-            return index_html;
-        }
-    };
-
-    // description should not be empty
-    // we use a generic blurb if the book data has none
-    // trip it to 500 characters
-    // let description = ["Can you answer this question about ", topic_name, "?"].concat();
-
-    // // <meta name="description" content="A pocket assistant ...">
-    // let index_html = match regex::Regex::new(r#"("description"[^>]+content=")([^"]+)"#) {
-    //     Ok(v) => v.replace(&index_html, ["${1}", &description].concat()),
-    //     Err(e) => {
-    //         error!("Invalid description regex. It's a bug. {:?}", e);
-    //         std::borrow::Cow::Borrowed(index_html.as_str())
-    //     }
-    // };
-
-    // // <meta property="og:description" content="A pocket assistant for keen readers...">
-    // let index_html = match regex::Regex::new(r#"("og:description"[^>]+content=")([^"]+)"#) {
-    //     Ok(v) => v.replace(&index_html, ["${1}", &description].concat()),
-    //     Err(e) => {
-    //         error!("Invalid og:description regex. It's a bug. {:?}", e);
-    //         index_html
-    //     }
-    // };
-
-    // // <meta name="twitter:description" content="A pocket assistant for keen readers..." />
-    // let index_html = match regex::Regex::new(r#"("twitter:description"[^>]+content=")([^"]+)"#) {
-    //     Ok(v) => v.replace(&index_html, ["${1}", &description].concat()),
-    //     Err(e) => {
-    //         error!("Invalid twitter:description regex. It's a bug. {:?}", e);
-    //         index_html
-    //     }
-    // };
-
-    let title = [topic_name, ": something new I learned today"].concat();
-
-    // replace the title in multiple places if the value is not empty
-    // <title>bla-bla</title>
-    let index_html = match regex::Regex::new(r"(<title>)([^<]+)") {
-        Ok(v) => v.replace(&index_html, ["${1}", &title].concat()),
-        Err(e) => {
-            error!("Invalid title regex. It's a bug. {:?}", e);
-            std::borrow::Cow::Borrowed(index_html.as_str())
-        }
-    };
-
-    // <meta property="og:title" content="bla...bla" />
-    let index_html = match regex::Regex::new(r#"("og:title"[^>]+content=")([^"]+)"#) {
-        Ok(v) => v.replace(&index_html, ["${1}", &title].concat()),
-        Err(e) => {
-            error!("Invalid og:title regex. It's a bug. {:?}", e);
-            index_html
+    // qid is optional and is ignored if invalid
+    let qid = event
+        .payload
+        .query_string_parameters
+        .get(fields::QID)
+        .map(|v| v.trim())
+        .filter(|v| Question::validate_qid(v));
+    info!("Qid: {:?}", qid);
+
+    // question tags fall back onto the topic tags if the question cannot be shown
+    let tags = match qid {
+        Some(qid) => {
+            let repo = DdbRepository::from_env().await;
+            match repo.get_question(&topic, qid).await {
+                Ok(Some(v)) => match OgTags::for_question(&v) {
+                    Some(tags) if v.stage == PublishStage::Published => {
+                        // crawlers get the question as static HTML
+                        let index_html = if path == QUESTION_PATH {
+                            ssr::inject_question(index_html, &v)
+                        } else {
+                            index_html
+                        };
+                        let tags = with_card(tags, &v).await;
+                        return lambda_utils::text_response(Some(tags.apply(index_html)), 200);
+                    }
+                    tags => tags,
+                },
+                Ok(None) => {
+                    info!("Question not found: {topic}/{qid}");
+                    OgTags::for_topic(&topic)
+                }
+                Err(e) => {
+                    error!("Failed to get question {topic}/{qid}: {:?}", e);
+                    OgTags::for_topic(&topic)
+                }
+            }
         }
+        None => OgTags::for_topic(&topic),
     };
 
-    // <meta name="twitter:title" content="bla...bla..." />
-    let index_html = match regex::Regex::new(r#"("twitter:title"[^>]+content=")([^"]+)"#) {
-        Ok(v) => v.replace(&index_html, ["${1}", &title].concat()),
-        Err(e) => {
-            error!("Invalid twitter:title regex. It's a bug. {:?}", e);
-            index_html
-        }
-    };
+    // return index.html with OG tags updated
+    match tags {
+        Some(v) => lambda_utils::text_response(Some(v.apply(index_html)), 200),
+        None => lambda_utils::text_response(Some(index_html), 200),
+    }
+}
 
-    // replace images with the topic-specific image
-    // e.g. <meta property="og:image" itemprop="image" content="https://bitesized.info/og-image.png" />
-    // the new name should be og-aws.png, og-rust.png, etc.
-    let og_image = ["/og-", topic, "-627.png"].concat();
-    index_html.replace("/default-627.png", &og_image)
+/// Adds the question card to the tags, rendering and uploading it on the first request.
+/// Keeps the topic image if the card cannot be uploaded.
+async fn with_card(tags: OgTags, question: &Question) -> OgTags {
+    let key = card::card_key(question);
+    match put_if_missing(&key, "image/png", || card::render_png(question)).await {
+        Ok(()) => tags.with_card(card::card_url(&key)),
+        // the error is logged inside
+        Err(_) => tags,
+    }
 }
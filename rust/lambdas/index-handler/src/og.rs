@@ -0,0 +1,238 @@
+use crate::card::{CARD_HEIGHT, CARD_WIDTH};
+use bitie_types::{
+    markdown::{escape_html, md_to_plain_text},
+    question::{PublishStage, Question},
+    topic::Topic,
+};
+use regex::{Captures, Regex};
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex},
+};
+use tracing::{error, info};
+
+/// The max length of the description in characters.
+/// Most sites cut it at around 200 characters.
+const MAX_DESCRIPTION_LENGTH: usize = 200;
+
+/// Page metadata injected into `<title>`, description, Open Graph and Twitter tags of index.html.
+/// The values are plain text and are escaped when injected.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct OgTags {
+    pub title: String,
+    /// None keeps the default description from index.html.
+    pub description: Option<String>,
+    /// The topic ID for the topic-specific preview image.
+    pub image_topic: String,
+    /// The URL of the question card to use instead of the topic image.
+    pub card_url: Option<String>,
+}
+
+impl OgTags {
+    /// Generic tags for a topic page or a question that cannot be shown.
+    /// Returns None if the topic is invalid.
+    pub fn for_topic(topic: &str) -> Option<Self> {
+        // get the user-friendly topic name
+        let topic_name = match Topic::TOPICS.iter().position(|v| v == &topic) {
+            Some(v) => Topic::TOPIC_NAMES[v],
+            None => {
+                info!("Invalid topic: {:?}", topic);
+                return None;
+            }
+        };
+
+        Some(Self {
+            title: [topic_name, ": something new I learned today"].concat(),
+            description: None,
+            image_topic: topic.to_string(),
+            card_url: None,
+        })
+    }
+
+    /// Tags with the question title and an excerpt of the question text.
+    /// Drafts are not public and get the topic tags instead.
+    pub fn for_question(question: &Question) -> Option<Self> {
+        let topic_tags = Self::for_topic(&question.topic)?;
+
+        if question.stage != PublishStage::Published {
+            info!("Question {}/{} is not published", question.topic, question.qid);
+            return Some(topic_tags);
+        }
+
+        let topic_name = Topic::into_name(&question.topic);
+        let description = md_to_plain_text(&question.question, MAX_DESCRIPTION_LENGTH);
+
+        Some(Self {
+            title: [&question.title, " | ", topic_name].concat(),
+            description: if description.is_empty() {
+                None
+            } else {
+                Some(description)
+            },
+            ..topic_tags
+        })
+    }
+
+    /// Sets the URL of the question card for the image tags.
+    pub fn with_card(self, card_url: String) -> Self {
+        Self {
+            card_url: Some(card_url),
+            ..self
+        }
+    }
+
+    /// Replaces the title, description and image tags in index.html.
+    /// Tags that are not in the HTML are skipped.
+    pub fn apply(&self, index_html: String) -> String {
+        let title = escape_html(&self.title);
+
+        // <title>bla-bla</title>
+        let index_html = replace_value(index_html, r"(<title>)([^<]*)", &title);
+        // <meta property="og:title" content="bla...bla" />
+        let index_html = replace_value(index_html, r#"("og:title"[^>]+content=")([^"]*)"#, &title);
+        // <meta name="twitter:title" content="bla...bla..." />
+        let index_html = replace_value(index_html, r#"("twitter:title"[^>]+content=")([^"]*)"#, &title);
+
+        let index_html = match &self.description {
+            Some(v) => {
+                let description = escape_html(v);
+                // <meta name="description" content="Find and fill gaps ...">
+                let index_html = replace_value(index_html, r#"("description"[^>]+content=")([^"]*)"#, &description);
+                // <meta property="og:description" content="How I find ...">
+                let index_html = replace_value(index_html, r#"("og:description"[^>]+content=")([^"]*)"#, &description);
+                // <meta name="twitter:description" content="How I find ..." />
+                replace_value(
+                    index_html,
+                    r#"("twitter:description"[^>]+content=")([^"]*)"#,
+                    &description,
+                )
+            }
+            None => index_html,
+        };
+
+        if let Some(card_url) = &self.card_url {
+            let card_url = escape_html(card_url);
+            // <meta property="og:image" itemprop="image" content="https://bitesized.info/og-images-1200a/og-general-627.png" />
+            let index_html = replace_value(index_html, r#"("og:image"[^>]+content=")([^"]*)"#, &card_url);
+            let index_html = replace_value(index_html, r#"("og:image:secure_url"[^>]+content=")([^"]*)"#, &card_url);
+            let index_html = replace_value(index_html, r#"("twitter:image"[^>]+content=")([^"]*)"#, &card_url);
+            let index_html = replace_value(index_html, r#"("og:image:type"[^>]+content=")([^"]*)"#, "image/png");
+            let index_html = replace_value(
+                index_html,
+                r#"("og:image:width"[^>]+content=")([^"]*)"#,
+                &CARD_WIDTH.to_string(),
+            );
+            return replace_value(
+                index_html,
+                r#"("og:image:height"[^>]+content=")([^"]*)"#,
+                &CARD_HEIGHT.to_string(),
+            );
+        }
+
+        // replace images with the topic-specific image
+        // e.g. <meta property="og:image" itemprop="image" content="https://bitesized.info/og-images-1200a/og-general-627.png" />
+        // the new name should be og-aws-627.png, og-rust-627.png, etc.
+        let og_image = ["/og-", &self.image_topic, "-627.png"].concat();
+        index_html.replace("/og-general-627.png", &og_image)
+    }
+}
+
+/// Compiled regexes are kept between warm invocations of the lambda.
+static REGEX_CACHE: LazyLock<Mutex<HashMap<&'static str, Regex>>> = LazyLock::new(Default::default);
+
+/// Returns the compiled regex for the pattern, compiling it on the first use.
+/// Returns None if the pattern is invalid.
+fn cached_regex(pattern: &'static str) -> Option<Regex> {
+    let mut cache = REGEX_CACHE.lock().expect("Poisoned mutex. It's a bug.");
+    if let Some(v) = cache.get(pattern) {
+        return Some(v.clone());
+    }
+
+    match Regex::new(pattern) {
+        Ok(v) => {
+            cache.insert(pattern, v.clone());
+            Some(v)
+        }
+        Err(e) => {
+            error!("Invalid regex {pattern}. It's a bug. {:?}", e);
+            None
+        }
+    }
+}
+
+/// Replaces the second capture group of the first match with the value.
+/// The value is inserted as-is, so `$` in it is not treated as a group reference.
+fn replace_value(html: String, pattern: &'static str, value: &str) -> String {
+    match cached_regex(pattern) {
+        Some(v) => v
+            .replace(&html, |caps: &Captures| [&caps[1], value].concat())
+            .into_owned(),
+        None => html,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    const INDEX_HTML: &str = r#"<title>Bite-sized learning</title>
+  <meta name="description" content="Find and fill gaps in your knowledge, one question at a time.">
+  <meta property="og:title" content="Bite-sized learning">
+  <meta property="og:description" content="How I find and fill gaps in my knowledge one question at a time">
+  <meta property="og:image" itemprop="image" content="https://bitesized.info/og-images-1200a/og-general-627.png" />
+  <meta property="og:image:width" content="1148">
+  <meta property="og:image:type" content="image/png" />
+  <meta name="twitter:image" content="https://bitesized.info/og-images-1200a/og-general-627.png" />
+  <meta name="twitter:title" content="Bite-sized learning" />
+  <meta name="twitter:description" content="How I find and fill gaps in my knowledge one question at a time" />"#;
+
+    fn question(stage: PublishStage) -> Question {
+        Question::from_str(
+            r#"{"qid":"89yZBXJBa9t2LB6xfj46Rm","topic":"aws","question":"What does `<b>` & `$1` do in \"HTML\"?","answers":[{"a":"1","e":null},{"a":"2","e":null,"c":true}],"title":"Tags & <b>$1</b>","updated":null}"#,
+        )
+        .unwrap()
+        .with_stage(stage)
+    }
+
+    #[test]
+    fn test_question_tags() {
+        let tags = OgTags::for_question(&question(PublishStage::Published)).unwrap();
+        let html = tags.apply(INDEX_HTML.to_string());
+
+        assert!(html.contains("<title>Tags &amp; &lt;b&gt;$1&lt;/b&gt; | AWS</title>"));
+        assert!(html.contains(r#"name="twitter:title" content="Tags &amp; &lt;b&gt;$1&lt;/b&gt; | AWS" />"#));
+        assert!(html.contains(r#"name="description" content="What does &lt;b&gt; &amp; $1 do in &quot;HTML&quot;?">"#));
+        assert!(html.contains(r#"property="og:description" content="What does &lt;b&gt;"#));
+        assert!(html.contains("/og-aws-627.png"));
+
+        let html = tags
+            .with_card("https://bitesized.info/og-cards/aws/89yZBXJBa9t2LB6xfj46Rm-0.png".to_string())
+            .apply(INDEX_HTML.to_string());
+        assert!(!html.contains("-627.png"));
+        assert!(html.contains(r#"name="twitter:image" content="https://bitesized.info/og-cards/aws/"#));
+        assert!(html.contains(r#"property="og:image:type" content="image/png""#));
+        assert!(html.contains(r#"property="og:image:width" content="1200""#));
+    }
+
+    #[test]
+    fn test_cached_regex() {
+        let pattern = r"(<title>)([^<]*)";
+        assert!(cached_regex(pattern).is_some());
+        assert!(REGEX_CACHE.lock().unwrap().contains_key(pattern));
+        assert!(cached_regex(r"(<title>").is_none());
+    }
+
+    #[test]
+    fn test_fallbacks() {
+        // drafts are not disclosed
+        let tags = OgTags::for_question(&question(PublishStage::Draft)).unwrap();
+        assert_eq!(tags, OgTags::for_topic("aws").unwrap());
+
+        let html = tags.apply(INDEX_HTML.to_string());
+        assert!(html.contains("<title>AWS: something new I learned today</title>"));
+        assert!(html.contains(r#"content="Find and fill gaps in your knowledge, one question at a time.""#));
+
+        assert!(OgTags::for_topic("invalid").is_none());
+    }
+}
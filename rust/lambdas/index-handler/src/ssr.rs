@@ -0,0 +1,165 @@
+//! Static question markup for crawlers that do not run JS.
+//!
+//! The question is rendered into the `#app` mount point of index.html, so it is replaced
+//! by the Vue app when it mounts. JSON-LD `Quiz` data is added to `<head>`.
+//! Correct answers and explanations are never included.
+
+use bitie_types::{
+    markdown::{escape_html, md_to_plain_text},
+    question::{PublishStage, Question, QuestionFormat},
+    topic::Topic,
+};
+use serde_json::{json, Value};
+use tracing::{error, info};
+
+/// The empty mount point of the Vue app in index.html.
+const APP_MOUNT_POINT: &str = r#"<div id="app"></div>"#;
+
+/// The max length of the question text in JSON-LD.
+const MAX_JSON_LD_TEXT_LENGTH: usize = 1_000;
+
+/// Adds the question markup and JSON-LD data to index.html.
+/// Returns the HTML unchanged for drafts or if the mount point is missing.
+pub(crate) fn inject_question(index_html: String, question: &Question) -> String {
+    if question.stage != PublishStage::Published {
+        info!("Question {}/{} is not published", question.topic, question.qid);
+        return index_html;
+    }
+
+    if !index_html.contains(APP_MOUNT_POINT) {
+        error!("No {APP_MOUNT_POINT} in index.html");
+        return index_html;
+    }
+
+    let markup = [r#"<div id="app">"#, &render_question(question), "</div>"].concat();
+    let index_html = index_html.replacen(APP_MOUNT_POINT, &markup, 1);
+
+    let json_ld = [
+        r#"<script type="application/ld+json">"#,
+        &escape_json(&quiz_json_ld(question)),
+        "</script>\n</head>",
+    ]
+    .concat();
+    index_html.replacen("</head>", &json_ld, 1)
+}
+
+/// Renders the question with the answer options as static HTML.
+fn render_question(question: &Question) -> String {
+    let question = question.clone().format(QuestionFormat::HtmlShort);
+
+    let answers = question
+        .answers
+        .iter()
+        .map(|v| ["<li>", v.text(), "</li>"].concat())
+        .collect::<String>();
+
+    format!(
+        r#"<article class="ssr-question"><p>{}</p><h1>{}</h1>{}<ol>{answers}</ol></article>"#,
+        escape_html(Topic::into_name(&question.topic)),
+        escape_html(&question.title),
+        question.question,
+    )
+}
+
+/// Returns schema.org `Quiz` data for the question.
+/// The answers are listed as `suggestedAnswer` without marking the correct ones.
+fn quiz_json_ld(question: &Question) -> Value {
+    let mut quiz = json!({
+        "@context": "https://schema.org",
+        "@type": "Quiz",
+        "name": question.title,
+        "url": format!("https://bitesized.info/question?topic={}&qid={}", question.topic, question.qid),
+        "about": {
+            "@type": "Thing",
+            "name": Topic::into_name(&question.topic),
+        },
+        "hasPart": [{
+            "@type": "Question",
+            "eduQuestionType": "Multiple choice",
+            "text": md_to_plain_text(&question.question, MAX_JSON_LD_TEXT_LENGTH),
+            "suggestedAnswer": question
+                .answers
+                .iter()
+                .map(|v| json!({
+                    "@type": "Answer",
+                    "text": md_to_plain_text(v.text(), MAX_JSON_LD_TEXT_LENGTH),
+                }))
+                .collect::<Vec<Value>>(),
+        }],
+    });
+
+    if let Some(updated) = question.updated {
+        quiz["dateModified"] = json!(updated.to_rfc3339());
+    }
+
+    if let Some(name) = question
+        .contributor
+        .as_ref()
+        .and_then(|v| v.name.as_deref())
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+    {
+        quiz["author"] = json!({ "@type": "Person", "name": name });
+    }
+
+    quiz
+}
+
+/// Serializes the value so that it cannot close the `<script>` element it is embedded in.
+fn escape_json(value: &Value) -> String {
+    value
+        .to_string()
+        .replace('<', r"\u003c")
+        .replace('>', r"\u003e")
+        .replace('&', r"\u0026")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    const INDEX_HTML: &str = r#"<html><head><title>Bite-sized learning</title></head>
+<body>
+  <div id="app"></div>
+  <script type="module" src="/src/main.ts"></script>
+</body></html>"#;
+
+    fn question(stage: PublishStage) -> Question {
+        Question::from_str(
+            r#"{"qid":"89yZBXJBa9t2LB6xfj46Rm","topic":"aws","question":"What is **1+1**?</script>","answers":[{"a":"`1`","e":"Wrong"},{"a":"2","e":"Explanation","c":true}],"title":"Simple & </script>","updated":"2024-10-31T08:39:17Z","contributor":{"name":"Jane"}}"#,
+        )
+        .unwrap()
+        .with_stage(stage)
+    }
+
+    #[test]
+    fn test_inject_question() {
+        let html = inject_question(INDEX_HTML.to_string(), &question(PublishStage::Published));
+
+        assert!(html.contains(
+            r#"<div id="app"><article class="ssr-question"><p>AWS</p><h1>Simple &amp; &lt;/script&gt;</h1>"#
+        ));
+        assert!(html.contains("<strong>1+1</strong>"));
+        assert!(html.contains("<li><p><code>1</code></p>\n</li>"));
+        assert!(!html.contains("Explanation"));
+
+        let (head, _) = html.split_once("</head>").unwrap();
+        let json_ld = head.split_once(r#"<script type="application/ld+json">"#).unwrap().1;
+        let json_ld = json_ld.strip_suffix("</script>\n").unwrap();
+        assert!(!json_ld.contains('<'));
+
+        let quiz = serde_json::from_str::<Value>(json_ld).unwrap();
+        assert_eq!(quiz["@type"], "Quiz");
+        assert_eq!(quiz["name"], "Simple & </script>");
+        assert_eq!(quiz["author"]["name"], "Jane");
+        assert_eq!(quiz["hasPart"][0]["suggestedAnswer"][1]["text"], "2");
+        assert!(!json_ld.contains("acceptedAnswer"));
+    }
+
+    #[test]
+    fn test_drafts_not_rendered() {
+        let html = inject_question(INDEX_HTML.to_string(), &question(PublishStage::Draft));
+        assert_eq!(html, INDEX_HTML);
+    }
+}
@@ -0,0 +1,22 @@
+# Run this script from the root of the project
+
+target=aarch64-unknown-linux-gnu
+region=us-east-1
+lambda=link-checker
+crate=link-checker
+
+RUSTFLAGS='-C target-feature=+crt-static' cargo build --release --target $target --package $crate
+cp ./target/$target/release/$crate ./bootstrap && zip proxy.zip bootstrap && rm bootstrap
+aws lambda update-function-code --region $region --function-name $lambda --zip-file fileb://proxy.zip
+rm proxy.zip
+
+# Available targets: 
+# x86_64-unknown-linux-gnu
+# x86_64-unknown-linux-musl
+# aarch64-unknown-linux-gnu
+# aarch64-unknown-linux-musl
+
+# schedule script - the lambda has no function URL and runs weekly
+# aws events put-rule --name link-checker-weekly --schedule-expression "rate(7 days)" --region "us-east-1"
+# aws lambda add-permission \--statement-id "AllowEventBridgeSchedule" \--action "lambda:InvokeFunction" \--principal "events.amazonaws.com" \--source-arn "arn:aws:events:us-east-1:512295225992:rule/link-checker-weekly" \--region "us-east-1" \--function-name link-checker
+# aws events put-targets --rule link-checker-weekly --targets "Id"="link-checker","Arn"="arn:aws:lambda:us-east-1:512295225992:function:link-checker" --region "us-east-1"
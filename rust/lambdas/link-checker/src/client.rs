@@ -0,0 +1,268 @@
+//! HTTP checks of individual links behind a trait, so that the audit can be tested offline.
+//!
+//! The links come from authors, so the client only connects to public IP addresses.
+//! IP hosts are checked before every request and redirect, and domain names are checked when they are resolved.
+
+use async_trait::async_trait;
+use bitie_types::links::extract_title;
+use reqwest::{
+    dns::{Addrs, Name, Resolve, Resolving},
+    header::CONTENT_TYPE,
+    redirect::Policy,
+    Url,
+};
+use std::{
+    error::Error,
+    fmt,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    sync::Arc,
+    time::Duration,
+};
+
+/// How long to wait for a single site.
+const TIMEOUT_SECS: u64 = 10;
+/// Redirects are common for documentation links, but long chains usually end on a generic page.
+const MAX_REDIRECTS: usize = 5;
+/// Identifies the checker in the access logs of the linked sites.
+const USER_AGENT: &str = "bitesized.info link checker (+https://bitesized.info/about)";
+/// The title is in the `<head>`, so there is no need to download the whole page.
+const MAX_BODY_BYTES: usize = 64 * 1024;
+
+/// The response to a link check.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkResponse {
+    /// HTTP status code after redirects.
+    pub status: u16,
+    /// The page title for successful HTML responses.
+    pub title: Option<String>,
+}
+
+/// Fetches links to find out if they still work.
+#[async_trait]
+pub trait LinkClient: Send + Sync {
+    /// Returns the HTTP status code and the page title or a short description of the network error.
+    async fn check(&self, url: &str) -> Result<LinkResponse, String>;
+}
+
+/// Checks links over HTTP with `GET` requests, reading only the start of HTML pages to find the title.
+pub struct HttpLinkClient {
+    client: reqwest::Client,
+}
+
+impl HttpLinkClient {
+    /// Creates a client with the checker's timeout, redirect policy and user agent.
+    pub fn new() -> Result<Self, reqwest::Error> {
+        let redirect = Policy::custom(|attempt| {
+            if attempt.previous().len() > MAX_REDIRECTS {
+                attempt.error("too many redirects")
+            } else if !is_public_url(attempt.url()) {
+                attempt.error(NonPublicAddress)
+            } else {
+                attempt.follow()
+            }
+        });
+
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(TIMEOUT_SECS))
+            .redirect(redirect)
+            .dns_resolver(Arc::new(PublicResolver))
+            .user_agent(USER_AGENT)
+            .build()?;
+        Ok(Self { client })
+    }
+}
+
+#[async_trait]
+impl LinkClient for HttpLinkClient {
+    async fn check(&self, url: &str) -> Result<LinkResponse, String> {
+        match Url::parse(url) {
+            Ok(v) if is_public_url(&v) => {}
+            Ok(_) => return Err(NonPublicAddress.to_string()),
+            Err(_) => return Err("invalid URL".to_string()),
+        }
+
+        let mut response = self.client.get(url).send().await.map_err(|e| describe_error(&e))?;
+        let status = response.status().as_u16();
+
+        let is_html = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("text/html"));
+        if !response.status().is_success() || !is_html {
+            return Ok(LinkResponse { status, title: None });
+        }
+
+        // a failure to read the body does not make the link broken, there is just no title
+        let mut body = Vec::with_capacity(MAX_BODY_BYTES);
+        while body.len() < MAX_BODY_BYTES {
+            match response.chunk().await {
+                Ok(Some(v)) => body.extend_from_slice(&v),
+                Ok(None) | Err(_) => break,
+            }
+        }
+
+        Ok(LinkResponse {
+            status,
+            title: extract_title(&String::from_utf8_lossy(&body)),
+        })
+    }
+}
+
+/// The link points at a loopback, private or other non-public address.
+#[derive(Debug)]
+struct NonPublicAddress;
+
+impl fmt::Display for NonPublicAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "non-public address")
+    }
+}
+
+impl Error for NonPublicAddress {}
+
+/// Resolves domain names with the system resolver and drops non-public addresses.
+/// Fails if no public addresses are left, so the client never connects to the internal network.
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addrs = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|v| is_public_ip(v.ip()))
+                .collect::<Vec<_>>();
+            if addrs.is_empty() {
+                return Err(NonPublicAddress.into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Returns false for non-HTTP URLs and URLs with non-public IP hosts.
+/// Domain names are checked by `PublicResolver`.
+fn is_public_url(url: &Url) -> bool {
+    if !matches!(url.scheme(), "http" | "https") {
+        return false;
+    }
+    match url.host() {
+        Some(url::Host::Domain(_)) => true,
+        Some(url::Host::Ipv4(v)) => is_public_ip(IpAddr::V4(v)),
+        Some(url::Host::Ipv6(v)) => is_public_ip(IpAddr::V6(v)),
+        None => false,
+    }
+}
+
+/// Returns false for loopback, private, link-local, shared, multicast and other special-purpose addresses.
+fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v) => is_public_ipv4(v),
+        IpAddr::V6(v) => match v.to_ipv4_mapped() {
+            Some(v) => is_public_ipv4(v),
+            None => is_public_ipv6(v),
+        },
+    }
+}
+
+fn is_public_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        || ip.is_documentation()
+        // 0.0.0.0/8, 100.64.0.0/10 shared, 198.18.0.0/15 benchmarking, 240.0.0.0/4 reserved
+        || a == 0
+        || (a == 100 && (64..128).contains(&b))
+        || (a == 198 && (18..20).contains(&b))
+        || a >= 240)
+}
+
+fn is_public_ipv6(ip: Ipv6Addr) -> bool {
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        || ip.is_unique_local()
+        || ip.is_unicast_link_local()
+        // the IPv4-compatible and NAT64 ranges can point at internal IPv4 addresses
+        || ip.segments()[..6] == [0; 6]
+        || ip.segments()[..2] == [0x64, 0xff9b]
+        // 2001:db8::/32 documentation
+        || ip.segments()[..2] == [0x2001, 0xdb8])
+}
+
+/// Returns a short description of the error for the reports, without the URL.
+fn describe_error(e: &reqwest::Error) -> String {
+    // the resolver and the redirect policy errors are wrapped by reqwest
+    let mut source = e.source();
+    while let Some(v) = source {
+        if v.is::<NonPublicAddress>() {
+            return NonPublicAddress.to_string();
+        }
+        source = v.source();
+    }
+
+    if e.is_timeout() {
+        "timeout".to_string()
+    } else if e.is_redirect() {
+        "too many redirects".to_string()
+    } else if e.is_connect() {
+        "connection failed".to_string()
+    } else {
+        "request failed".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_public_url() {
+        for url in [
+            "https://docs.aws.amazon.com/",
+            "http://93.184.215.14/",
+            "https://[2606:2800:21f:cb07:6820:80da:af6b:8b2c]/",
+        ] {
+            assert!(is_public_url(&Url::parse(url).unwrap()), "{url}");
+        }
+
+        for url in [
+            "ftp://docs.aws.amazon.com/",
+            "http://127.0.0.1:9001/",
+            "http://169.254.169.254/latest/meta-data/",
+            "http://10.0.0.1/",
+            "http://172.16.0.1/",
+            "http://192.168.1.1/",
+            "http://100.64.0.1/",
+            "http://0.0.0.0/",
+            "http://[::1]/",
+            "http://[fd00::1]/",
+            "http://[fe80::1]/",
+            "http://[::ffff:127.0.0.1]/",
+            "http://[64:ff9b::a9fe:a9fe]/",
+        ] {
+            assert!(!is_public_url(&Url::parse(url).unwrap()), "{url}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_non_public_links() {
+        let client = HttpLinkClient::new().unwrap();
+
+        // rejected before the request
+        assert_eq!(
+            client.check("http://169.254.169.254/").await,
+            Err("non-public address".to_string())
+        );
+
+        // rejected by the resolver, no network is needed for localhost
+        assert_eq!(
+            client.check("http://localhost:9001/").await,
+            Err("non-public address".to_string())
+        );
+    }
+}
@@ -0,0 +1,376 @@
+//! Audits the refresher links in all published questions.
+//!
+//! The lambda runs on a schedule. Each run collects the links from the Markdown of the questions,
+//! checks the ones not checked within `RECHECK_SECS` and reports the broken ones per question
+//! to their authors and to the moderators.
+//! The page titles found along the way are saved with the results and shown to learners as link labels.
+
+use bitie_types::{
+    links::{normalize_url, LinkStatus},
+    question::{Question, QuestionFormat},
+    topic::Topic,
+};
+use chrono::{DateTime, Duration, Utc};
+use client::{HttpLinkClient, LinkClient};
+use futures::{stream, StreamExt};
+use lambda_runtime::{Error, LambdaEvent};
+use lambda_utils::repository::{DdbRepository, LinkRepository, QuestionRepository};
+use std::collections::{BTreeSet, HashMap};
+use tracing::{error, info, warn};
+
+mod client;
+mod report;
+
+/// Links checked more recently than this are not checked again.
+/// It is a bit less than the weekly schedule so that every run rechecks the links from the previous one.
+const RECHECK_SECS: i64 = 6 * 24 * 3600;
+
+/// The max number of HTTP requests per run.
+/// The least recently checked links go first, the rest are checked by the next run.
+const MAX_CHECKS_PER_RUN: usize = 300;
+
+/// The number of links checked at the same time.
+const CONCURRENT_CHECKS: usize = 10;
+
+/// The checks still running after this time are dropped to leave time for the reports
+/// within the 15 min lambda timeout. Their links are checked by the next run.
+const CHECK_TIME_LIMIT_SECS: u64 = 10 * 60;
+
+/// Broken links in a single question.
+#[derive(Debug, PartialEq)]
+pub(crate) struct BrokenLinks {
+    pub topic: String,
+    pub qid: String,
+    pub title: String,
+    /// The email hash of the author.
+    pub author: Option<String>,
+    pub links: Vec<LinkStatus>,
+}
+
+/// The entry point for the lambda runtime. The scheduler event carries no useful data.
+pub async fn my_handler(_event: LambdaEvent<serde_json::Value>) -> Result<(), Error> {
+    let repo = DdbRepository::from_env().await;
+    let client = HttpLinkClient::new()?;
+
+    let broken = audit_links(&repo, &client, Utc::now()).await?;
+    report::send_reports(&repo, &broken).await;
+
+    Ok(())
+}
+
+/// Checks the links in all published questions and returns the questions with broken links.
+/// The results of the checks are saved in the repository.
+/// It is separate from `my_handler` to be testable without DDB and HTTP.
+async fn audit_links<R, C>(repo: &R, client: &C, now: DateTime<Utc>) -> Result<Vec<BrokenLinks>, Error>
+where
+    R: QuestionRepository + LinkRepository,
+    C: LinkClient,
+{
+    // collect the links from the full text of every published question
+    let mut questions = Vec::new();
+    for topic in Topic::TOPICS {
+        for item in repo.get_published_questions_by_topic(topic).await? {
+            match repo.get_question(&item.topic, &item.qid).await {
+                Ok(Some(v)) => {
+                    let links = question_links(&v);
+                    if !links.is_empty() {
+                        questions.push((v, links));
+                    }
+                }
+                Ok(None) => warn!("Question {}/{} not found", item.topic, item.qid),
+                Err(e) => error!("Failed to get question {}/{}: {:?}", item.topic, item.qid, e),
+            }
+        }
+    }
+    info!("Questions with links: {}", questions.len());
+
+    // the same link may be used in many questions, but it is checked once
+    let urls = questions
+        .iter()
+        .flat_map(|(_, links)| links.iter().cloned())
+        .collect::<BTreeSet<String>>();
+    info!("Unique links: {}", urls.len());
+
+    let mut statuses = HashMap::with_capacity(urls.len());
+    let mut stale = Vec::new();
+    for url in urls {
+        match repo.get_link(&url).await {
+            Ok(Some(v)) if now - v.checked < Duration::seconds(RECHECK_SECS) => {
+                statuses.insert(url, v);
+            }
+            Ok(v) => stale.push((v, url)),
+            Err(e) => {
+                error!("Failed to get link {url}: {:?}", e);
+                stale.push((None, url));
+            }
+        }
+    }
+
+    // never checked links go first, then the least recently checked ones
+    stale.sort_by_key(|(v, _)| v.as_ref().map(|v| v.checked));
+    info!("Links to check: {}", stale.len());
+
+    // slow sites are checked in parallel and the whole run is time-boxed
+    {
+        let checks = stream::iter(stale.iter().take(MAX_CHECKS_PER_RUN))
+            .map(|(previous, url)| check_link(client, url, previous.as_ref(), now))
+            .buffer_unordered(CONCURRENT_CHECKS)
+            .take_until(tokio::time::sleep(std::time::Duration::from_secs(
+                CHECK_TIME_LIMIT_SECS,
+            )));
+        let mut checks = std::pin::pin!(checks);
+        while let Some(link) = checks.next().await {
+            if let Err(e) = repo.save_link(&link).await {
+                error!("Failed to save link {}: {:?}", link.url, e);
+            }
+            statuses.insert(link.url.clone(), link);
+        }
+    }
+
+    // the previous result is better than nothing for the links left for the next run
+    let unchecked = stale.iter().filter(|(_, url)| !statuses.contains_key(url)).count();
+    if unchecked > 0 {
+        warn!("Links left for the next run: {unchecked}");
+    }
+    for (previous, url) in stale {
+        if let (false, Some(v)) = (statuses.contains_key(&url), previous) {
+            statuses.insert(url, v);
+        }
+    }
+
+    let broken = questions
+        .into_iter()
+        .filter_map(|(question, links)| {
+            let links = links
+                .iter()
+                .filter_map(|v| statuses.get(v))
+                .filter(|v| v.is_broken())
+                .cloned()
+                .collect::<Vec<LinkStatus>>();
+            if links.is_empty() {
+                return None;
+            }
+            Some(BrokenLinks {
+                topic: question.topic,
+                qid: question.qid,
+                title: question.title,
+                author: question.author,
+                links,
+            })
+        })
+        .collect::<Vec<BrokenLinks>>();
+    info!("Questions with broken links: {}", broken.len());
+
+    Ok(broken)
+}
+
+/// Returns the unique normalised HTTP links from the question, answers and explanations.
+fn question_links(question: &Question) -> Vec<String> {
+    let links = question
+        .clone()
+        .format(QuestionFormat::HtmlFull(None))
+        .refresher_links
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|v| normalize_url(&v.url))
+        .collect::<BTreeSet<String>>();
+    links.into_iter().collect()
+}
+
+/// Checks the link over HTTP and returns the result as it should be stored.
+/// The previous title is kept if the page is down, so that a short outage does not remove the label.
+async fn check_link<C: LinkClient>(
+    client: &C,
+    url: &str,
+    previous: Option<&LinkStatus>,
+    now: DateTime<Utc>,
+) -> LinkStatus {
+    let mut link = match client.check(url).await {
+        Ok(v) => LinkStatus {
+            url: url.to_string(),
+            http_status: Some(v.status),
+            error: None,
+            title: v.title,
+            checked: now,
+        },
+        Err(e) => LinkStatus {
+            url: url.to_string(),
+            http_status: None,
+            error: Some(e),
+            title: None,
+            checked: now,
+        },
+    };
+    info!("{url}: {:?} {:?} {:?}", link.http_status, link.error, link.title);
+
+    if link.is_broken() {
+        link.title = previous.and_then(|v| v.title.clone());
+    }
+
+    link
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use bitie_types::question::PublishStage;
+    use client::LinkResponse;
+    use lambda_utils::repository::MemoryRepository;
+    use std::{str::FromStr, sync::Mutex};
+
+    /// Returns preset statuses and records the requested URLs.
+    /// Unknown URLs are OK pages titled after the URL.
+    struct MockClient {
+        statuses: HashMap<&'static str, Result<u16, String>>,
+        requested: Mutex<Vec<String>>,
+        /// How long each response takes.
+        delay: std::time::Duration,
+    }
+
+    #[async_trait]
+    impl LinkClient for MockClient {
+        async fn check(&self, url: &str) -> Result<LinkResponse, String> {
+            self.requested.lock().unwrap().push(url.to_string());
+            tokio::time::sleep(self.delay).await;
+            match self.statuses.get(url) {
+                Some(v) => v.clone().map(|status| LinkResponse { status, title: None }),
+                None => Ok(LinkResponse {
+                    status: 200,
+                    title: Some(format!("Page at {url}")),
+                }),
+            }
+        }
+    }
+
+    fn question(qid: &str, question: &str, stage: PublishStage) -> Question {
+        Question::from_str(&format!(
+            r#"{{"qid":"{qid}","topic":"aws","question":"{question}","answers":[{{"a":"1","e":"See [docs](https://Docs.example.com/a#b)"}},{{"a":"2","e":null,"c":true}}],"title":"Simple question","updated":null,"author":"abc"}}"#
+        ))
+        .unwrap()
+        .with_stage(stage)
+    }
+
+    #[tokio::test]
+    async fn test_audit_links() {
+        let repo = MemoryRepository::with_questions(vec![
+            question(
+                "89yZBXJBa9t2LB6xfj46Rm",
+                "What is [this](https://dead.example.com/)? [Home](/about)",
+                PublishStage::Published,
+            ),
+            question(
+                "NgGdoZov4T6jV46ty4JUX6",
+                "What is [1+1](https://ok.example.com/)?",
+                PublishStage::Published,
+            ),
+            question(
+                "3RuWxwkgBgpWk6ZUARaZx6",
+                "What is [that](https://draft.example.com/)?",
+                PublishStage::Draft,
+            ),
+        ]);
+        let client = MockClient {
+            statuses: HashMap::from([
+                ("https://dead.example.com/", Ok(404)),
+                ("https://docs.example.com/a", Err("timeout".to_string())),
+            ]),
+            requested: Mutex::new(Vec::new()),
+            delay: std::time::Duration::ZERO,
+        };
+        let now = Utc::now();
+
+        let broken = audit_links(&repo, &client, now).await.unwrap();
+
+        // shared links are checked once, relative links and drafts are skipped
+        client.requested.lock().unwrap().sort();
+        assert_eq!(
+            *client.requested.lock().unwrap(),
+            vec![
+                "https://dead.example.com/",
+                "https://docs.example.com/a",
+                "https://ok.example.com/"
+            ]
+        );
+        assert_eq!(broken.len(), 2);
+        assert_eq!(broken[0].qid, "89yZBXJBa9t2LB6xfj46Rm");
+        assert_eq!(broken[0].links.len(), 2);
+        assert_eq!(broken[1].qid, "NgGdoZov4T6jV46ty4JUX6");
+        assert_eq!(broken[1].links.len(), 1);
+        assert_eq!(broken[1].links[0].error.as_deref(), Some("timeout"));
+
+        let saved = repo.get_link("https://dead.example.com/").await.unwrap().unwrap();
+        assert_eq!(saved.http_status, Some(404));
+        assert_eq!(saved.checked, now);
+        let saved = repo.get_link("https://ok.example.com/").await.unwrap().unwrap();
+        assert_eq!(saved.title.as_deref(), Some("Page at https://ok.example.com/"));
+
+        // recently checked links are not checked again, but are still reported
+        client.requested.lock().unwrap().clear();
+        let broken_again = audit_links(&repo, &client, now + Duration::seconds(60)).await.unwrap();
+        assert!(client.requested.lock().unwrap().is_empty());
+        assert_eq!(broken_again, broken);
+
+        // stale links are rechecked
+        audit_links(&repo, &client, now + Duration::seconds(RECHECK_SECS))
+            .await
+            .unwrap();
+        assert_eq!(client.requested.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_audit_links_time_limit() {
+        let repo = MemoryRepository::with_questions(vec![question(
+            "89yZBXJBa9t2LB6xfj46Rm",
+            "What is [this](https://dead.example.com/)?",
+            PublishStage::Published,
+        )]);
+        let now = Utc::now();
+        let previous = LinkStatus {
+            url: "https://dead.example.com/".to_string(),
+            http_status: Some(404),
+            error: None,
+            title: None,
+            checked: now - Duration::seconds(RECHECK_SECS),
+        };
+        repo.save_link(&previous).await.unwrap();
+
+        // every site hangs, but the run stops in time and reports the previous results
+        let client = MockClient {
+            statuses: HashMap::new(),
+            requested: Mutex::new(Vec::new()),
+            delay: std::time::Duration::from_secs(CHECK_TIME_LIMIT_SECS + 1),
+        };
+        let broken = audit_links(&repo, &client, now).await.unwrap();
+        assert_eq!(client.requested.lock().unwrap().len(), 2);
+        assert_eq!(broken.len(), 1);
+        assert_eq!(broken[0].links, vec![previous.clone()]);
+        assert_eq!(repo.get_link(&previous.url).await.unwrap(), Some(previous));
+        assert!(repo.get_link("https://docs.example.com/a").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_check_link_keeps_title() {
+        let client = MockClient {
+            statuses: HashMap::from([("https://down.example.com/", Ok(503))]),
+            requested: Mutex::new(Vec::new()),
+            delay: std::time::Duration::ZERO,
+        };
+        let now = Utc::now();
+        let previous = LinkStatus {
+            url: "https://down.example.com/".to_string(),
+            http_status: Some(200),
+            error: None,
+            title: Some("Docs".to_string()),
+            checked: now - Duration::seconds(RECHECK_SECS),
+        };
+
+        let link = check_link(&client, "https://down.example.com/", Some(&previous), now).await;
+        assert_eq!(link.http_status, Some(503));
+        assert_eq!(link.title.as_deref(), Some("Docs"));
+
+        // a working page replaces the title
+        let link = check_link(&client, "https://up.example.com/", Some(&previous), now).await;
+        assert_eq!(link.title.as_deref(), Some("Page at https://up.example.com/"));
+    }
+}
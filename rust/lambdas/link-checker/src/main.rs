@@ -0,0 +1,20 @@
+use lambda_runtime::{service_fn, Error, Runtime};
+use link_checker::my_handler;
+use tracing_subscriber::filter::LevelFilter;
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    // required to enable CloudWatch error logging by the runtime
+    tracing_subscriber::fmt()
+        .without_time()
+        .with_max_level(LevelFilter::INFO)
+        .with_ansi(false)
+        .init();
+
+    let func = service_fn(my_handler);
+    let runtime = Runtime::new(func);
+    #[cfg(not(debug_assertions))]
+    let runtime = runtime.layer(lambda_runtime::layers::TracingLayer::new());
+    runtime.run().await?;
+    Ok(())
+}
@@ -0,0 +1,158 @@
+//! Emails about broken links to the question authors and the moderators.
+
+use crate::BrokenLinks;
+use bitie_types::links::LinkStatus;
+use lambda_utils::repository::UserRepository;
+use std::collections::BTreeMap;
+use tracing::{error, info, warn};
+
+/// The page where authors can turn off the notifications about their questions.
+const OPT_OUT_URL: &str = "https://bitesized.info/subscription";
+
+/// Sends each author a list of broken links in their questions, unless they opted out of link reports,
+/// and a summary of all broken links to the moderators.
+/// All errors are logged inside the function because the link statuses are already saved.
+pub(crate) async fn send_reports<R: UserRepository>(repo: &R, broken: &[BrokenLinks]) {
+    if broken.is_empty() {
+        info!("No broken links");
+        return;
+    }
+
+    let mut by_author = BTreeMap::<&str, Vec<&BrokenLinks>>::new();
+    for item in broken {
+        match &item.author {
+            Some(v) => by_author.entry(v.as_str()).or_default().push(item),
+            None => warn!("No author for {}/{}", item.topic, item.qid),
+        }
+    }
+
+    for (author, items) in by_author {
+        let user = match repo.get_user_by_email_hash(author).await {
+            Ok(Some(v)) => v,
+            Ok(None) => {
+                warn!("No user record for author {author}");
+                continue;
+            }
+            Err(e) => {
+                error!("Failed to get author {author}: {:?}", e);
+                continue;
+            }
+        };
+
+        if user.links_opt_out == Some(true) {
+            info!("Author {author} opted out of link reports");
+            continue;
+        }
+
+        let (subject, body) = render_author_report(&items);
+        lambda_utils::email::send_text_email(&user.email, &subject, &body).await;
+    }
+
+    let (subject, body) = render_mods_report(broken);
+    lambda_utils::email::send_text_email(&lambda_utils::email::mods_email(), &subject, &body).await;
+}
+
+/// Returns the subject and the plain text body of the email to the author.
+fn render_author_report(items: &[&BrokenLinks]) -> (String, String) {
+    let subject = match items.len() {
+        1 => "Broken links in your question".to_string(),
+        v => format!("Broken links in {v} of your questions"),
+    };
+
+    let items = items
+        .iter()
+        .map(|v| render_item(v))
+        .collect::<Vec<String>>()
+        .join("\n\n---\n\n");
+
+    let body = format!(
+        "Hi,\n\nSome links in your questions at bitesized.info no longer work:\n\n---\n\n{items}\n\n---\n\nYou can update the questions by following the links above.\n\nTo stop these emails, turn off broken link reports at {OPT_OUT_URL}\n"
+    );
+
+    (subject, body)
+}
+
+/// Returns the subject and the plain text body of the summary for the moderators.
+fn render_mods_report(broken: &[BrokenLinks]) -> (String, String) {
+    let links = broken.iter().map(|v| v.links.len()).sum::<usize>();
+    let subject = format!("Link audit: {links} broken links in {} questions", broken.len());
+    let body = broken
+        .iter()
+        .map(render_item)
+        .collect::<Vec<String>>()
+        .join("\n\n---\n\n");
+
+    (subject, body)
+}
+
+/// Lists the broken links under the title and the URL of the question.
+fn render_item(item: &BrokenLinks) -> String {
+    let links = item
+        .links
+        .iter()
+        .map(|v| format!("- {} ({})", v.url, describe_status(v)))
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    format!(
+        "{}\nhttps://bitesized.info/question?topic={}&qid={}\n\n{links}",
+        item.title, item.topic, item.qid
+    )
+}
+
+/// Returns the HTTP status code or the error, e.g. `404` or `timeout`.
+fn describe_status(link: &LinkStatus) -> String {
+    match (link.http_status, &link.error) {
+        (Some(v), _) => v.to_string(),
+        (None, Some(v)) => v.clone(),
+        (None, None) => "no response".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn broken(qid: &str, urls: &[(&str, Option<u16>)]) -> BrokenLinks {
+        BrokenLinks {
+            topic: "aws".to_string(),
+            qid: qid.to_string(),
+            title: "Simple question".to_string(),
+            author: Some("abc".to_string()),
+            links: urls
+                .iter()
+                .map(|(url, http_status)| LinkStatus {
+                    url: url.to_string(),
+                    http_status: *http_status,
+                    error: http_status.is_none().then(|| "timeout".to_string()),
+                    title: None,
+                    checked: Utc::now(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_render_reports() {
+        let b1 = broken(
+            "89yZBXJBa9t2LB6xfj46Rm",
+            &[("https://a.com/", Some(404)), ("https://b.com/", None)],
+        );
+        let b2 = broken("NgGdoZov4T6jV46ty4JUX6", &[("https://c.com/", Some(500))]);
+
+        let (subject, body) = render_author_report(&[&b1]);
+        assert_eq!(subject, "Broken links in your question");
+        assert!(body.contains(
+            "Simple question\nhttps://bitesized.info/question?topic=aws&qid=89yZBXJBa9t2LB6xfj46Rm\n\n- https://a.com/ (404)\n- https://b.com/ (timeout)"
+        ));
+        assert!(body.contains(&format!("turn off broken link reports at {OPT_OUT_URL}")));
+
+        let (subject, _) = render_author_report(&[&b1, &b2]);
+        assert_eq!(subject, "Broken links in 2 of your questions");
+
+        let (subject, body) = render_mods_report(&[b1, b2]);
+        assert_eq!(subject, "Link audit: 3 broken links in 2 questions");
+        assert!(body.contains("- https://c.com/ (500)"));
+    }
+}
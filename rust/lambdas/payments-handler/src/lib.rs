@@ -0,0 +1,170 @@
+use aws_lambda_events::{
+    http::method::Method,
+    lambda_function_urls::{LambdaFunctionUrlRequest, LambdaFunctionUrlResponse},
+};
+use bitie_types::payments::{PaymentProcessorSecrets, QuestionDonation, STRIPE_SECRETS_ENV_VAR};
+use lambda_runtime::{Error, LambdaEvent};
+use lambda_utils::{
+    error::ApiError,
+    request::{RequestExt, Router, ANY_PATH},
+    response,
+};
+use tracing::info;
+
+mod checkout;
+
+/// The entry point for the lambda runtime and the local dev server.
+pub async fn my_handler(event: LambdaEvent<LambdaFunctionUrlRequest>) -> Result<LambdaFunctionUrlResponse, Error> {
+    if let Some(v) = response::preflight(&event.payload) {
+        return Ok(v);
+    }
+
+    let request_headers = event.payload.headers.clone();
+    let response = handle_request(event.payload).await?;
+    Ok(response::finalize(&request_headers, response))
+}
+
+/// Processes the request.
+async fn handle_request(request: LambdaFunctionUrlRequest) -> Result<LambdaFunctionUrlResponse, Error> {
+    if let Err(e) = Router::new().route(Method::POST, ANY_PATH, ()).resolve(&request) {
+        return e.into_response();
+    }
+
+    // this request must have a body
+    let order_details = match request.json_body::<QuestionDonation>() {
+        Ok(v) => v,
+        Err(e) => return e.into_response(),
+    };
+
+    // get the name of the secret from the environment
+    let secrets = match get_secrets().await {
+        Some(v) => v,
+        None => {
+            info!("Missing payment processor secrets");
+            return ApiError::Internal("Server misconfiguration.".to_string()).into_response();
+        }
+    };
+
+    info!("Order details: {:?}", order_details);
+
+    // attempt to get the checkout URL from the payment provider and return it as text
+    match checkout::get_checkout_url(order_details, secrets).await {
+        Some(v) => lambda_utils::text_response(Some(v), 200),
+        None => {
+            info!("Failed to get the checkout URL");
+            ApiError::Upstream("Failed to get the checkout URL".to_string()).into_response()
+        }
+    }
+}
+
+/// Returns Stripe Key and Stripe Secret from AWS Secrets Manager or None if the secrets cannot be retrieved.
+/// Errors are logged inside the function.
+async fn get_secrets() -> Option<PaymentProcessorSecrets> {
+    // It is logical to return Result, but the errors are handled inside the function, so Option is easier.
+
+    // get the name of the secret from the environment
+    let secret_arn = match std::env::var(STRIPE_SECRETS_ENV_VAR) {
+        Ok(v) => v.trim().to_string(),
+        Err(e) => {
+            info!("Missing `{STRIPE_SECRETS_ENV_VAR}` env var with the ARN of the secret containing Stripe keys: {e}");
+            return None;
+        }
+    };
+
+    let asm = aws_sdk_secretsmanager::Client::new(&aws_config::load_from_env().await);
+
+    let response = match asm.get_secret_value().secret_id(secret_arn).send().await {
+        Ok(v) => v,
+        Err(e) => {
+            info!("Failed to get Stripe keys: {:?}", e);
+            return None;
+        }
+    };
+
+    let secrets = match response.secret_string() {
+        Some(v) => match serde_json::from_str::<PaymentProcessorSecrets>(v) {
+            Ok(v) => v,
+            Err(e) => {
+                info!("Failed to parse the secret: {:?}", e);
+                return None;
+            }
+        },
+
+        None => {
+            info!("Secret is not a string");
+            return None;
+        }
+    };
+
+    Some(secrets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitie_types::question::ContributorProfile;
+    use test_log::test;
+
+    #[test(tokio::test)]
+    async fn test_get_secrets() {
+        let secrets = get_secrets().await;
+        assert!(secrets.is_some());
+    }
+
+    #[test(tokio::test)]
+    async fn test_get_checkout_url() {
+        let secrets = get_secrets().await.unwrap();
+
+        let full_order_details = QuestionDonation {
+            contributor: Some(ContributorProfile {
+                name: Some("Consulting Solutions".to_string()),
+                url: Some("https://example.com/consul-sol".to_string()),
+                img_url: Some("https://example.com/consul-sol.png".to_string()),
+                about: Some("We provide consulting solutions".to_string()),
+            }),
+            qty: 1,
+            cancel_url: "https://example.com/retry".to_string(),
+            success_url: "https://example.com/thankyou".to_string(),
+            topics: Some("AWS Rust".to_string()),
+        };
+
+        let order_details = full_order_details.clone();
+
+        let url = checkout::get_checkout_url(order_details, secrets.clone()).await;
+        assert!(url.is_some(), "Full input URL");
+        println!("Full input URL: {}", url.unwrap());
+
+        let order_details = QuestionDonation {
+            contributor: None,
+            ..full_order_details.clone()
+        };
+
+        let url = checkout::get_checkout_url(order_details, secrets.clone()).await;
+        assert!(url.is_some(), "No contrib URL");
+        println!("No contrib URL: {}", url.unwrap());
+
+        let order_details = QuestionDonation {
+            contributor: None,
+            topics: None,
+            ..full_order_details.clone()
+        };
+
+        let url = checkout::get_checkout_url(order_details, secrets.clone()).await;
+        assert!(url.is_some(), "No contrib, no topics URL");
+        println!("No contrib, no topics URL: {}", url.unwrap());
+
+        // invalid input tests
+        assert!(
+            checkout::get_checkout_url(
+                QuestionDonation {
+                    qty: 21,
+                    ..full_order_details.clone()
+                },
+                secrets.clone(),
+            )
+            .await
+            .is_none(),
+            "Qty == 21"
+        );
+    }
+}
@@ -16,5 +16,10 @@ rm proxy.zip
 # aarch64-unknown-linux-gnu
 # aarch64-unknown-linux-musl
 
+# answer links in emails are signed with a secret shared with the email sender
+# --environment replaces all variables, so include the existing ones
+# new questions for review are emailed to BITIE_MODS_EMAIL, if set
+# aws lambda update-function-configuration --region $region --function-name $lambda --environment "Variables={ANSWER_TOKEN_SECRET=...,BITIE_MODS_EMAIL=...}"
+
 # permissions script
 # aws lambda add-permission \--statement-id "AllowCloudFrontServicePrincipal" \--action "lambda:InvokeFunctionUrl" \--principal "cloudfront.amazonaws.com" \--source-arn "arn:aws:cloudfront::512295225992:distribution/E1EOR95K1Z2GQD" \--region "us-east-1" \--function-name question-handler
\ No newline at end of file
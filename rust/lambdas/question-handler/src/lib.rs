@@ -0,0 +1,840 @@
+use aws_lambda_events::{
+    http::method::Method,
+    lambda_function_urls::{LambdaFunctionUrlRequest, LambdaFunctionUrlResponse},
+};
+use bitie_types::{
+    answer_token::{verify_answer_token, ANSWER_TOKEN_PARAM},
+    ddb::fields,
+    embed::{validate_host, EMBED_PARAM},
+    jwt::JwtUser,
+    links::normalize_url,
+    offline::{OfflineAnswer, OfflineSyncResult, MAX_OFFLINE_SYNC_ANSWERS, OFFLINE_BUNDLE_TTL_SECS},
+    question::{PublishStage, Question, QuestionFormat},
+    topic::Topic,
+    user::{AnswerStatus, AskedQuestion},
+};
+use chrono::{DateTime, Duration, Utc};
+use lambda_runtime::{Error, LambdaEvent};
+use lambda_utils::{
+    error::ApiError,
+    rate_limit::{self, DdbRateLimiter, RateLimit, RateLimiter},
+    repository::{DdbRepository, LinkRepository, QuestionRepository, UserRepository},
+    request::{RequestExt, Router, ANY_PATH},
+    response::{self, CacheControl, ResponseBuilder},
+    ANSWER_TOKEN_SECRET_ENV_VAR,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+};
+use tracing::{error, info, warn};
+
+/// The entry point for the lambda runtime and the local dev server.
+pub async fn my_handler(event: LambdaEvent<LambdaFunctionUrlRequest>) -> Result<LambdaFunctionUrlResponse, Error> {
+    if let Some(v) = response::preflight(&event.payload) {
+        return Ok(v);
+    }
+
+    let request_headers = event.payload.headers.clone();
+    let repo = DdbRepository::from_env().await;
+    let limiter = DdbRateLimiter::from_env().await;
+    let answer_secret = lambda_utils::answer_token_secret();
+    let response = handle_request(event.payload, &repo, &limiter, answer_secret.as_deref()).await?;
+    Ok(response::finalize(&request_headers, response))
+}
+
+/// The actions supported by this handler.
+#[derive(Debug, Clone, Copy)]
+enum Action {
+    /// Returns the question in HTML and records the answer, if any.
+    Get,
+    /// Saves the question from the body, or returns it in markdown for editing if there is no body.
+    Save,
+    /// Records a batch of answers given in offline mode.
+    Sync,
+}
+
+/// Processes the request against the given storage and rate limiter.
+/// `answer_secret` is the secret for answer tokens from emails. The tokens are ignored if it is None.
+/// It is separate from `my_handler` to be testable without DDB.
+async fn handle_request<R, L>(
+    request: LambdaFunctionUrlRequest,
+    repo: &R,
+    limiter: &L,
+    answer_secret: Option<&[u8]>,
+) -> Result<LambdaFunctionUrlResponse, Error>
+where
+    R: QuestionRepository + UserRepository + LinkRepository,
+    L: RateLimiter,
+{
+    let router = Router::new()
+        .route(Method::GET, ANY_PATH, Action::Get)
+        .route(Method::PUT, ANY_PATH, Action::Save)
+        .route(Method::POST, ANY_PATH, Action::Sync);
+    let action = match router.resolve(&request) {
+        Ok(v) => v,
+        Err(e) => return e.into_response(),
+    };
+
+    // the user may be authenticated with an email inside the token
+    let jwt_user = request.jwt_user();
+    // answers param is optional
+    let answers = match lambda_utils::url_list_to_vec(request.query_string_parameters.get(fields::ANSWERS)) {
+        Some(v) => Some(v.iter().filter_map(|v| v.parse::<usize>().ok()).collect()),
+        None => {
+            info!("No answers param in the query string");
+            None
+        }
+    };
+    // the host of the site with the embedded widget, if any
+    let embed_host = request.query_param(EMBED_PARAM).and_then(validate_host);
+
+    match action {
+        Action::Get => {
+            // topic and qid are required for get queries
+            let (topic, qid) = match (request.required_topic(), request.required_qid()) {
+                (Ok(topic), Ok(qid)) => (topic, qid),
+                (Err(e), _) | (_, Err(e)) => return e.into_response(),
+            };
+
+            // get the question from the DB
+            let question = repo.get_question(&topic, &qid).await;
+
+            let question = match question {
+                Ok(Some(v)) => v,
+                Ok(None) => {
+                    info!("No question found for topic: {topic}");
+                    return ApiError::NotFound("No question found".to_string()).into_response();
+                }
+                Err(e) => return ApiError::from(e).into_response(),
+            };
+
+            // learners answering from an email have a signed token instead of the JWT
+            let jwt_user = match jwt_user {
+                Some(v) => Some(v),
+                None => {
+                    let token = request.query_param(ANSWER_TOKEN_PARAM);
+                    answer_token_user(repo, answer_secret, token, &topic, &qid, &answers).await
+                }
+            };
+
+            // update the user answers if the user is known
+            // the logic to update or not is inside the function
+            let status = question.answer_status(answers.as_deref(), Utc::now());
+            record_answer(repo, &jwt_user, &question, &status).await;
+
+            // answers from the widget embedded on another site are also counted per site
+            // the host is sent by the widget and can be faked, so the counting is limited per IP
+            if let (Some(host), Some(_)) = (&embed_host, &answers) {
+                if question.stage == PublishStage::Published {
+                    let rate_limit_keys = request
+                        .request_context
+                        .http
+                        .source_ip
+                        .iter()
+                        .map(|v| format!("embed/ip/{v}"))
+                        .collect::<Vec<String>>();
+                    match rate_limit::enforce(limiter, &rate_limit_keys, &RateLimit::EMBED_STATS).await {
+                        Ok(()) => {
+                            if let Err(e) = repo.increment_embed_stats(&topic, &qid, host, &status).await {
+                                error!("Failed to update embed stats: {:?}", e);
+                            }
+                        }
+                        Err(_) => info!("Embed answer not counted"),
+                    }
+                }
+            }
+
+            // no answers means initial question display and no explanations
+            let response_format = if answers.is_some() {
+                QuestionFormat::HtmlFull(answers.clone())
+            } else {
+                QuestionFormat::HtmlShort
+            };
+
+            // only anonymous views of published questions are the same for everyone
+            // the rest either depend on the user or on the answers
+            let cache_control = if jwt_user.is_none() && answers.is_none() && question.stage == PublishStage::Published
+            {
+                CacheControl::Public {
+                    max_age: 60,
+                    s_maxage: 300,
+                }
+            } else {
+                CacheControl::NoStore
+            };
+
+            let question = with_link_titles(repo, question.format(response_format)).await;
+
+            match ResponseBuilder::new(200).json(&question) {
+                Ok(v) => v
+                    .cache_control(cache_control)
+                    .vary(lambda_utils::X_BITIE_TOKEN_HEADER)
+                    .etag_from_body()
+                    .build(),
+                Err(e) => e.into_response(),
+            }
+        }
+
+        Action::Save => {
+            // add / edit a question, get as markdown
+
+            // must be an authenticated user
+            let jwt_user = match request.required_jwt_user() {
+                Ok(v) => v,
+                Err(e) => return e.into_response(),
+            };
+
+            match request.text_body() {
+                // save the question in the DB if there is a body
+                Ok(body) => {
+                    // info!("Received question: {body}");
+                    let q = match Question::from_str(body) {
+                        // add the email hash of the current user and update the timestamp
+                        Ok(v) => v
+                            .with_author(&jwt_user.email_hash) // defaults to the current user
+                            .with_updated()
+                            .with_stage(PublishStage::Draft), // always reset it to Draft in save, other stages are set elsewhere
+                        Err(_) => return ApiError::Validation("Invalid question".to_string()).into_response(),
+                    };
+
+                    // every save notifies the moderators by email
+                    let rate_limit_keys = [format!("save/user/{}", jwt_user.email_hash)];
+                    if let Err(e) = rate_limit::enforce(limiter, &rate_limit_keys, &RateLimit::QUESTION_SAVE).await {
+                        return e.into_response();
+                    }
+
+                    // DDB returns an error if the author does not match
+                    match repo.save_question(&q).await {
+                        Ok(_) => {
+                            notify_moderators(&q).await;
+                            lambda_utils::json_response(Some(&q.format(QuestionFormat::HtmlShort)), 200)
+                        }
+                        Err(e) => ApiError::from(e).into_response(),
+                    }
+                }
+                // return the question in markdown format if there is no body
+                Err(_) => {
+                    let (topic, qid) = match (request.required_topic(), request.required_qid()) {
+                        (Ok(topic), Ok(qid)) => (topic, qid),
+                        (Err(e), _) | (_, Err(e)) => return e.into_response(),
+                    };
+                    // return the question in markdown format if the author matches
+                    let question = match repo.get_question(&topic, &qid).await {
+                        Ok(Some(v)) => v,
+                        Ok(None) => return ApiError::NotFound("No question found".to_string()).into_response(),
+                        Err(e) => return ApiError::from(e).into_response(),
+                    };
+
+                    if question.author.as_ref() == Some(&jwt_user.email_hash) {
+                        lambda_utils::json_response(Some(&question.format(QuestionFormat::MarkdownFull)), 200)
+                    } else {
+                        warn!("Forbidden (email hash mismatch): {:?}", jwt_user);
+                        ApiError::Forbidden("Only the author can edit the question".to_string()).into_response()
+                    }
+                }
+            }
+        }
+
+        Action::Sync => {
+            // answers can only be synced to the history of a known user
+            let jwt_user = match request.required_jwt_user() {
+                Ok(v) => v,
+                Err(e) => return e.into_response(),
+            };
+
+            let offline_answers = match request.json_body::<Vec<OfflineAnswer>>() {
+                Ok(v) => v,
+                Err(e) => return e.into_response(),
+            };
+            if offline_answers.len() > MAX_OFFLINE_SYNC_ANSWERS {
+                return ApiError::Validation(format!("Too many answers. {MAX_OFFLINE_SYNC_ANSWERS} allowed"))
+                    .into_response();
+            }
+
+            let rate_limit_keys = [format!("offline-sync/user/{}", jwt_user.email_hash)];
+            if let Err(e) = rate_limit::enforce(limiter, &rate_limit_keys, &RateLimit::OFFLINE_SYNC).await {
+                return e.into_response();
+            }
+
+            let result = sync_offline_answers(repo, &jwt_user, offline_answers, Utc::now()).await;
+            lambda_utils::json_response(Some(&result), 200)
+        }
+    }
+}
+
+/// Records the answers given in offline mode as if they were given online at the time they were queued.
+/// The answers are scored again against the current version of the question.
+/// Answers for questions already answered within the bundle lifetime, for unknown or unpublished questions,
+/// or dated outside of the bundle lifetime are ignored.
+/// All errors are logged inside the function.
+async fn sync_offline_answers<R>(
+    repo: &R,
+    jwt_user: &JwtUser,
+    offline_answers: Vec<OfflineAnswer>,
+    now: DateTime<Utc>,
+) -> OfflineSyncResult
+where
+    R: QuestionRepository + UserRepository,
+{
+    let oldest = now - Duration::seconds(OFFLINE_BUNDLE_TTL_SECS);
+
+    // the same queue may be synced more than once, e.g. after a network error,
+    // and the timestamps come from the client, so a question counts once per bundle lifetime
+    let mut recorded = match repo.get_question_history(&jwt_user.email).await {
+        Ok(v) => v
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|v| !matches!(v.status, AnswerStatus::Asked(_)) && *v.status.timestamp() >= oldest)
+            .map(|v| (v.topic, v.qid))
+            .collect::<HashSet<(String, String)>>(),
+        Err(e) => {
+            error!("Failed to get question history for {}: {:?}", jwt_user.email, e);
+            return OfflineSyncResult {
+                synced: 0,
+                ignored: offline_answers.len(),
+            };
+        }
+    };
+
+    let mut result = OfflineSyncResult::default();
+    let jwt_user = Some(jwt_user.clone());
+
+    for offline_answer in offline_answers {
+        let OfflineAnswer { asked, answers } = offline_answer;
+
+        let ts = *asked.status.timestamp();
+        if ts > now || ts < oldest {
+            info!("Offline answer outside of the bundle lifetime: {asked}");
+            result.ignored += 1;
+            continue;
+        }
+
+        if !recorded.insert((asked.topic.clone(), asked.qid.clone())) {
+            info!("Offline answer already synced: {asked}");
+            result.ignored += 1;
+            continue;
+        }
+
+        let question = match repo.get_question(&asked.topic, &asked.qid).await {
+            Ok(Some(v)) if v.stage == PublishStage::Published => v,
+            Ok(_) => {
+                info!("Offline answer for an unknown or unpublished question: {asked}");
+                result.ignored += 1;
+                continue;
+            }
+            Err(e) => {
+                error!("Failed to get question for offline answer {asked}: {:?}", e);
+                result.ignored += 1;
+                continue;
+            }
+        };
+
+        // the status from the learner's device is not trusted
+        let status = question.answer_status(Some(&answers), ts);
+        record_answer(repo, &jwt_user, &question, &status).await;
+        result.synced += 1;
+    }
+
+    info!("Synced offline answers: {:?}", result);
+    result
+}
+
+/// Returns the user from a signed answer token, e.g. from an answer link in an email.
+/// A token is accepted only once, so repeated clicks do not rewrite the user's history.
+/// The token is rejected if it cannot be recorded as used.
+/// All errors are logged inside the function.
+async fn answer_token_user<R>(
+    repo: &R,
+    secret: Option<&[u8]>,
+    token: Option<&str>,
+    topic: &str,
+    qid: &str,
+    answers: &Option<Vec<usize>>,
+) -> Option<JwtUser>
+where
+    R: UserRepository,
+{
+    let (token, answers) = (token?, answers.as_ref()?);
+    let secret = match secret {
+        Some(v) => v,
+        None => {
+            warn!("Answer token received, but `{ANSWER_TOKEN_SECRET_ENV_VAR}` is not set");
+            return None;
+        }
+    };
+
+    let verified = verify_answer_token(secret, token, topic, qid, answers, Utc::now())?;
+    let email_hash = verified.email_hash;
+
+    let user = match repo.get_user_by_email_hash(&email_hash).await {
+        Ok(Some(v)) => v,
+        Ok(None) => {
+            warn!("No user for answer token: {email_hash}");
+            return None;
+        }
+        Err(e) => {
+            error!("Failed to get user for answer token {email_hash}: {:?}", e);
+            return None;
+        }
+    };
+
+    // the token is only used up once it is known to be good for a user
+    match repo.use_one_time_token(&verified.signature, verified.expires).await {
+        Ok(true) => Some(JwtUser {
+            email: user.email,
+            email_hash,
+        }),
+        Ok(false) => {
+            info!("Answer token already used: {email_hash}/{topic}/{qid}");
+            None
+        }
+        Err(e) => {
+            error!("Failed to record answer token as used {email_hash}: {:?}", e);
+            None
+        }
+    }
+}
+
+/// Adds the answer to the user's history and updates the question stats.
+///
+/// The stats are not updated if the user is the author.
+/// All errors are logged inside the function.
+async fn record_answer<R>(repo: &R, jwt_user: &Option<JwtUser>, question: &Question, status: &AnswerStatus)
+where
+    R: QuestionRepository + UserRepository,
+{
+    let is_author = match jwt_user {
+        Some(jwt_user) => Some(&jwt_user.email_hash) == question.author.as_ref(),
+        None => false,
+    };
+
+    // update the user history, e.g. `aws/9GjFyqQMTmpDJBYgtxoaBA/2024-10-31T20:08:47Zi`
+    match jwt_user {
+        Some(jwt_user) => {
+            if is_author {
+                // REMOVE THESE LINES AFTER TESTING
+                info!("User is the author - TEMPORARILY updating user history for testing");
+            } else {
+                info!("Updating user history: {}", jwt_user.email);
+            }
+
+            let asked_question = AskedQuestion {
+                topic: question.topic.clone(),
+                qid: question.qid.clone(),
+                status: status.clone(),
+            };
+
+            if let Err(e) = repo.add_asked_question(&jwt_user.email, &asked_question).await {
+                error!("Failed to update user answers {}: {:?}", jwt_user.email, e);
+            }
+        }
+        None => info!("Unregistered user - NOT updating user history"),
+    }
+
+    // do not update stats if the user is the author
+    if is_author {
+        info!("User is the author - NOT updating question stats");
+        return;
+    }
+
+    if let Err(e) = repo
+        .increment_answer_stats(&question.topic, &question.qid, status)
+        .await
+    {
+        error!("Failed to update question stats: {:?}", e);
+    }
+}
+
+/// Adds the page titles cached by the link checker to the refresher links.
+/// Errors are logged and the links are returned without titles.
+async fn with_link_titles<R: LinkRepository>(repo: &R, mut question: Question) -> Question {
+    let links = match question.refresher_links.as_mut() {
+        Some(v) => v,
+        None => return question,
+    };
+
+    let urls = links
+        .iter()
+        .filter_map(|v| normalize_url(&v.url))
+        .collect::<Vec<String>>();
+    if urls.is_empty() {
+        return question;
+    }
+
+    let titles = match repo.get_links(&urls).await {
+        Ok(v) => v
+            .into_iter()
+            .filter_map(|v| Some((v.url, v.title?)))
+            .collect::<HashMap<String, String>>(),
+        Err(e) => {
+            error!("Failed to get link titles: {:?}", e);
+            return question;
+        }
+    };
+
+    for link in links.iter_mut() {
+        link.title = normalize_url(&link.url).and_then(|v| titles.get(&v).cloned());
+    }
+
+    question
+}
+
+/// Sends an email to the moderators about a new question for review and approval.
+async fn notify_moderators(question: &Question) {
+    let subject = format!("{}: {}", Topic::into_name(&question.topic), question.title);
+
+    let question_url = format!(
+        "https://bitesized.info/review?topic={}&qid={}",
+        question.topic, question.qid
+    );
+
+    let is_complete = if question.is_complete() {
+        "complete"
+    } else {
+        "incomplete"
+    };
+
+    let body = format!("{}\n\nState: {}\n\n{}", question_url, is_complete, question.question);
+
+    lambda_utils::email::send_text_email(&lambda_utils::email::mods_email(), &subject, &body).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitie_types::{
+        answer_token::{sign_answer_token, ANSWER_TOKEN_TTL_SECS},
+        embed::EmbedStats,
+        links::LinkStatus,
+        question::Stats,
+    };
+    use lambda_utils::{error::ApiErrorBody, rate_limit::MemoryRateLimiter, repository::MemoryRepository, test_utils};
+
+    const QID: &str = "89yZBXJBa9t2LB6xfj46Rm";
+    const OTHER_QID: &str = "NgGdoZov4T6jV46ty4JUX6";
+
+    /// Builds a minimal Function URL request with the given method and query string.
+    fn request(method: &str, query: &[(&str, &str)]) -> LambdaFunctionUrlRequest {
+        test_utils::request(method, "/q", query, None)
+    }
+
+    const SECRET: &[u8] = b"test-secret";
+
+    /// Calls the handler with a fresh rate limiter.
+    async fn handle(request: LambdaFunctionUrlRequest, repo: &MemoryRepository) -> LambdaFunctionUrlResponse {
+        handle_request(request, repo, &MemoryRateLimiter::new(), Some(SECRET))
+            .await
+            .unwrap()
+    }
+
+    fn repo() -> MemoryRepository {
+        let question = test_utils::question("aws", QID)
+            .with_author("author")
+            .with_updated()
+            .with_stage(PublishStage::Published);
+
+        MemoryRepository::with_questions(vec![question])
+    }
+
+    #[tokio::test]
+    async fn test_get_question() {
+        let repo = repo();
+
+        let response = handle(request("GET", &[("topic", "aws")]), &repo).await;
+        assert_eq!(response.status_code, 400, "missing qid");
+
+        let response = handle(request("GET", &[("topic", "aws"), ("qid", "invalid")]), &repo).await;
+        assert_eq!(response.status_code, 400, "invalid qid");
+
+        let response = handle(
+            request("GET", &[("topic", "aws"), ("qid", "8Bmn6sFMsdXyZKQyJWBUwQ")]),
+            &repo,
+        )
+        .await;
+        assert_eq!(response.status_code, 404, "unknown qid");
+        let error = serde_json::from_str::<ApiErrorBody>(&response.body.unwrap()).unwrap();
+        assert_eq!(error.code, "not_found");
+
+        let response = handle(request("GET", &[("topic", "aws"), ("qid", QID)]), &repo).await;
+        assert_eq!(response.status_code, 200);
+        assert_eq!(
+            response.headers.get("Cache-Control").unwrap(),
+            "public, max-age=60, s-maxage=300"
+        );
+        assert!(response.headers.get("ETag").is_some());
+        let question = serde_json::from_str::<Question>(&response.body.unwrap()).unwrap();
+        assert_eq!(question.question, "<p>What is 1+1?</p>\n");
+    }
+
+    #[tokio::test]
+    async fn test_refresher_link_titles() {
+        let question = Question::from_str(&format!(
+            r#"{{"qid":"{QID}","topic":"aws","question":"What is [1+1](https://example.com/math)?","answers":[{{"a":"1","e":"One"}},{{"a":"2","e":"See [docs](https://Docs.example.com/a#b)","c":true}}],"title":"Simple question","updated":null}}"#
+        ))
+        .unwrap()
+        .with_stage(PublishStage::Published);
+        let repo = MemoryRepository::with_questions(vec![question]);
+        repo.save_link(&LinkStatus {
+            url: "https://docs.example.com/a".to_string(),
+            http_status: Some(200),
+            error: None,
+            title: Some("Docs".to_string()),
+            checked: Utc::now(),
+        })
+        .await
+        .unwrap();
+
+        let response = handle(
+            request("GET", &[("topic", "aws"), ("qid", QID), ("answers", "1")]),
+            &repo,
+        )
+        .await;
+        assert_eq!(response.status_code, 200);
+        let question = serde_json::from_str::<serde_json::Value>(&response.body.unwrap()).unwrap();
+        assert_eq!(
+            question["refresherLinks"],
+            serde_json::json!([
+                { "url": "https://example.com/math", "site": "example.com", "source": "question" },
+                { "url": "https://Docs.example.com/a", "title": "Docs", "site": "docs.example.com", "source": "correctAnswer" },
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_answer_updates_stats() {
+        let repo = repo();
+
+        for answers in ["1", "0", ""] {
+            let response = handle(
+                request("GET", &[("topic", "aws"), ("qid", QID), ("answers", answers)]),
+                &repo,
+            )
+            .await;
+            assert_eq!(response.status_code, 200);
+            assert_eq!(response.headers.get("Cache-Control").unwrap(), "no-store");
+        }
+
+        assert_eq!(
+            repo.get_question("aws", QID).await.unwrap().unwrap().stats,
+            Some(Stats {
+                correct: 1,
+                incorrect: 1,
+                skipped: 1
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_answer_updates_embed_stats() {
+        let repo = repo();
+
+        for answers in ["1", "0"] {
+            let query = [
+                ("topic", "aws"),
+                ("qid", QID),
+                ("answers", answers),
+                ("embed", "Example.com"),
+            ];
+            assert_eq!(handle(request("GET", &query), &repo).await.status_code, 200);
+        }
+        // invalid hosts and views without answers are not counted here
+        let query = [("topic", "aws"), ("qid", QID), ("answers", "1"), ("embed", "<b>")];
+        assert_eq!(handle(request("GET", &query), &repo).await.status_code, 200);
+        let query = [("topic", "aws"), ("qid", QID), ("embed", "example.com")];
+        assert_eq!(handle(request("GET", &query), &repo).await.status_code, 200);
+
+        assert_eq!(
+            repo.embed_stats(QID, "example.com"),
+            Some(EmbedStats {
+                views: 0,
+                correct: 1,
+                incorrect: 1,
+                skipped: 0
+            })
+        );
+        assert_eq!(
+            repo.get_question("aws", QID)
+                .await
+                .unwrap()
+                .unwrap()
+                .stats
+                .unwrap()
+                .correct,
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn test_answer_token() {
+        let repo = repo();
+        repo.create_user("learner@example.com", "abc").await.unwrap();
+        let limiter = MemoryRateLimiter::new();
+        let expires = Utc::now() + chrono::Duration::seconds(ANSWER_TOKEN_TTL_SECS);
+        let token = sign_answer_token(SECRET, "abc", "aws", QID, &[1], expires);
+
+        // the token is for a different answer
+        let query = [("topic", "aws"), ("qid", QID), ("answers", "0"), ("t", token.as_str())];
+        let response = handle_request(request("GET", &query), &repo, &limiter, Some(SECRET))
+            .await
+            .unwrap();
+        assert_eq!(response.status_code, 200);
+        assert!(repo
+            .get_question_history("learner@example.com")
+            .await
+            .unwrap()
+            .is_none());
+
+        // no secret, no user
+        let query = [("topic", "aws"), ("qid", QID), ("answers", "1"), ("t", token.as_str())];
+        handle_request(request("GET", &query), &repo, &limiter, None)
+            .await
+            .unwrap();
+        assert!(repo
+            .get_question_history("learner@example.com")
+            .await
+            .unwrap()
+            .is_none());
+
+        let response = handle_request(request("GET", &query), &repo, &limiter, Some(SECRET))
+            .await
+            .unwrap();
+        assert_eq!(response.status_code, 200);
+        let history = repo.get_question_history("learner@example.com").await.unwrap().unwrap();
+        assert_eq!(history.len(), 1);
+        assert!(matches!(history[0].status, AnswerStatus::Correct(_)));
+
+        // the token is accepted once
+        handle_request(request("GET", &query), &repo, &limiter, Some(SECRET))
+            .await
+            .unwrap();
+        let history = repo.get_question_history("learner@example.com").await.unwrap().unwrap();
+        assert_eq!(history.len(), 1);
+
+        // a token is not used up until there is a user for it
+        let token = sign_answer_token(SECRET, "def", "aws", QID, &[1], expires);
+        let query = [("topic", "aws"), ("qid", QID), ("answers", "1"), ("t", token.as_str())];
+        handle_request(request("GET", &query), &repo, &limiter, Some(SECRET))
+            .await
+            .unwrap();
+        repo.create_user("other@example.com", "def").await.unwrap();
+        handle_request(request("GET", &query), &repo, &limiter, Some(SECRET))
+            .await
+            .unwrap();
+        let history = repo.get_question_history("other@example.com").await.unwrap().unwrap();
+        assert_eq!(history.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_sync_offline_answers() {
+        let repo = repo();
+        let jwt_user = JwtUser {
+            email: "learner@example.com".to_string(),
+            email_hash: "abc".to_string(),
+        };
+        let now = Utc::now();
+        let offline_answer = |qid: &str, answers: Vec<usize>, ts: DateTime<Utc>| OfflineAnswer {
+            asked: AskedQuestion {
+                topic: "aws".to_string(),
+                qid: qid.to_string(),
+                // the device claims everything is correct
+                status: AnswerStatus::Correct(ts),
+            },
+            answers,
+        };
+
+        let other = test_utils::question("aws", OTHER_QID)
+            .with_author("author")
+            .with_updated()
+            .with_stage(PublishStage::Published);
+        repo.save_question(&other).await.unwrap();
+
+        let queue = vec![
+            offline_answer(QID, vec![1], now - Duration::hours(2)),
+            offline_answer(OTHER_QID, vec![0], now - Duration::hours(1)),
+            offline_answer(QID, vec![1], now + Duration::hours(1)),
+            offline_answer(QID, vec![1], now - Duration::seconds(OFFLINE_BUNDLE_TTL_SECS + 1)),
+            offline_answer("8Bmn6sFMsdXyZKQyJWBUwQ", vec![1], now - Duration::hours(1)),
+        ];
+
+        let result = sync_offline_answers(&repo, &jwt_user, queue.clone(), now).await;
+        assert_eq!(result, OfflineSyncResult { synced: 2, ignored: 3 });
+
+        let history = repo.get_question_history("learner@example.com").await.unwrap().unwrap();
+        assert_eq!(history.len(), 2);
+        assert!(matches!(history[0].status, AnswerStatus::Correct(_)));
+        assert!(
+            matches!(history[1].status, AnswerStatus::Incorrect(_)),
+            "scored again on the server"
+        );
+
+        // syncing the same queue again changes nothing
+        let result = sync_offline_answers(&repo, &jwt_user, queue, now).await;
+        assert_eq!(result, OfflineSyncResult { synced: 0, ignored: 5 });
+        assert_eq!(
+            repo.get_question("aws", QID).await.unwrap().unwrap().stats,
+            Some(Stats {
+                correct: 1,
+                incorrect: 0,
+                skipped: 0
+            })
+        );
+        assert_eq!(
+            repo.get_question("aws", OTHER_QID).await.unwrap().unwrap().stats,
+            Some(Stats {
+                correct: 0,
+                incorrect: 1,
+                skipped: 0
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sync_offline_answers_replay() {
+        let repo = repo();
+        let jwt_user = JwtUser {
+            email: "learner@example.com".to_string(),
+            email_hash: "abc".to_string(),
+        };
+        let now = Utc::now();
+        let offline_answer = |minutes: i64| OfflineAnswer {
+            asked: AskedQuestion {
+                topic: "aws".to_string(),
+                qid: QID.to_string(),
+                status: AnswerStatus::Correct(now - Duration::minutes(minutes)),
+            },
+            answers: vec![1],
+        };
+
+        // copies of the same answer with shifted timestamps count once per sync
+        let queue = (1..=10).map(offline_answer).collect::<Vec<OfflineAnswer>>();
+        let result = sync_offline_answers(&repo, &jwt_user, queue, now).await;
+        assert_eq!(result, OfflineSyncResult { synced: 1, ignored: 9 });
+
+        // and are not counted again by later syncs within the bundle lifetime
+        let queue = (11..=20).map(offline_answer).collect::<Vec<OfflineAnswer>>();
+        let result = sync_offline_answers(&repo, &jwt_user, queue, now).await;
+        assert_eq!(result, OfflineSyncResult { synced: 0, ignored: 10 });
+
+        assert_eq!(
+            repo.get_question("aws", QID).await.unwrap().unwrap().stats,
+            Some(Stats {
+                correct: 1,
+                incorrect: 0,
+                skipped: 0
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_put_requires_token() {
+        let response = handle(request("PUT", &[]), &repo()).await;
+        assert_eq!(response.status_code, 401);
+
+        let response = handle(request("POST", &[]), &repo()).await;
+        assert_eq!(response.status_code, 401, "offline sync");
+
+        let response = handle(request("DELETE", &[]), &repo()).await;
+        assert_eq!(response.status_code, 405);
+    }
+}
@@ -17,4 +17,8 @@ rm proxy.zip
 # aarch64-unknown-linux-musl
 
 # permissions script
-# aws lambda add-permission \--statement-id "AllowCloudFrontServicePrincipal" \--action "lambda:InvokeFunctionUrl" \--principal "cloudfront.amazonaws.com" \--source-arn "arn:aws:cloudfront::512295225992:distribution/E1EOR95K1Z2GQD" \--region "us-east-1" \--function-name question-list-handler
\ No newline at end of file
+# aws lambda add-permission \--statement-id "AllowCloudFrontServicePrincipal" \--action "lambda:InvokeFunctionUrl" \--principal "cloudfront.amazonaws.com" \--source-arn "arn:aws:cloudfront::512295225992:distribution/E1EOR95K1Z2GQD" \--region "us-east-1" \--function-name question-list-handler
+
+# the private key for signing offline bundles, e.g. `openssl genpkey -algorithm ed25519`
+# --environment replaces all variables, so include the existing ones
+# aws lambda update-function-configuration --region $region --function-name $lambda --environment "Variables={OFFLINE_BUNDLE_KEY=...}"
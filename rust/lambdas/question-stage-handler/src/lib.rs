@@ -0,0 +1,75 @@
+use aws_lambda_events::{
+    http::method::Method,
+    lambda_function_urls::{LambdaFunctionUrlRequest, LambdaFunctionUrlResponse},
+};
+use bitie_types::{ddb::fields, question::PublishStage};
+use lambda_runtime::{Error, LambdaEvent};
+use lambda_utils::{
+    error::ApiError,
+    repository::{DdbRepository, QuestionRepository},
+    request::{RequestExt, Router, ANY_PATH},
+    response,
+};
+use std::str::FromStr;
+use tracing::{info, warn};
+
+/// The entry point for the lambda runtime and the local dev server.
+pub async fn my_handler(event: LambdaEvent<LambdaFunctionUrlRequest>) -> Result<LambdaFunctionUrlResponse, Error> {
+    if let Some(v) = response::preflight(&event.payload) {
+        return Ok(v);
+    }
+
+    let request_headers = event.payload.headers.clone();
+    let repo = DdbRepository::from_env().await;
+    let response = handle_request(event.payload, &repo).await?;
+    Ok(response::finalize(&request_headers, response))
+}
+
+/// Processes the request against the given storage.
+/// It is separate from `my_handler` to be testable without DDB.
+async fn handle_request<R: QuestionRepository>(
+    request: LambdaFunctionUrlRequest,
+    repo: &R,
+) -> Result<LambdaFunctionUrlResponse, Error> {
+    if let Err(e) = Router::new().route(Method::GET, ANY_PATH, ()).resolve(&request) {
+        return e.into_response();
+    }
+
+    // topic, qid and stage are required - exit if any of them is missing
+
+    let topic = match request.required_topic() {
+        Ok(v) => v,
+        Err(e) => return e.into_response(),
+    };
+
+    let qid = match request.required_qid() {
+        Ok(v) => v,
+        Err(e) => return e.into_response(),
+    };
+
+    let stage = match request.query_param(fields::STAGE).map(PublishStage::from_str) {
+        Some(Ok(v)) => v,
+        Some(Err(e)) => {
+            warn!("Invalid stage in the query string: {:?}", e);
+            return ApiError::Validation("Invalid stage in the query string".to_string()).into_response();
+        }
+        None => {
+            return ApiError::Validation("Missing stage in the query string".to_string()).into_response();
+        }
+    };
+
+    // this action is only allowed for mods
+    // TODO: use some other way of determining if the user is a mod
+    match request.jwt_user() {
+        Some(v) if v.email_hash == "0e3bf888c95b085a7172b2e819692bb5b46c26ad067f9405c8ba1dd950732b65" => {
+            info!("Stage change by {}: {topic}/{qid}/{stage}", v.email)
+        }
+        Some(_) => return ApiError::Forbidden("Must provide a mod's token".to_string()).into_response(),
+        None => return ApiError::Unauthorized.into_response(),
+    }
+
+    match repo.change_publish_stage(&topic, &qid, stage).await {
+        Ok(_) => lambda_utils::text_response(None, 204),
+        Err(e) => ApiError::from(e).into_response(),
+    }
+}
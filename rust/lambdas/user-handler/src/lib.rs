@@ -0,0 +1,109 @@
+use aws_lambda_events::{
+    http::method::Method,
+    lambda_function_urls::{LambdaFunctionUrlRequest, LambdaFunctionUrlResponse},
+};
+use bitie_types::{
+    ddb::fields,
+    // question::{Question, QuestionFormat},
+    topic::Topic,
+};
+use lambda_runtime::{Error, LambdaEvent};
+use lambda_utils::{
+    error::ApiError,
+    repository::{DdbRepository, UserRepository},
+    request::{RequestExt, Router, ANY_PATH},
+    response,
+};
+use tracing::error;
+
+/// The query string param to turn feedback notifications on or off, e.g. `?feedback=off`.
+const FEEDBACK_PARAM: &str = "feedback";
+/// The query string param to turn broken link reports on or off, e.g. `?links=off`.
+const LINKS_PARAM: &str = "links";
+
+/// The entry point for the lambda runtime and the local dev server.
+pub async fn my_handler(event: LambdaEvent<LambdaFunctionUrlRequest>) -> Result<LambdaFunctionUrlResponse, Error> {
+    if let Some(v) = response::preflight(&event.payload) {
+        return Ok(v);
+    }
+
+    let request_headers = event.payload.headers.clone();
+    let repo = DdbRepository::from_env().await;
+    let response = handle_request(event.payload, &repo).await?;
+    Ok(response::finalize(&request_headers, response))
+}
+
+/// Processes the request against the given storage.
+/// It is separate from `my_handler` to be testable without DDB.
+async fn handle_request<R: UserRepository>(
+    request: LambdaFunctionUrlRequest,
+    repo: &R,
+) -> Result<LambdaFunctionUrlResponse, Error> {
+    if let Err(e) = Router::new().route(Method::GET, ANY_PATH, ()).resolve(&request) {
+        return e.into_response();
+    }
+
+    // can only proceed if the user is authenticated with an email
+    let jwt_user = match request.required_jwt_user() {
+        Ok(v) => v,
+        Err(e) => return e.into_response(),
+    };
+
+    // topics param is optional
+    let topics = lambda_utils::url_list_to_vec(request.query_string_parameters.get(fields::TOPICS))
+        .map(Topic::filter_valid_topics);
+
+    // notification params are optional
+    let feedback_opt_out = match opt_out_param(&request, FEEDBACK_PARAM) {
+        Ok(v) => v,
+        Err(e) => return e.into_response(),
+    };
+    let links_opt_out = match opt_out_param(&request, LINKS_PARAM) {
+        Ok(v) => v,
+        Err(e) => return e.into_response(),
+    };
+
+    // get the user or update the user subscription
+    let user = match topics {
+        Some(v) => repo.update_subscription(&jwt_user.email, v).await,
+        None => repo.get_user(&jwt_user.email).await,
+    };
+
+    // feedback notifications can be changed together with the subscription
+    let user = match (user, feedback_opt_out) {
+        (Ok(_), Some(v)) => repo.update_feedback_opt_out(&jwt_user.email, v).await,
+        (user, _) => user,
+    };
+    let user = match (user, links_opt_out) {
+        (Ok(_), Some(v)) => repo.update_links_opt_out(&jwt_user.email, v).await,
+        (user, _) => user,
+    };
+
+    // create a new user if it's the first time login
+    let user = match user {
+        Ok(Some(v)) => Ok(Some(v)),
+        Ok(None) => repo.create_user(&jwt_user.email, &jwt_user.email_hash).await,
+        _ => user,
+    };
+
+    // return the right response
+    match user {
+        Ok(Some(v)) => lambda_utils::json_response(Some(&v), 200),
+        Ok(None) => {
+            error!("User not found after it was created");
+            ApiError::Internal("Failed to create a new user".to_string()).into_response()
+        }
+        Err(e) => ApiError::from(e).into_response(),
+    }
+}
+
+/// Returns the opt-out flag from an `on`/`off` notification param, e.g. true for `?links=off`.
+/// Returns None if the param is not present.
+fn opt_out_param(request: &LambdaFunctionUrlRequest, name: &str) -> Result<Option<bool>, ApiError> {
+    match request.query_param(name) {
+        Some("on") => Ok(Some(false)),
+        Some("off") => Ok(Some(true)),
+        Some(_) => Err(ApiError::Validation(format!("{name} must be on or off"))),
+        None => Ok(None),
+    }
+}
@@ -0,0 +1,154 @@
+//! Signed tokens that let learners answer emailed questions without logging in.
+//!
+//! Each answer option in an email links to the question with the answer and a token in the query string.
+//! The token is `<email hash>.<expiry timestamp>.<signature>`, where the signature is a hex-encoded
+//! HMAC-SHA256 over the email hash, topic, qid, answers and expiry, so it cannot be reused
+//! for another question, answer or user.
+//! The lambda that checks the token is responsible for accepting it only once, e.g. by storing the signature.
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use tracing::info;
+
+/// The query string parameter with the token, e.g. `...&answers=1&t=0e3b...`.
+pub const ANSWER_TOKEN_PARAM: &str = "t";
+
+/// How long the links in an email stay valid.
+pub const ANSWER_TOKEN_TTL_SECS: i64 = 7 * 24 * 3600;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// The details of a valid answer token.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VerifiedAnswerToken {
+    /// The email hash of the learner the token was issued to.
+    pub email_hash: String,
+    /// The hex-encoded signature that identifies the token.
+    pub signature: String,
+    /// When the token stops being valid.
+    pub expires: DateTime<Utc>,
+}
+
+/// Returns a token for the learner with `email_hash` to submit `answers` to the question.
+pub fn sign_answer_token(
+    secret: &[u8],
+    email_hash: &str,
+    topic: &str,
+    qid: &str,
+    answers: &[usize],
+    expires: DateTime<Utc>,
+) -> String {
+    let expires = expires.timestamp();
+    let signature = hex::encode(
+        signature(secret, email_hash, topic, qid, answers, expires)
+            .finalize()
+            .into_bytes(),
+    );
+    format!("{email_hash}.{expires}.{signature}")
+}
+
+/// Returns the token details if the token is valid for the question and answers
+/// and has not expired. Otherwise returns None.
+/// All errors are logged inside the function.
+pub fn verify_answer_token(
+    secret: &[u8],
+    token: &str,
+    topic: &str,
+    qid: &str,
+    answers: &[usize],
+    now: DateTime<Utc>,
+) -> Option<VerifiedAnswerToken> {
+    let mut parts = token.trim().split('.');
+    let (email_hash, expires, signature_hex) = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(a), Some(b), Some(c), None) if !a.is_empty() => (a, b, c),
+        _ => {
+            info!("Invalid answer token format: {token}");
+            return None;
+        }
+    };
+
+    let expires = match expires.parse::<i64>() {
+        Ok(v) => v,
+        Err(e) => {
+            info!("Invalid answer token expiry: {token}, {:?}", e);
+            return None;
+        }
+    };
+    if now.timestamp() >= expires {
+        info!("Expired answer token: {token}");
+        return None;
+    }
+    let expires = match DateTime::from_timestamp(expires, 0) {
+        Some(v) => v,
+        None => {
+            info!("Invalid answer token expiry: {token}");
+            return None;
+        }
+    };
+
+    let signature_bytes = match hex::decode(signature_hex) {
+        Ok(v) => v,
+        Err(e) => {
+            info!("Invalid answer token signature encoding: {token}, {:?}", e);
+            return None;
+        }
+    };
+
+    // the comparison is constant-time
+    match signature(secret, email_hash, topic, qid, answers, expires.timestamp()).verify_slice(&signature_bytes) {
+        Ok(_) => Some(VerifiedAnswerToken {
+            email_hash: email_hash.to_string(),
+            signature: signature_hex.to_lowercase(),
+            expires,
+        }),
+        Err(_) => {
+            info!("Invalid answer token signature: {token}");
+            None
+        }
+    }
+}
+
+/// Returns the HMAC with all the signed values, one per line.
+fn signature(secret: &[u8], email_hash: &str, topic: &str, qid: &str, answers: &[usize], expires: i64) -> HmacSha256 {
+    let answers = answers.iter().map(|v| v.to_string()).collect::<Vec<String>>().join(".");
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts keys of any size. It's a bug.");
+    mac.update(format!("{email_hash}\n{topic}\n{qid}\n{answers}\n{expires}").as_bytes());
+    mac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    const SECRET: &[u8] = b"test-secret";
+    const QID: &str = "89yZBXJBa9t2LB6xfj46Rm";
+
+    #[test]
+    fn test_answer_token() {
+        let now = Utc::now();
+        let expires = now + Duration::seconds(ANSWER_TOKEN_TTL_SECS);
+        let token = sign_answer_token(SECRET, "abc", "aws", QID, &[1], expires);
+        assert!(token.starts_with(&format!("abc.{}.", expires.timestamp())));
+
+        let verified = verify_answer_token(SECRET, &token, "aws", QID, &[1], now).unwrap();
+        assert_eq!(verified.email_hash, "abc");
+        assert_eq!(verified.expires.timestamp(), expires.timestamp());
+        assert!(token.ends_with(&format!(".{}", verified.signature)));
+
+        // any change to the signed values invalidates the token
+        assert!(verify_answer_token(SECRET, &token, "aws", QID, &[0], now).is_none());
+        assert!(verify_answer_token(SECRET, &token, "rust", QID, &[1], now).is_none());
+        assert!(verify_answer_token(b"other-secret", &token, "aws", QID, &[1], now).is_none());
+        assert!(verify_answer_token(SECRET, &token.replacen("abc", "abd", 1), "aws", QID, &[1], now).is_none());
+
+        // expired
+        assert!(verify_answer_token(SECRET, &token, "aws", QID, &[1], expires).is_none());
+
+        // malformed
+        assert!(verify_answer_token(SECRET, "", "aws", QID, &[1], now).is_none());
+        assert!(verify_answer_token(SECRET, "abc.x.00", "aws", QID, &[1], now).is_none());
+        assert!(verify_answer_token(SECRET, &format!("{token}.extra"), "aws", QID, &[1], now).is_none());
+    }
+}
@@ -0,0 +1,713 @@
+//! Conversions between DDB items and `Question` / `User` / `Feedback` / `LinkStatus` structs.
+//! The attribute names come from `ddb::fields`.
+//!
+//! Reading is strict about the keys and the attribute types, but lenient about missing optional attributes:
+//! - missing `title` -> `Question::DEFAULT_TITLE`
+//! - missing `stage` -> `PublishStage::default()`
+//! - missing `updated` -> None
+//! - missing stats counters -> 0
+
+use super::{fields, DEFAULT_USER_TABLE_SK_VALUE};
+use crate::feedback::{Feedback, FeedbackStatus};
+use crate::links::LinkStatus;
+use crate::question::{PublishStage, Question, Stats};
+use crate::user::{AskedQuestion, User};
+use aws_sdk_dynamodb::types::AttributeValue;
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::str::FromStr;
+use tracing::warn;
+
+/// A DDB item as returned by the SDK.
+pub type Item = HashMap<String, AttributeValue>;
+
+/// Lists all the problems found in a DDB item during conversion.
+#[derive(Debug, PartialEq, Default)]
+pub struct ItemError {
+    /// Required attributes that are not in the item.
+    pub missing: Vec<&'static str>,
+    /// Attributes with the wrong DDB type or a value that cannot be parsed, with the reason.
+    pub invalid: Vec<(&'static str, String)>,
+}
+
+impl Display for ItemError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid DDB item")?;
+        if !self.missing.is_empty() {
+            write!(f, ", missing: {}", self.missing.join(", "))?;
+        }
+        if !self.invalid.is_empty() {
+            let invalid = self
+                .invalid
+                .iter()
+                .map(|(field, reason)| format!("{field} ({reason})"))
+                .collect::<Vec<String>>()
+                .join(", ");
+            write!(f, ", invalid: {invalid}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ItemError {}
+
+/// Reads attributes from an item and collects all the errors along the way
+/// to report them in one go.
+struct ItemReader<'a> {
+    item: &'a Item,
+    error: ItemError,
+}
+
+impl<'a> ItemReader<'a> {
+    fn new(item: &'a Item) -> Self {
+        Self {
+            item,
+            error: ItemError::default(),
+        }
+    }
+
+    /// Returns a String attribute or logs it as missing.
+    fn required_s(&mut self, field: &'static str) -> Option<&'a str> {
+        if !self.item.contains_key(field) {
+            self.error.missing.push(field);
+        }
+        self.optional_s(field)
+    }
+
+    /// Returns a String attribute, if present.
+    fn optional_s(&mut self, field: &'static str) -> Option<&'a str> {
+        match self.item.get(field) {
+            Some(AttributeValue::S(v)) => Some(v.as_str()),
+            // an explicit NULL is the same as no value
+            Some(AttributeValue::Null(_)) | None => None,
+            Some(v) => {
+                self.error
+                    .invalid
+                    .push((field, format!("expected S, got {}", type_name(v))));
+                None
+            }
+        }
+    }
+
+    /// Returns a String attribute converted with FromStr, if present.
+    fn optional_parsed<T>(&mut self, field: &'static str) -> Option<T>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        match self.optional_s(field).map(T::from_str) {
+            Some(Ok(v)) => Some(v),
+            Some(Err(e)) => {
+                self.error.invalid.push((field, e.to_string()));
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Returns an RFC3339 String attribute as a timestamp, if present.
+    fn optional_timestamp(&mut self, field: &'static str) -> Option<DateTime<Utc>> {
+        match self.optional_s(field).map(DateTime::parse_from_rfc3339) {
+            Some(Ok(v)) => Some(v.with_timezone(&Utc)),
+            Some(Err(e)) => {
+                self.error.invalid.push((field, e.to_string()));
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Returns an RFC3339 String attribute as a timestamp or logs it as missing.
+    fn required_timestamp(&mut self, field: &'static str) -> Option<DateTime<Utc>> {
+        if !self.item.contains_key(field) {
+            self.error.missing.push(field);
+        }
+        self.optional_timestamp(field)
+    }
+
+    /// Returns a Number attribute, if present.
+    fn optional_n<T>(&mut self, field: &'static str) -> Option<T>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        match self.item.get(field) {
+            Some(AttributeValue::N(v)) => match T::from_str(v) {
+                Ok(v) => Some(v),
+                Err(e) => {
+                    self.error.invalid.push((field, e.to_string()));
+                    None
+                }
+            },
+            Some(AttributeValue::Null(_)) | None => None,
+            Some(v) => {
+                self.error
+                    .invalid
+                    .push((field, format!("expected N, got {}", type_name(v))));
+                None
+            }
+        }
+    }
+
+    /// Returns a String Set attribute, if present.
+    fn optional_ss(&mut self, field: &'static str) -> Option<&'a [String]> {
+        match self.item.get(field) {
+            Some(AttributeValue::Ss(v)) => Some(v.as_slice()),
+            Some(AttributeValue::Null(_)) | None => None,
+            Some(v) => {
+                self.error
+                    .invalid
+                    .push((field, format!("expected SS, got {}", type_name(v))));
+                None
+            }
+        }
+    }
+
+    /// Returns a Boolean attribute, if present.
+    fn optional_bool(&mut self, field: &'static str) -> Option<bool> {
+        match self.item.get(field) {
+            Some(AttributeValue::Bool(v)) => Some(*v),
+            Some(AttributeValue::Null(_)) | None => None,
+            Some(v) => {
+                self.error
+                    .invalid
+                    .push((field, format!("expected BOOL, got {}", type_name(v))));
+                None
+            }
+        }
+    }
+
+    /// Records an invalid value found outside of the reader.
+    fn invalid(&mut self, field: &'static str, reason: String) {
+        self.error.invalid.push((field, reason));
+    }
+
+    /// Returns the collected errors, if any.
+    fn finish(self) -> Result<(), ItemError> {
+        if self.error.missing.is_empty() && self.error.invalid.is_empty() {
+            Ok(())
+        } else {
+            Err(self.error)
+        }
+    }
+}
+
+/// Returns the DDB type name for error messages.
+fn type_name(v: &AttributeValue) -> &'static str {
+    match v {
+        AttributeValue::B(_) => "B",
+        AttributeValue::Bool(_) => "BOOL",
+        AttributeValue::Bs(_) => "BS",
+        AttributeValue::L(_) => "L",
+        AttributeValue::M(_) => "M",
+        AttributeValue::N(_) => "N",
+        AttributeValue::Ns(_) => "NS",
+        AttributeValue::Null(_) => "NULL",
+        AttributeValue::S(_) => "S",
+        AttributeValue::Ss(_) => "SS",
+        _ => "unknown",
+    }
+}
+
+/// Converts DDB timestamps into the format used in all tables, e.g. `2024-10-31T08:39:17Z`.
+pub fn timestamp_to_attr(ts: &DateTime<Utc>) -> AttributeValue {
+    AttributeValue::S(ts.to_rfc3339_opts(SecondsFormat::Secs, true))
+}
+
+/// Converts a questions table item into a Question.
+///
+/// Full items with the `details` attribute are deserialized from it with the `stage` attribute
+/// and the stats counters taking precedence.
+/// Index items without `details` produce a question for list display with only the keys,
+/// title, stage, updated and author populated.
+impl TryFrom<Item> for Question {
+    type Error = ItemError;
+
+    fn try_from(item: Item) -> Result<Self, Self::Error> {
+        let mut reader = ItemReader::new(&item);
+
+        let topic = reader.required_s(fields::TOPIC);
+        let qid = reader.required_s(fields::QID);
+        let details = reader.optional_s(fields::DETAILS);
+        let title = reader.optional_s(fields::TITLE);
+        let stage = reader.optional_parsed::<PublishStage>(fields::STAGE);
+        let updated = reader.optional_timestamp(fields::UPDATED);
+        let author = reader.optional_s(fields::AUTHOR);
+        let correct = reader.optional_n::<u32>(fields::QUESTION_STATS_CORRECT);
+        let incorrect = reader.optional_n::<u32>(fields::QUESTION_STATS_INCORRECT);
+        let skipped = reader.optional_n::<u32>(fields::QUESTION_STATS_SKIPPED);
+
+        let question = match details.map(Question::from_str) {
+            Some(Ok(v)) => Some(v),
+            Some(Err(e)) => {
+                reader.invalid(fields::DETAILS, e.to_string());
+                None
+            }
+            None => None,
+        };
+
+        if let Err(e) = reader.finish() {
+            warn!("{e}: {:?} / {:?}", topic, qid);
+            return Err(e);
+        }
+
+        // the reader returns no errors only if the keys are present
+        let (topic, qid) = (topic.unwrap_or_default(), qid.unwrap_or_default());
+
+        let question = match question {
+            // full question
+            Some(v) => Question {
+                stats: Some(Stats {
+                    correct: correct.unwrap_or_default(),
+                    incorrect: incorrect.unwrap_or_default(),
+                    skipped: skipped.unwrap_or_default(),
+                }),
+                stage: stage.unwrap_or(v.stage.clone()),
+                ..v
+            },
+            // list display question from an index
+            None => Question {
+                topic: topic.to_string(),
+                qid: qid.to_string(),
+                title: title.unwrap_or(Question::DEFAULT_TITLE).to_string(),
+                updated,
+                answers: Vec::new(),
+                question: String::new(),
+                correct: 0,
+                author: author.map(|v| v.to_string()),
+                contributor: None,
+                stats: None,
+                stage: stage.unwrap_or_default(),
+                refresher_links: None,
+            },
+        };
+
+        Ok(question)
+    }
+}
+
+/// Converts a Question into a full questions table item.
+/// The stats counters are not included because they are only ever incremented in place.
+impl From<&Question> for Item {
+    fn from(question: &Question) -> Self {
+        let mut item = HashMap::from([
+            (fields::TOPIC.to_string(), AttributeValue::S(question.topic.clone())),
+            (fields::QID.to_string(), AttributeValue::S(question.qid.clone())),
+            (fields::DETAILS.to_string(), AttributeValue::S(question.to_string())),
+            (fields::TITLE.to_string(), AttributeValue::S(question.title.clone())),
+            (fields::STAGE.to_string(), AttributeValue::S(question.stage.to_string())),
+        ]);
+
+        if let Some(v) = &question.author {
+            item.insert(fields::AUTHOR.to_string(), AttributeValue::S(v.clone()));
+        }
+        if let Some(v) = &question.updated {
+            item.insert(fields::UPDATED.to_string(), timestamp_to_attr(v));
+        }
+
+        item
+    }
+}
+
+/// Converts a users table item into a User.
+/// Invalid entries in the question history are logged and skipped
+/// to keep the user record usable.
+impl TryFrom<Item> for User {
+    type Error = ItemError;
+
+    fn try_from(item: Item) -> Result<Self, Self::Error> {
+        let mut reader = ItemReader::new(&item);
+
+        let email = reader.required_s(fields::EMAIL);
+        let email_hash = reader.optional_s(fields::EMAIL_HASH);
+        let topics = reader.optional_ss(fields::TOPICS);
+        let questions = reader.optional_ss(fields::QUESTIONS);
+        let unsubscribe = reader.optional_s(fields::UNSUBSCRIBE);
+        let updated = reader.optional_timestamp(fields::UPDATED);
+        let is_mod = reader.optional_bool(fields::IS_MOD);
+        let feedback_opt_out = reader.optional_bool(fields::FEEDBACK_OPT_OUT);
+        let feedback_notified = reader.optional_timestamp(fields::FEEDBACK_NOTIFIED);
+        let links_opt_out = reader.optional_bool(fields::LINKS_OPT_OUT);
+
+        if let Err(e) = reader.finish() {
+            warn!("{e}: {:?}", email);
+            return Err(e);
+        }
+
+        let questions = questions
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|v| match AskedQuestion::from_str(v) {
+                Ok(v) => Some(v),
+                Err(_) => {
+                    warn!("Cannot deser question: {v}");
+                    None
+                }
+            })
+            .collect::<Vec<AskedQuestion>>();
+
+        Ok(User {
+            email: email.unwrap_or_default().to_string(),
+            email_hash: email_hash.unwrap_or_default().to_string(),
+            topics: topics.unwrap_or_default().to_vec(),
+            questions,
+            unsubscribe: unsubscribe.unwrap_or_default().to_string(),
+            updated,
+            is_mod: is_mod.filter(|v| *v),
+            feedback_opt_out: feedback_opt_out.filter(|v| *v),
+            feedback_notified,
+            links_opt_out: links_opt_out.filter(|v| *v),
+        })
+    }
+}
+
+/// Converts a User into a users table item.
+/// Empty lists are omitted because DDB does not allow empty sets.
+impl From<&User> for Item {
+    fn from(user: &User) -> Self {
+        let mut item = HashMap::from([
+            (fields::EMAIL.to_string(), AttributeValue::S(user.email.clone())),
+            (
+                fields::SORT_KEY.to_string(),
+                AttributeValue::S(DEFAULT_USER_TABLE_SK_VALUE.to_string()),
+            ),
+            (
+                fields::EMAIL_HASH.to_string(),
+                AttributeValue::S(user.email_hash.clone()),
+            ),
+            (
+                fields::UNSUBSCRIBE.to_string(),
+                AttributeValue::S(user.unsubscribe.clone()),
+            ),
+        ]);
+
+        if !user.topics.is_empty() {
+            item.insert(fields::TOPICS.to_string(), AttributeValue::Ss(user.topics.clone()));
+        }
+        if !user.questions.is_empty() {
+            item.insert(
+                fields::QUESTIONS.to_string(),
+                AttributeValue::Ss(user.questions.iter().map(|v| v.to_string()).collect()),
+            );
+        }
+        if let Some(v) = &user.updated {
+            item.insert(fields::UPDATED.to_string(), timestamp_to_attr(v));
+        }
+        if let Some(v) = user.is_mod {
+            item.insert(fields::IS_MOD.to_string(), AttributeValue::Bool(v));
+        }
+        if let Some(v) = user.feedback_opt_out {
+            item.insert(fields::FEEDBACK_OPT_OUT.to_string(), AttributeValue::Bool(v));
+        }
+        if let Some(v) = &user.feedback_notified {
+            item.insert(fields::FEEDBACK_NOTIFIED.to_string(), timestamp_to_attr(v));
+        }
+        if let Some(v) = user.links_opt_out {
+            item.insert(fields::LINKS_OPT_OUT.to_string(), AttributeValue::Bool(v));
+        }
+
+        item
+    }
+}
+
+/// Converts a feedback table item into Feedback.
+/// A missing status is treated as open.
+impl TryFrom<Item> for Feedback {
+    type Error = ItemError;
+
+    fn try_from(item: Item) -> Result<Self, Self::Error> {
+        let mut reader = ItemReader::new(&item);
+
+        let topic = reader.required_s(fields::TOPIC);
+        let qid = reader.required_s(fields::QID);
+        let fid = reader.required_s(fields::FID);
+        let submitter = reader.optional_s(fields::SUBMITTER);
+        let ip_hash = reader.optional_s(fields::IP_HASH);
+        let text = reader.required_s(fields::TEXT);
+        let status = reader.optional_parsed::<FeedbackStatus>(fields::STATUS);
+        let reply = reader.optional_s(fields::REPLY);
+        let created = reader.required_timestamp(fields::CREATED);
+        let updated = reader.optional_timestamp(fields::UPDATED);
+
+        if let Err(e) = reader.finish() {
+            warn!("{e}: {:?} / {:?}", qid, fid);
+            return Err(e);
+        }
+
+        Ok(Feedback {
+            topic: topic.unwrap_or_default().to_string(),
+            qid: qid.unwrap_or_default().to_string(),
+            fid: fid.unwrap_or_default().to_string(),
+            submitter: submitter.map(|v| v.to_string()),
+            ip_hash: ip_hash.map(|v| v.to_string()),
+            text: text.unwrap_or_default().to_string(),
+            status: status.unwrap_or_default(),
+            reply: reply.map(|v| v.to_string()),
+            created: created.unwrap_or_default(),
+            updated,
+        })
+    }
+}
+
+/// Converts Feedback into a feedback table item.
+impl From<&Feedback> for Item {
+    fn from(feedback: &Feedback) -> Self {
+        let mut item = HashMap::from([
+            (fields::TOPIC.to_string(), AttributeValue::S(feedback.topic.clone())),
+            (fields::QID.to_string(), AttributeValue::S(feedback.qid.clone())),
+            (fields::FID.to_string(), AttributeValue::S(feedback.fid.clone())),
+            (fields::TEXT.to_string(), AttributeValue::S(feedback.text.clone())),
+            (
+                fields::STATUS.to_string(),
+                AttributeValue::S(feedback.status.to_string()),
+            ),
+            (fields::CREATED.to_string(), timestamp_to_attr(&feedback.created)),
+        ]);
+
+        if let Some(v) = &feedback.submitter {
+            item.insert(fields::SUBMITTER.to_string(), AttributeValue::S(v.clone()));
+        }
+        if let Some(v) = &feedback.ip_hash {
+            item.insert(fields::IP_HASH.to_string(), AttributeValue::S(v.clone()));
+        }
+        if let Some(v) = &feedback.reply {
+            item.insert(fields::REPLY.to_string(), AttributeValue::S(v.clone()));
+        }
+        if let Some(v) = &feedback.updated {
+            item.insert(fields::UPDATED.to_string(), timestamp_to_attr(v));
+        }
+
+        item
+    }
+}
+
+/// Converts a links table item into LinkStatus.
+impl TryFrom<Item> for LinkStatus {
+    type Error = ItemError;
+
+    fn try_from(item: Item) -> Result<Self, Self::Error> {
+        let mut reader = ItemReader::new(&item);
+
+        let url = reader.required_s(fields::URL);
+        let http_status = reader.optional_n::<u16>(fields::HTTP_STATUS);
+        let error = reader.optional_s(fields::ERROR);
+        let title = reader.optional_s(fields::TITLE);
+        let checked = reader.required_timestamp(fields::CHECKED);
+
+        if let Err(e) = reader.finish() {
+            warn!("{e}: {:?}", url);
+            return Err(e);
+        }
+
+        Ok(LinkStatus {
+            url: url.unwrap_or_default().to_string(),
+            http_status,
+            error: error.map(|v| v.to_string()),
+            title: title.map(|v| v.to_string()),
+            checked: checked.unwrap_or_default(),
+        })
+    }
+}
+
+/// Converts LinkStatus into a links table item.
+impl From<&LinkStatus> for Item {
+    fn from(link: &LinkStatus) -> Self {
+        let mut item = HashMap::from([
+            (fields::URL.to_string(), AttributeValue::S(link.url.clone())),
+            (fields::CHECKED.to_string(), timestamp_to_attr(&link.checked)),
+        ]);
+
+        if let Some(v) = link.http_status {
+            item.insert(fields::HTTP_STATUS.to_string(), AttributeValue::N(v.to_string()));
+        }
+        if let Some(v) = &link.error {
+            item.insert(fields::ERROR.to_string(), AttributeValue::S(v.clone()));
+        }
+        if let Some(v) = &link.title {
+            item.insert(fields::TITLE.to_string(), AttributeValue::S(v.clone()));
+        }
+
+        item
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::user::AnswerStatus;
+
+    fn s(v: &str) -> AttributeValue {
+        AttributeValue::S(v.to_string())
+    }
+
+    fn question() -> Question {
+        Question::from_str(
+            r#"{"qid":"89yZBXJBa9t2LB6xfj46Rm","topic":"aws","question":"What is 1+1?","answers":[{"a":"1","e":null},{"a":"2","e":null,"c":true}],"title":"Simple question","updated":"2024-10-31T08:39:17Z","author":"abc"}"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_question_to_from_item() {
+        let q = question().with_stage(PublishStage::Published);
+        let mut item = Item::from(&q);
+        item.insert(
+            fields::QUESTION_STATS_CORRECT.to_string(),
+            AttributeValue::N("3".to_string()),
+        );
+
+        let q2 = Question::try_from(item).unwrap();
+        assert_eq!(q2.stage, PublishStage::Published);
+        assert_eq!(
+            q2.stats,
+            Some(Stats {
+                correct: 3,
+                incorrect: 0,
+                skipped: 0
+            })
+        );
+        assert_eq!(Question { stats: None, ..q2 }, q);
+    }
+
+    #[test]
+    fn test_question_from_index_item() {
+        let item = HashMap::from([
+            (fields::TOPIC.to_string(), s("aws")),
+            (fields::QID.to_string(), s("89yZBXJBa9t2LB6xfj46Rm")),
+            (fields::STAGE.to_string(), s("published")),
+            (fields::AUTHOR.to_string(), s("abc")),
+        ]);
+
+        let q = Question::try_from(item).unwrap();
+        assert_eq!(q.title, Question::DEFAULT_TITLE);
+        assert_eq!(q.stage, PublishStage::Published);
+        assert_eq!(q.updated, None);
+        assert_eq!(q.author, Some("abc".to_string()));
+        assert!(q.question.is_empty());
+    }
+
+    #[test]
+    fn test_question_item_errors() {
+        let item = HashMap::from([
+            (fields::TOPIC.to_string(), AttributeValue::N("1".to_string())),
+            (fields::STAGE.to_string(), s("reviewed")),
+            (fields::UPDATED.to_string(), s("yesterday")),
+            (fields::DETAILS.to_string(), s("{}")),
+        ]);
+
+        let e = Question::try_from(item).unwrap_err();
+        assert_eq!(e.missing, vec![fields::QID]);
+        assert_eq!(
+            e.invalid.iter().map(|(field, _)| *field).collect::<Vec<&str>>(),
+            vec![fields::TOPIC, fields::STAGE, fields::UPDATED, fields::DETAILS]
+        );
+        assert!(e
+            .to_string()
+            .starts_with("Invalid DDB item, missing: qid, invalid: topic (expected S, got N)"));
+    }
+
+    #[test]
+    fn test_user_to_from_item() {
+        let user = User {
+            email: "a@b.c".to_string(),
+            email_hash: "abc".to_string(),
+            topics: vec!["aws".to_string(), "rust".to_string()],
+            questions: vec![AskedQuestion {
+                topic: "aws".to_string(),
+                qid: "3RuWxwkgBgpWk6ZUARaZx6".to_string(),
+                status: AnswerStatus::Correct(DateTime::parse_from_rfc3339("2024-10-31T08:39:17Z").unwrap().to_utc()),
+            }],
+            unsubscribe: "xyz".to_string(),
+            updated: Some(DateTime::parse_from_rfc3339("2024-10-31T08:39:17Z").unwrap().to_utc()),
+            is_mod: Some(true),
+            feedback_opt_out: Some(true),
+            feedback_notified: Some(DateTime::parse_from_rfc3339("2024-11-01T10:00:00Z").unwrap().to_utc()),
+            links_opt_out: Some(true),
+        };
+
+        let mut item = Item::from(&user);
+        assert_eq!(item.get(fields::SORT_KEY), Some(&s(DEFAULT_USER_TABLE_SK_VALUE)));
+
+        // invalid history entries are skipped
+        if let Some(AttributeValue::Ss(v)) = item.get_mut(fields::QUESTIONS) {
+            v.push("invalid".to_string());
+        }
+
+        let user2 = User::try_from(item).unwrap();
+        assert_eq!(user2.email, user.email);
+        assert_eq!(user2.topics, user.topics);
+        assert_eq!(user2.questions, user.questions);
+        assert_eq!(user2.updated, user.updated);
+        assert_eq!(user2.is_mod, Some(true));
+        assert_eq!(user2.feedback_opt_out, Some(true));
+        assert_eq!(user2.feedback_notified, user.feedback_notified);
+        assert_eq!(user2.links_opt_out, Some(true));
+    }
+
+    #[test]
+    fn test_user_item_errors() {
+        let item = HashMap::from([
+            (fields::EMAIL.to_string(), s("a@b.c")),
+            // an explicit NULL is valid for optional attributes
+            (fields::TOPICS.to_string(), AttributeValue::Null(true)),
+            (fields::IS_MOD.to_string(), s("true")),
+        ]);
+
+        let e = User::try_from(item).unwrap_err();
+        assert!(e.missing.is_empty());
+        assert_eq!(e.invalid, vec![(fields::IS_MOD, "expected BOOL, got S".to_string())]);
+    }
+
+    #[test]
+    fn test_feedback_to_from_item() {
+        // timestamps are stored with second precision
+        let created = DateTime::parse_from_rfc3339("2024-10-31T08:39:17Z").unwrap().to_utc();
+        let feedback = Feedback {
+            created,
+            ..Feedback::new(
+                "aws",
+                "89yZBXJBa9t2LB6xfj46Rm",
+                Some("abc".to_string()),
+                "1.2.3.4",
+                "Typo",
+            )
+        };
+
+        let item = Item::from(&feedback);
+        assert!(!item.contains_key(fields::REPLY));
+        assert_eq!(Feedback::try_from(item).unwrap(), feedback);
+
+        let item = HashMap::from([
+            (fields::QID.to_string(), s("89yZBXJBa9t2LB6xfj46Rm")),
+            (fields::STATUS.to_string(), s("closed")),
+        ]);
+        let e = Feedback::try_from(item).unwrap_err();
+        assert_eq!(
+            e.missing,
+            vec![fields::TOPIC, fields::FID, fields::TEXT, fields::CREATED]
+        );
+        assert_eq!(e.invalid.len(), 1);
+    }
+
+    #[test]
+    fn test_link_to_from_item() {
+        let checked = DateTime::parse_from_rfc3339("2024-10-31T08:39:17Z").unwrap().to_utc();
+        let link = LinkStatus {
+            url: "https://example.com/".to_string(),
+            http_status: Some(200),
+            error: None,
+            title: Some("Example".to_string()),
+            checked,
+        };
+
+        let item = Item::from(&link);
+        assert!(!item.contains_key(fields::ERROR));
+        assert_eq!(LinkStatus::try_from(item).unwrap(), link);
+
+        let item = HashMap::from([(fields::HTTP_STATUS.to_string(), s("404"))]);
+        let e = LinkStatus::try_from(item).unwrap_err();
+        assert_eq!(e.missing, vec![fields::URL, fields::CHECKED]);
+        assert_eq!(e.invalid.len(), 1);
+    }
+}
@@ -0,0 +1,125 @@
+/// Conversions between DDB items and the structs.
+#[cfg(feature = "ddb")]
+pub mod item;
+
+/// SK is not used at the moment and is set to a constant value.
+pub const DEFAULT_USER_TABLE_SK_VALUE: &str = "sub";
+
+/// The host of the per-question item in the embeds table that counts the embedding hosts.
+/// It is not a valid host name, so it never clashes with a real one.
+pub const EMBED_HOSTS_COUNTER_SK_VALUE: &str = "#hosts";
+
+/// The list of table names in the DynamoDB.
+pub mod tables {
+    /// Contains the full text of the questions along with the metadata.
+    pub const QUESTIONS: &str = "questions_20241009_1118";
+    /// A shorter representation of the questions for the list view: topic, qid,
+    pub const QUESTIONS_IDX_TOPIC: &str = "topic-stage-title-updated-author";
+    /// A list of questions with keys + title by author's email hash
+    pub const QUESTIONS_IDX_AUTHOR: &str = "author-title-stage-updated";
+    /// List of users, their subscriptions and answered questions.
+    pub const USERS: &str = "users_20241023_0712";
+    /// Users by email hash, e.g. to find the author of a question. Only the keys are projected.
+    pub const USERS_IDX_EMAIL_HASH: &str = "email_hash-email";
+    /// Signatures of one-time tokens that were already used, with `ttl` as the TTL attribute.
+    pub const USED_TOKENS: &str = "used_tokens";
+    /// Token buckets for rate limiting with `ttl` as the TTL attribute.
+    pub const RATE_LIMITS: &str = "rate_limits";
+    /// Feedback tickets keyed by qid + fid.
+    pub const FEEDBACK: &str = "feedback";
+    /// All feedback tickets by status, e.g. to list open ones for moderators.
+    pub const FEEDBACK_IDX_STATUS: &str = "status-created";
+    /// Stats of questions embedded on other sites keyed by qid + host.
+    pub const EMBEDS: &str = "embeds";
+    /// The results of refresher link checks keyed by the normalised URL.
+    pub const LINKS: &str = "links";
+}
+
+/// The list of field names across all DDB tables.
+pub mod fields {
+    /// An ID of the topic for a group of questions.
+    /// May contains lower-case characters, digits, and underscores.
+    pub const TOPIC: &str = "topic";
+    /// A list of topics as a DDB String Set.
+    pub const TOPICS: &str = "topics";
+    /// Question ID - a base58 encoded UUID7.
+    pub const QID: &str = "qid";
+    /// A timestamp for when the record was last updated.
+    pub const UPDATED: &str = "updated";
+    /// A markdown text for all the answers together.
+    pub const ANSWERS: &str = "answers";
+    /// A markdown text for the question.
+    pub const QUESTION: &str = "question";
+    /// A list of questions sent to the user or the user interacted with.
+    pub const QUESTIONS: &str = "questions";
+    /// A Number Set of correct answers.
+    pub const CORRECT: &str = "correct";
+    /// User ID - a base58 encoded UUID7.
+    pub const UID: &str = "uid";
+    /// The generic sort key
+    pub const SORT_KEY: &str = "sk";
+    /// A generic details field with a JSON structure.
+    pub const DETAILS: &str = "details";
+    /// User's email address
+    pub const EMAIL: &str = "email";
+    /// Hash of the user's email address
+    pub const EMAIL_HASH: &str = "email_hash";
+    /// A unique unsubscribe token in lower-case
+    pub const UNSUBSCRIBE: &str = "unsubscribe";
+    /// The email address of the user who created the question.
+    pub const AUTHOR: &str = "author";
+    /// The title of the question. It's duplicated from the question field.
+    pub const TITLE: &str = "title";
+    /// The publishing stage of the question: draft, in review, published.
+    pub const STAGE: &str = "stage";
+    /// A counter for the number of correct answers.    
+    pub const QUESTION_STATS_CORRECT: &str = "stat_c";
+    /// A counter for the number of incorrect answers.
+    pub const QUESTION_STATS_INCORRECT: &str = "stat_i";
+    /// A counter for the number of skipped answers.
+    pub const QUESTION_STATS_SKIPPED: &str = "stat_s";
+    /// A boolean flag for the users with moderator privileges.
+    pub const IS_MOD: &str = "mod";
+    /// The key of a rate limit bucket, e.g. `feedback/ip/1.2.3.4`.
+    pub const RATE_LIMIT_KEY: &str = "key";
+    /// The number of tokens left in a rate limit bucket.
+    pub const TOKENS: &str = "tokens";
+    /// The signature of a one-time token, e.g. an answer token from an email.
+    pub const TOKEN_ID: &str = "token_id";
+    /// Expiration time in seconds since the epoch for DDB TTL.
+    pub const TTL: &str = "ttl";
+    /// Feedback ID - a base58 encoded UUID4.
+    pub const FID: &str = "fid";
+    /// Email hash of the user who submitted the feedback.
+    pub const SUBMITTER: &str = "submitter";
+    /// Salted hash of the IP address the feedback was submitted from.
+    pub const IP_HASH: &str = "ip_hash";
+    /// Free-form text, e.g. the feedback message.
+    pub const TEXT: &str = "text";
+    /// The status of a feedback ticket: open, resolved.
+    pub const STATUS: &str = "status";
+    /// A reply from the moderator.
+    pub const REPLY: &str = "reply";
+    /// A timestamp for when the record was created.
+    pub const CREATED: &str = "created";
+    /// A boolean flag for authors who do not want feedback notifications.
+    pub const FEEDBACK_OPT_OUT: &str = "fb_opt_out";
+    /// A timestamp for when the author was last sent a feedback digest.
+    pub const FEEDBACK_NOTIFIED: &str = "fb_notified";
+    /// A boolean flag for authors who do not want broken link reports.
+    pub const LINKS_OPT_OUT: &str = "links_opt_out";
+    /// The host of the site a question is embedded on, e.g. `example.com`.
+    pub const HOST: &str = "host";
+    /// A counter for the number of times the question was shown.
+    pub const QUESTION_STATS_VIEWS: &str = "stat_v";
+    /// The number of hosts a question is embedded on, see `EMBED_HOSTS_COUNTER_SK_VALUE`.
+    pub const EMBED_HOSTS: &str = "hosts";
+    /// A normalised URL of a refresher link.
+    pub const URL: &str = "url";
+    /// The HTTP status code returned for a link.
+    pub const HTTP_STATUS: &str = "http_status";
+    /// A description of the network error, e.g. when a link could not be fetched.
+    pub const ERROR: &str = "error";
+    /// A timestamp for when the link was last checked.
+    pub const CHECKED: &str = "checked";
+}
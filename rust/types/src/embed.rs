@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+
+/// The query string param with the host of the site the question is embedded on.
+pub const EMBED_PARAM: &str = "embed";
+
+/// The max length of a DNS name.
+const MAX_HOST_LENGTH: usize = 253;
+
+/// The max number of embedding sites counted per question.
+/// The host comes from the request, so new hosts beyond this number are ignored to limit the number of stats records.
+pub const MAX_EMBED_HOSTS: usize = 50;
+
+/// Counters for a question embedded on another site.
+/// Stored per question and per host of the embedding site.
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct EmbedStats {
+    /// How many times the widget was shown.
+    pub views: u32,
+    pub correct: u32,
+    pub incorrect: u32,
+    pub skipped: u32,
+}
+
+/// Returns the lower-case host part of the URL, e.g. `example.com` for `https://Example.com:8080/page?a=b`.
+/// Returns None if there is no valid host.
+pub fn host_from_url(url: &str) -> Option<String> {
+    let (_, rest) = url.trim().split_once("://")?;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    // drop user info and the port
+    let host = authority.rsplit('@').next()?;
+    let host = host.split(':').next()?;
+    validate_host(host)
+}
+
+/// Returns the host in lower case if it is a valid DNS name, e.g. `example.com`.
+/// IP addresses pass the check as well.
+pub fn validate_host(host: &str) -> Option<String> {
+    let host = host.trim().trim_end_matches('.').to_ascii_lowercase();
+    if host.is_empty()
+        || host.len() > MAX_HOST_LENGTH
+        || host.starts_with(['.', '-'])
+        || host.contains("..")
+        || !host.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-')
+    {
+        return None;
+    }
+    Some(host)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_host_from_url() {
+        assert_eq!(
+            host_from_url("https://Example.com/page?a=b").as_deref(),
+            Some("example.com")
+        );
+        assert_eq!(
+            host_from_url("http://user@blog.example.com:8080").as_deref(),
+            Some("blog.example.com")
+        );
+        assert_eq!(host_from_url("https://example.com#top").as_deref(), Some("example.com"));
+        assert_eq!(host_from_url("example.com/page"), None);
+        assert_eq!(host_from_url("https://"), None);
+        assert_eq!(host_from_url("https://exa mple.com/"), None);
+    }
+
+    #[test]
+    fn test_validate_host() {
+        assert_eq!(validate_host("Example.COM.").as_deref(), Some("example.com"));
+        assert_eq!(validate_host("127.0.0.1").as_deref(), Some("127.0.0.1"));
+        assert_eq!(validate_host("-example.com"), None);
+        assert_eq!(validate_host("example..com"), None);
+        assert_eq!(validate_host("<script>"), None);
+        assert_eq!(validate_host(&"a".repeat(254)), None);
+    }
+}
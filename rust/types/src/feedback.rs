@@ -0,0 +1,147 @@
+use crate::question::Question;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{fmt::Display, str::FromStr};
+
+/// The max length of the feedback text and the moderator's reply in characters.
+pub const MAX_FEEDBACK_LENGTH: usize = 2_000;
+
+/// Whether the feedback still needs the attention of a moderator.
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone, Copy, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum FeedbackStatus {
+    #[default]
+    Open,
+    Resolved,
+}
+
+impl FromStr for FeedbackStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "open" => Ok(FeedbackStatus::Open),
+            "resolved" => Ok(FeedbackStatus::Resolved),
+            _ => Err(format!("Invalid feedback status: {}", s)),
+        }
+    }
+}
+
+impl Display for FeedbackStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FeedbackStatus::Open => write!(f, "open"),
+            FeedbackStatus::Resolved => write!(f, "resolved"),
+        }
+    }
+}
+
+/// A feedback ticket about a question submitted by a user.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Feedback {
+    /// The topic of the question.
+    pub topic: String,
+    /// The question ID, PK of the feedback table.
+    pub qid: String,
+    /// Feedback ID - a base58 encoded UUID4, SK of the feedback table.
+    pub fid: String,
+    /// Email hash of the submitter if they were logged in.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub submitter: Option<String>,
+    /// Salted hash of the submitter's IP address to spot abuse without storing the IP.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub ip_hash: Option<String>,
+    /// The feedback text as submitted by the user.
+    pub text: String,
+    pub status: FeedbackStatus,
+    /// An optional reply from the moderator who resolved the feedback.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub reply: Option<String>,
+    /// When the feedback was submitted.
+    pub created: DateTime<Utc>,
+    /// When the feedback was last updated, e.g. resolved.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub updated: Option<DateTime<Utc>>,
+}
+
+impl Feedback {
+    /// Creates a new open feedback ticket with a random ID.
+    /// The IP address is hashed and is not stored.
+    pub fn new(topic: &str, qid: &str, submitter: Option<String>, ip: &str, text: &str) -> Self {
+        Self {
+            topic: topic.to_string(),
+            qid: qid.to_string(),
+            fid: bs58::encode(uuid::Uuid::new_v4().as_bytes()).into_string(),
+            submitter,
+            ip_hash: if ip.is_empty() { None } else { Some(Self::hash_ip(ip)) },
+            text: text.to_string(),
+            status: FeedbackStatus::Open,
+            reply: None,
+            created: Utc::now(),
+            updated: None,
+        }
+    }
+
+    /// Returns a salted hash of the IP address in hex format.
+    pub fn hash_ip(ip: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(crate::PUBLIC_SALT);
+        hasher.update(ip);
+        hex::encode(hasher.finalize())
+    }
+
+    /// Returns true if the feedback ID has the same format as a qid.
+    pub fn validate_fid(fid: &str) -> bool {
+        Question::validate_qid(fid)
+    }
+
+    /// Marks the feedback as resolved with an optional reply.
+    pub fn resolve(self, reply: Option<String>) -> Self {
+        Self {
+            status: FeedbackStatus::Resolved,
+            reply,
+            updated: Some(Utc::now()),
+            ..self
+        }
+    }
+
+    /// Removes the details that identify the submitter.
+    /// Authors only see the text, the status and the reply.
+    pub fn without_submitter(self) -> Self {
+        Self {
+            submitter: None,
+            ip_hash: None,
+            ..self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feedback_status() {
+        for status in [FeedbackStatus::Open, FeedbackStatus::Resolved] {
+            assert_eq!(FeedbackStatus::from_str(&status.to_string()).unwrap(), status);
+        }
+        assert!(FeedbackStatus::from_str("closed").is_err());
+    }
+
+    #[test]
+    fn test_new_feedback() {
+        let feedback = Feedback::new("aws", "89yZBXJBa9t2LB6xfj46Rm", None, "1.2.3.4", "The answer is wrong");
+        assert!(Feedback::validate_fid(&feedback.fid));
+        assert_eq!(feedback.status, FeedbackStatus::Open);
+        assert_eq!(feedback.ip_hash, Some(Feedback::hash_ip("1.2.3.4")));
+        assert_ne!(Feedback::hash_ip("1.2.3.4"), Feedback::hash_ip("1.2.3.5"));
+
+        let feedback = feedback.resolve(Some("Fixed".to_string())).without_submitter();
+        assert_eq!(feedback.status, FeedbackStatus::Resolved);
+        assert_eq!(feedback.reply.as_deref(), Some("Fixed"));
+        assert!(feedback.updated.is_some());
+        assert!(feedback.ip_hash.is_none());
+    }
+}
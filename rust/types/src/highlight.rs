@@ -0,0 +1,319 @@
+//! Syntax highlighting of fenced code blocks in questions and answers.
+//!
+//! The highlighter is a single-pass tokenizer driven by small per-language tables.
+//! It runs in the lambdas and in the WASM module, so it must stay cheap to ship:
+//! the budget is 16 KB of the WASM bundle, which rules out grammar-based crates like `syntect`.
+//! The tokens are wrapped in `<span class="hl-*">` and styled by the front-end CSS.
+//!
+//! Only the languages from the allow-list are highlighted.
+//! Code blocks in other languages are rendered as plain `<pre><code>` by the Markdown converter.
+
+use crate::markdown::escape_html;
+
+/// CSS classes of the highlighted tokens.
+const KEYWORD: &str = "hl-keyword";
+const LITERAL: &str = "hl-literal";
+const STRING: &str = "hl-string";
+const NUMBER: &str = "hl-number";
+const COMMENT: &str = "hl-comment";
+
+/// Describes the tokens of a language.
+struct Syntax {
+    /// The name used in the `language-*` class, followed by aliases used in code fences.
+    names: &'static [&'static str],
+    keywords: &'static [&'static str],
+    /// Built-in values, e.g. `true` or `None`.
+    literals: &'static [&'static str],
+    line_comments: &'static [&'static str],
+    block_comment: Option<(&'static str, &'static str)>,
+    quotes: &'static [char],
+    /// Keywords and literals are matched regardless of case, e.g. in SQL.
+    ignore_case: bool,
+}
+
+/// The allow-list of highlighted languages.
+const LANGUAGES: &[Syntax] = &[
+    Syntax {
+        names: &["rust", "rs"],
+        keywords: &[
+            "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern", "fn",
+            "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "self",
+            "Self", "static", "struct", "super", "trait", "type", "unsafe", "use", "where", "while",
+        ],
+        literals: &["true", "false", "None", "Some", "Ok", "Err"],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        // `'` is not a quote because of lifetimes, e.g. `&'a str`
+        quotes: &['"'],
+        ignore_case: false,
+    },
+    Syntax {
+        names: &["json"],
+        keywords: &[],
+        literals: &["true", "false", "null"],
+        line_comments: &[],
+        block_comment: None,
+        quotes: &['"'],
+        ignore_case: false,
+    },
+    Syntax {
+        names: &["javascript", "js", "typescript", "ts"],
+        keywords: &[
+            "async",
+            "await",
+            "break",
+            "case",
+            "catch",
+            "class",
+            "const",
+            "continue",
+            "default",
+            "delete",
+            "do",
+            "else",
+            "export",
+            "extends",
+            "finally",
+            "for",
+            "from",
+            "function",
+            "if",
+            "import",
+            "in",
+            "instanceof",
+            "interface",
+            "let",
+            "new",
+            "of",
+            "return",
+            "switch",
+            "this",
+            "throw",
+            "try",
+            "type",
+            "typeof",
+            "var",
+            "void",
+            "while",
+            "yield",
+        ],
+        literals: &["true", "false", "null", "undefined"],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"', '\'', '`'],
+        ignore_case: false,
+    },
+    Syntax {
+        names: &["python", "py"],
+        keywords: &[
+            "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del", "elif", "else",
+            "except", "finally", "for", "from", "global", "if", "import", "in", "is", "lambda", "nonlocal", "not",
+            "or", "pass", "raise", "return", "try", "while", "with", "yield",
+        ],
+        literals: &["True", "False", "None"],
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: &['"', '\''],
+        ignore_case: false,
+    },
+    Syntax {
+        names: &["bash", "sh", "shell"],
+        keywords: &[
+            "case", "do", "done", "echo", "elif", "else", "esac", "export", "fi", "for", "function", "if", "in",
+            "local", "return", "then", "until", "while",
+        ],
+        literals: &["true", "false"],
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: &['"', '\''],
+        ignore_case: false,
+    },
+    Syntax {
+        names: &["sql"],
+        keywords: &[
+            "and", "as", "asc", "by", "create", "delete", "desc", "distinct", "from", "group", "having", "in", "index",
+            "insert", "into", "is", "join", "key", "left", "like", "limit", "not", "on", "or", "order", "primary",
+            "right", "select", "set", "table", "update", "values", "where",
+        ],
+        literals: &["true", "false", "null"],
+        line_comments: &["--"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['\'', '"'],
+        ignore_case: true,
+    },
+    Syntax {
+        names: &["toml"],
+        keywords: &[],
+        literals: &["true", "false"],
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: &['"', '\''],
+        ignore_case: false,
+    },
+    Syntax {
+        names: &["yaml", "yml"],
+        keywords: &[],
+        literals: &["true", "false", "null"],
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: &['"', '\''],
+        ignore_case: false,
+    },
+];
+
+/// Returns the language name for the `language-*` class if the language of the code fence is highlighted,
+/// e.g. `rust` for ` ```rs,ignore`.
+pub fn language(info: &str) -> Option<&'static str> {
+    syntax(info).map(|v| v.names[0])
+}
+
+/// Returns the code as escaped HTML with the tokens wrapped in `<span class="hl-*">`.
+/// Returns None if the language is not in the allow-list.
+///
+/// Strings end at the closing quote or at the end of the line, so a stray apostrophe
+/// cannot swallow the rest of the block.
+pub fn highlight(code: &str, info: &str) -> Option<String> {
+    let syntax = syntax(info)?;
+    let mut html = String::with_capacity(code.len() * 2);
+    let mut rest = code;
+
+    while let Some(c) = rest.chars().next() {
+        let (class, len) = if syntax.line_comments.iter().any(|v| rest.starts_with(v)) {
+            (Some(COMMENT), rest.find('\n').unwrap_or(rest.len()))
+        } else if let Some((open, close)) = syntax.block_comment.filter(|(open, _)| rest.starts_with(open)) {
+            let len = rest[open.len()..]
+                .find(close)
+                .map_or(rest.len(), |v| open.len() + v + close.len());
+            (Some(COMMENT), len)
+        } else if syntax.quotes.contains(&c) {
+            (Some(STRING), string_len(rest, c))
+        } else if c.is_ascii_digit() {
+            (Some(NUMBER), number_len(rest))
+        } else if c.is_alphabetic() || c == '_' {
+            let len = rest
+                .find(|c: char| !c.is_alphanumeric() && c != '_')
+                .unwrap_or(rest.len());
+            (syntax.classify(&rest[..len]), len)
+        } else {
+            (None, c.len_utf8())
+        };
+
+        match class {
+            Some(class) => {
+                html.push_str(r#"<span class=""#);
+                html.push_str(class);
+                html.push_str(r#"">"#);
+                html.push_str(&escape_html(&rest[..len]));
+                html.push_str("</span>");
+            }
+            None => html.push_str(&escape_html(&rest[..len])),
+        }
+        rest = &rest[len..];
+    }
+
+    Some(html)
+}
+
+impl Syntax {
+    /// Returns the CSS class for a word, if it is a keyword or a literal.
+    fn classify(&self, word: &str) -> Option<&'static str> {
+        let matches = |v: &&str| {
+            if self.ignore_case {
+                v.eq_ignore_ascii_case(word)
+            } else {
+                *v == word
+            }
+        };
+
+        if self.keywords.iter().any(matches) {
+            Some(KEYWORD)
+        } else if self.literals.iter().any(matches) {
+            Some(LITERAL)
+        } else {
+            None
+        }
+    }
+}
+
+/// Returns the syntax for the info string of a code fence, e.g. `rust` or `rust,ignore`.
+fn syntax(info: &str) -> Option<&'static Syntax> {
+    let lang = info.split([',', ' ', '{']).next()?.trim().to_ascii_lowercase();
+    LANGUAGES.iter().find(|v| v.names.contains(&lang.as_str()))
+}
+
+/// Returns the length of the string literal at the start of the text, including the quotes.
+fn string_len(text: &str, quote: char) -> usize {
+    let mut escaped = false;
+    for (i, c) in text.char_indices().skip(1) {
+        match c {
+            '\n' => return i,
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            _ if c == quote => return i + c.len_utf8(),
+            _ => {}
+        }
+    }
+    text.len()
+}
+
+/// Returns the length of the number at the start of the text, e.g. `1_000`, `0xFF`, `1.5e3` or `10u8`.
+/// A `.` is only a part of the number if followed by a digit, so `0..10` is a range.
+fn number_len(text: &str) -> usize {
+    let bytes = text.as_bytes();
+    let mut len = 0;
+    while len < bytes.len() {
+        let is_part = bytes[len].is_ascii_alphanumeric()
+            || bytes[len] == b'_'
+            || (bytes[len] == b'.' && bytes.get(len + 1).is_some_and(u8::is_ascii_digit));
+        if !is_part {
+            break;
+        }
+        len += 1;
+    }
+    len
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_language() {
+        assert_eq!(language("rust"), Some("rust"));
+        assert_eq!(language("rs,ignore"), Some("rust"));
+        assert_eq!(language("TS"), Some("javascript"));
+        assert_eq!(language("brainfuck"), None);
+        assert_eq!(language(""), None);
+    }
+
+    #[test]
+    fn test_highlight_rust() {
+        assert_eq!(
+            highlight("let s: &'a str = \"a\\\"<b>\"; // 0..10\nfor i in 0..10 {}", "rust").unwrap(),
+            concat!(
+                r#"<span class="hl-keyword">let</span> s: &amp;&#39;a str = <span class="hl-string">&quot;a\&quot;&lt;b&gt;&quot;</span>; "#,
+                r#"<span class="hl-comment">// 0..10</span>"#,
+                "\n",
+                r#"<span class="hl-keyword">for</span> i <span class="hl-keyword">in</span> <span class="hl-number">0</span>..<span class="hl-number">10</span> {}"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_highlight_other() {
+        assert_eq!(
+            highlight(r#"{"n": 1.5e3, "ok": true}"#, "json").unwrap(),
+            r#"{<span class="hl-string">&quot;n&quot;</span>: <span class="hl-number">1.5e3</span>, <span class="hl-string">&quot;ok&quot;</span>: <span class="hl-literal">true</span>}"#
+        );
+        assert_eq!(
+            highlight("Select * FROM t -- it's", "sql").unwrap(),
+            r#"<span class="hl-keyword">Select</span> * <span class="hl-keyword">FROM</span> t <span class="hl-comment">-- it&#39;s</span>"#
+        );
+        // an unterminated string stops at the end of the line
+        assert_eq!(
+            highlight("echo don't\nexit", "bash").unwrap(),
+            "<span class=\"hl-keyword\">echo</span> don<span class=\"hl-string\">&#39;t</span>\nexit"
+        );
+        assert_eq!(highlight("x < y", "cobol"), None);
+    }
+}
@@ -1,9 +1,17 @@
+pub mod answer_token;
 pub mod ddb;
+pub mod embed;
+pub mod feedback;
+pub mod highlight;
 pub mod jwt;
+pub mod links;
 pub mod markdown;
+pub mod math;
+pub mod offline;
 pub mod payments;
 pub mod question;
 pub mod relations;
+pub mod sanitizer;
 pub mod topic;
 pub mod user;
 
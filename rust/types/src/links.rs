@@ -0,0 +1,209 @@
+use crate::embed::host_from_url;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// The max length of a page title in characters. Longer titles are truncated with `…`.
+pub const MAX_LINK_TITLE_LEN: usize = 120;
+
+/// Where in the question a refresher link was found.
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum LinkSource {
+    Question,
+    /// An answer or its explanation when the learner has not answered yet,
+    /// so the links do not give away which answers are correct.
+    Answer,
+    CorrectAnswer,
+    IncorrectAnswer,
+}
+
+/// A link to the material for refreshing the knowledge needed to answer the question.
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RefresherLink {
+    /// The URL as it appears in the Markdown.
+    pub url: String,
+    /// The title of the linked page fetched by the link checker, if known.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub title: Option<String>,
+    /// The host of the linked site without `www.`, e.g. `docs.aws.amazon.com`.
+    /// None for relative links.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub site: Option<String>,
+    pub source: LinkSource,
+}
+
+impl RefresherLink {
+    /// Creates a link with the site name from the URL and no title.
+    pub fn new(url: String, source: LinkSource) -> Self {
+        let site = host_from_url(&url).map(|v| v.trim_start_matches("www.").to_string());
+        Self {
+            url,
+            title: None,
+            site,
+            source,
+        }
+    }
+}
+
+/// The result of the last check of a refresher link.
+/// Links are stored once per normalised URL, no matter how many questions use them.
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkStatus {
+    /// Normalised URL, see `normalize_url`.
+    pub url: String,
+    /// HTTP status code of the last response after redirects.
+    /// None if there was no response, e.g. DNS or TLS errors or a timeout.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub http_status: Option<u16>,
+    /// A short description of the network error, if there was no response.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub error: Option<String>,
+    /// The title of the page, if it is an HTML page with a title.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub title: Option<String>,
+    /// When the link was last checked.
+    pub checked: DateTime<Utc>,
+}
+
+impl LinkStatus {
+    /// Returns true if the link is dead or the site is down.
+    /// 401, 403 and 429 are not counted because many documentation sites block bots
+    /// while the page is fine in the browser.
+    pub fn is_broken(&self) -> bool {
+        match self.http_status {
+            Some(401 | 403 | 429) => false,
+            Some(v) => v >= 400,
+            None => true,
+        }
+    }
+}
+
+/// Returns the URL in a form suitable for checking and deduplication, e.g.
+/// `https://docs.aws.amazon.com/dynamodb/?a=b` for ` HTTPS://Docs.AWS.amazon.com:443/dynamodb/?a=b#top`:
+/// - the scheme and the host are in lower case
+/// - the fragment and the default port are removed
+///
+/// Returns None for relative, `mailto:` and other non-HTTP links.
+pub fn normalize_url(url: &str) -> Option<String> {
+    let url = url.trim();
+    let (scheme, rest) = url.split_once("://")?;
+    let scheme = scheme.to_ascii_lowercase();
+    let default_port = match scheme.as_str() {
+        "https" => ":443",
+        "http" => ":80",
+        _ => return None,
+    };
+
+    let host = host_from_url(url)?;
+    let path_start = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+    let port = rest[..path_start]
+        .rsplit('@')
+        .next()
+        .and_then(|v| v.find(':').map(|i| &v[i..]))
+        .filter(|v| *v != default_port)
+        .unwrap_or_default();
+    let path = rest[path_start..].split('#').next().unwrap_or_default();
+    let path = if path.is_empty() { "/" } else { path };
+
+    Some(format!("{scheme}://{host}{port}{path}"))
+}
+
+/// Returns the text of the `<title>` element of an HTML page with the basic entities decoded
+/// and the whitespace collapsed, truncated to `MAX_LINK_TITLE_LEN`.
+/// Returns None if there is no title or it is empty.
+pub fn extract_title(html: &str) -> Option<String> {
+    // ASCII lower-casing keeps the byte offsets the same
+    let lower = html.to_ascii_lowercase();
+    let start = lower.find("<title")?;
+    let start = start + lower[start..].find('>')? + 1;
+    let end = start + lower[start..].find("</title")?;
+
+    let title = html[start..end]
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#x27;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&");
+    let title = title.split_whitespace().collect::<Vec<&str>>().join(" ");
+
+    if title.is_empty() {
+        return None;
+    }
+
+    match title.char_indices().nth(MAX_LINK_TITLE_LEN - 1) {
+        Some((i, _)) => Some([title[..i].trim_end(), "…"].concat()),
+        None => Some(title),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_url() {
+        assert_eq!(
+            normalize_url(" HTTPS://Docs.AWS.amazon.com:443/dynamodb/?a=b#top").as_deref(),
+            Some("https://docs.aws.amazon.com/dynamodb/?a=b")
+        );
+        assert_eq!(
+            normalize_url("http://user@example.com:8080").as_deref(),
+            Some("http://example.com:8080/")
+        );
+        assert_eq!(
+            normalize_url("https://example.com#top").as_deref(),
+            Some("https://example.com/")
+        );
+        assert_eq!(normalize_url("/question?topic=aws"), None);
+        assert_eq!(normalize_url("mailto:max@onebro.me"), None);
+        assert_eq!(normalize_url("ftp://example.com/file"), None);
+    }
+
+    #[test]
+    fn test_is_broken() {
+        let link = |http_status| LinkStatus {
+            url: "https://example.com/".to_string(),
+            http_status,
+            error: None,
+            title: None,
+            checked: Utc::now(),
+        };
+        assert!(!link(Some(200)).is_broken());
+        assert!(!link(Some(403)).is_broken());
+        assert!(link(Some(404)).is_broken());
+        assert!(link(Some(503)).is_broken());
+        assert!(link(None).is_broken());
+    }
+
+    #[test]
+    fn test_refresher_link() {
+        let link = RefresherLink::new("https://www.example.com/a".to_string(), LinkSource::Question);
+        assert_eq!(link.site.as_deref(), Some("example.com"));
+        assert_eq!(
+            serde_json::to_string(&link).unwrap(),
+            r#"{"url":"https://www.example.com/a","site":"example.com","source":"question"}"#
+        );
+        assert!(RefresherLink::new("/about".to_string(), LinkSource::Answer)
+            .site
+            .is_none());
+    }
+
+    #[test]
+    fn test_extract_title() {
+        assert_eq!(
+            extract_title("<html><head><TITLE lang=\"en\">\n  Amazon DynamoDB &amp; you\n</TITLE></head>").as_deref(),
+            Some("Amazon DynamoDB & you")
+        );
+        assert_eq!(extract_title("<title> </title>"), None);
+        assert_eq!(extract_title("<h1>No title</h1>"), None);
+        assert_eq!(extract_title("<title>Unclosed"), None);
+
+        let title = extract_title(&format!("<title>{}</title>", "a".repeat(200))).unwrap();
+        assert_eq!(title.chars().count(), MAX_LINK_TITLE_LEN);
+        assert!(title.ends_with('…'));
+    }
+}
@@ -1,4 +1,6 @@
-use pulldown_cmark::{html::push_html, Event, Parser, Tag};
+pub use crate::sanitizer::HtmlPolicy;
+use crate::{highlight, math, sanitizer::close_tags};
+use pulldown_cmark::{html::push_html, CodeBlockKind, Event, Options, Parser, Tag, TagEnd};
 use serde::Serialize;
 use wasm_bindgen::prelude::*;
 
@@ -31,12 +33,40 @@ pub struct ValidatedMarkdown {
     pub images: Vec<String>,
 }
 
-/// Converts markdown to HTML.
+/// GitHub-flavoured Markdown extensions enabled for questions and answers:
+/// tables, strikethrough, task lists, footnotes, `# Heading {#id}` IDs and `$...$` / `$$...$$` math.
+/// Prices like `$5 or $10` are not math because the closing `$` cannot follow a space,
+/// but a price in the same paragraph as a formula has to be escaped as `\$5`.
+pub const MD_OPTIONS: Options = Options::ENABLE_TABLES
+    .union(Options::ENABLE_STRIKETHROUGH)
+    .union(Options::ENABLE_TASKLISTS)
+    .union(Options::ENABLE_FOOTNOTES)
+    .union(Options::ENABLE_HEADING_ATTRIBUTES)
+    .union(Options::ENABLE_MATH);
+
+/// Converts markdown to HTML with `MD_OPTIONS` and the default `HtmlPolicy`.
 /// Disallowed elements are ignored and not included in the HTML.
 /// Links and images are collected and returned in addition to the HTML.
-///
-/// Disallowed elements: html tags, images.
 pub fn md_to_html(md: &str, include_html: bool) -> ValidatedMarkdown {
+    md_to_html_with_policy(md, include_html, MD_OPTIONS, &HtmlPolicy::DEFAULT)
+}
+
+/// Converts markdown to HTML letting through only what is allowed by the policy.
+///
+/// - HTML tags and attributes not in the allow-list are dropped, the text between them is kept
+/// - images from approved hosts are rendered with `loading="lazy"` and alt text, other images are replaced with the alt text
+/// - links with disallowed schemes, e.g. `javascript:`, are replaced with their text
+/// - fenced code blocks in supported languages are syntax-highlighted, see `highlight` module
+/// - headings keep their `{#id}`, but not classes or other attributes
+/// - math is converted to MathML, see `math` module
+///
+/// `options` are the Markdown extensions to parse, usually `MD_OPTIONS`.
+pub fn md_to_html_with_policy(
+    md: &str,
+    include_html: bool,
+    options: Options,
+    policy: &HtmlPolicy,
+) -> ValidatedMarkdown {
     if md.is_empty() {
         return ValidatedMarkdown {
             html: String::new(),
@@ -47,7 +77,7 @@ pub fn md_to_html(md: &str, include_html: bool) -> ValidatedMarkdown {
     }
 
     // convert the markdown to tokens
-    let parser = Parser::new(md);
+    let parser = Parser::new_ext(md, options);
     info!("Input parsed into tokens");
 
     // containers for specific elements with pre-allocated capacity
@@ -56,37 +86,122 @@ pub fn md_to_html(md: &str, include_html: bool) -> ValidatedMarkdown {
     let mut links = Vec::with_capacity(10);
     let mut images = Vec::with_capacity(10);
 
+    // an allowed image is rendered as a whole once its alt text is collected: (url, title, alt)
+    let mut image: Option<(String, String, String)> = None;
+    // the end tags of dropped elements have to be dropped as well
+    let mut dropped_images = 0_usize;
+    let mut dropped_link = false;
+    // a highlighted code block is rendered as a whole once its text is collected: (language, info, code)
+    let mut code_block: Option<(&'static str, String, String)> = None;
+
+    // HTML tags opened in one event can be closed in another, e.g. `<sub>` and `</sub>` around text
+    let mut open_tags = Vec::new();
+
     // filter out disallowed elements and collect links and images
-    let parser = parser.filter(|event| match event {
-        Event::InlineHtml(v) => {
-            ignored.push(v.to_string());
-            false
-        }
-        Event::Html(v) => {
-            ignored.push(v.to_string());
-            false
+    let mut events = Vec::with_capacity(md.len() / 8);
+    for event in parser {
+        if let Some((_, _, alt)) = &mut image {
+            match &event {
+                Event::End(TagEnd::Image) => {
+                    if let Some((url, title, alt)) = image.take() {
+                        events.push(Event::InlineHtml(policy.render_image(&url, &alt, &title).into()));
+                    }
+                }
+                Event::Text(v) | Event::Code(v) => alt.push_str(v),
+                _ => {}
+            }
+            continue;
         }
-        Event::Start(Tag::Image { dest_url, .. }) => {
-            ignored.push(["image (", dest_url, ")"].concat());
-            images.push(dest_url.to_string());
-            false
+
+        if let Some((_, _, code)) = &mut code_block {
+            match &event {
+                Event::End(TagEnd::CodeBlock) => {
+                    if let Some((language, info, code)) = code_block.take() {
+                        let code = highlight::highlight(&code, &info).unwrap_or_else(|| escape_html(&code));
+                        events.push(Event::Html(
+                            format!("<pre><code class=\"language-{language}\">{code}</code></pre>\n").into(),
+                        ));
+                    }
+                }
+                Event::Text(v) => code.push_str(v),
+                _ => {}
+            }
+            continue;
         }
-        Event::Start(Tag::Link { dest_url, .. }) if !dest_url.is_empty() => {
-            links.push(dest_url.to_string());
-            true
+
+        match &event {
+            Event::InlineHtml(v) => {
+                let html = policy.sanitize_html_part(v, &mut open_tags, &mut ignored);
+                if !html.is_empty() {
+                    events.push(Event::InlineHtml(html.into()));
+                }
+            }
+            Event::Html(v) => {
+                let html = policy.sanitize_html_part(v, &mut open_tags, &mut ignored);
+                if !html.is_empty() {
+                    events.push(Event::Html(html.into()));
+                }
+            }
+            Event::Start(Tag::Image { dest_url, title, .. }) => {
+                images.push(dest_url.to_string());
+                if policy.is_allowed_image(dest_url) {
+                    image = Some((dest_url.to_string(), title.to_string(), String::new()));
+                } else {
+                    ignored.push(["image (", dest_url, ")"].concat());
+                    dropped_images += 1;
+                }
+            }
+            Event::End(TagEnd::Image) if dropped_images > 0 => dropped_images -= 1,
+            Event::Start(Tag::Link { dest_url, .. }) if !dest_url.is_empty() => {
+                if policy.is_allowed_link(dest_url) {
+                    links.push(dest_url.to_string());
+                    events.push(event);
+                } else {
+                    ignored.push(["link (", dest_url, ")"].concat());
+                    dropped_link = true;
+                }
+            }
+            Event::End(TagEnd::Link) if dropped_link => dropped_link = false,
+            Event::Start(Tag::Heading {
+                level,
+                id,
+                classes,
+                attrs,
+            }) if !classes.is_empty() || !attrs.is_empty() => {
+                ignored.extend(classes.iter().map(|v| ["class (", v, ")"].concat()));
+                ignored.extend(attrs.iter().map(|(k, _)| ["attribute (", k, ")"].concat()));
+                events.push(Event::Start(Tag::Heading {
+                    level: *level,
+                    id: id.clone(),
+                    classes: Vec::new(),
+                    attrs: Vec::new(),
+                }));
+            }
+            Event::InlineMath(v) => {
+                events.push(Event::InlineHtml(math::tex_to_mathml(v, false, &mut ignored).into()));
+            }
+            Event::DisplayMath(v) => {
+                events.push(Event::InlineHtml(math::tex_to_mathml(v, true, &mut ignored).into()));
+            }
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info))) if highlight::language(info).is_some() => {
+                code_block = highlight::language(info).map(|v| (v, info.to_string(), String::new()));
+            }
+            _ => events.push(event),
         }
-        _ => true,
-    });
+    }
+
+    // the tags left open must not leak into the markup around the fragment
+    if !open_tags.is_empty() {
+        events.push(Event::Html(close_tags(&mut open_tags).into()));
+    }
 
     // convert the tokens to HTML, if requested
     let html = if include_html {
         // pre-allocate the HTML string for 1.5 times the size of the markdown
         let mut html = String::with_capacity(md.len() * 3 / 2);
-        push_html(&mut html, parser);
+        push_html(&mut html, events.into_iter());
         html
     } else {
-        let _ = parser.collect::<Vec<_>>();
-        // parser.for_each(|_| ());
         String::new()
     };
 
@@ -153,6 +268,186 @@ pub fn sort_links(
     all_links
 }
 
+/// Converts markdown to plain text for previews, e.g. a page description.
+/// HTML, images and code blocks are dropped, math is kept as TeX, block elements are separated by spaces
+/// and the whitespace is collapsed.
+/// The text is truncated at a word boundary to `max_chars` with `…` at the end.
+pub fn md_to_plain_text(md: &str, max_chars: usize) -> String {
+    let mut text = String::with_capacity(md.len());
+    let mut in_code_block = false;
+
+    for event in Parser::new_ext(md, MD_OPTIONS) {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+            Event::End(TagEnd::CodeBlock) => {
+                in_code_block = false;
+                text.push(' ');
+            }
+            Event::Text(v) | Event::Code(v) | Event::InlineMath(v) | Event::DisplayMath(v) if !in_code_block => {
+                text.push_str(&v)
+            }
+            Event::SoftBreak
+            | Event::HardBreak
+            | Event::End(
+                TagEnd::Paragraph | TagEnd::Heading(_) | TagEnd::BlockQuote(_) | TagEnd::Item | TagEnd::TableCell,
+            ) => text.push(' '),
+            _ => {}
+        }
+    }
+
+    let text = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+    if text.chars().count() <= max_chars {
+        return text;
+    }
+
+    // leave room for the ellipsis and cut at the last space, if any
+    let cut = text.chars().take(max_chars.saturating_sub(1)).collect::<String>();
+    let cut = match cut.rfind(' ') {
+        Some(v) if v > 0 => &cut[..v],
+        _ => cut.as_str(),
+    };
+    [cut.trim_end_matches(|c: char| c.is_ascii_punctuation()), "…"].concat()
+}
+
+/// Converts markdown to plain text for emails and notifications, keeping the layout readable:
+/// - paragraphs and other blocks are separated by blank lines
+/// - list items start with `- ` or their number, table cells are separated with ` | `
+/// - code and math are kept as is
+/// - links are followed by their URL in brackets, unless the text is the URL
+/// - images are replaced with their alt text and HTML is dropped
+pub fn md_to_text(md: &str) -> String {
+    let mut text = String::with_capacity(md.len() + md.len() / 8);
+    // the next number for each level of nested lists, None for bullet lists
+    let mut lists: Vec<Option<u64>> = Vec::new();
+    // the URL of the current link and where its text starts
+    let mut link: Option<(String, usize)> = None;
+
+    for event in Parser::new_ext(md, MD_OPTIONS) {
+        match event {
+            Event::Text(v) | Event::Code(v) | Event::InlineMath(v) | Event::DisplayMath(v) => text.push_str(&v),
+            Event::SoftBreak => text.push(' '),
+            Event::HardBreak => text.push('\n'),
+            Event::TaskListMarker(v) => text.push_str(if v { "[x] " } else { "[ ] " }),
+            Event::FootnoteReference(v) => text.push_str(&["[", &v, "]"].concat()),
+            Event::Start(Tag::List(v)) => lists.push(v),
+            Event::End(TagEnd::List(_)) => {
+                lists.pop();
+                if lists.is_empty() {
+                    end_text_block(&mut text);
+                }
+            }
+            Event::Start(Tag::Item) => {
+                start_text_line(&mut text);
+                text.push_str(&"  ".repeat(lists.len().saturating_sub(1)));
+                match lists.last_mut() {
+                    Some(Some(v)) => {
+                        text.push_str(&format!("{v}. "));
+                        *v += 1;
+                    }
+                    _ => text.push_str("- "),
+                }
+            }
+            Event::Start(Tag::Link { dest_url, .. }) => link = Some((dest_url.to_string(), text.len())),
+            Event::End(TagEnd::Link) => {
+                if let Some((url, start)) = link.take() {
+                    if text[start..] != url {
+                        text.push_str(&[" (", &url, ")"].concat());
+                    }
+                }
+            }
+            Event::Start(Tag::CodeBlock(_) | Tag::Table(_)) => start_text_line(&mut text),
+            Event::End(TagEnd::TableCell) => text.push_str(" | "),
+            Event::End(TagEnd::TableHead | TagEnd::TableRow) => {
+                text.truncate(text.trim_end_matches(" | ").len());
+                text.push('\n');
+            }
+            Event::End(
+                TagEnd::Paragraph
+                | TagEnd::Heading(_)
+                | TagEnd::CodeBlock
+                | TagEnd::BlockQuote(_)
+                | TagEnd::Table
+                | TagEnd::FootnoteDefinition,
+            ) => end_text_block(&mut text),
+            _ => {}
+        }
+    }
+
+    text.trim_end().to_string()
+}
+
+/// Starts a new line unless the text is empty or already ends with one.
+fn start_text_line(text: &mut String) {
+    if !text.is_empty() && !text.ends_with('\n') {
+        text.push('\n');
+    }
+}
+
+/// Ends the text with a blank line to separate it from the next block.
+fn end_text_block(text: &mut String) {
+    text.truncate(text.trim_end_matches(' ').len());
+    if text.is_empty() {
+        return;
+    }
+    while !text.ends_with("\n\n") {
+        text.push('\n');
+    }
+}
+
+/// Inline styles for the HTML produced by `md_to_html` because most email clients ignore `<style>`.
+/// The order matters: tags with a `style` attribute added by the parser are styled before the plain ones.
+const EMAIL_STYLES: &[(&str, &str)] = &[
+    (
+        "<pre>",
+        r#"<pre style="background:#f1f5f9;padding:8px;border-radius:4px;overflow-x:auto">"#,
+    ),
+    ("<code", r#"<code style="font-family:monospace;background:#f1f5f9""#),
+    (
+        "<blockquote>",
+        r#"<blockquote style="margin:0 0 0 8px;padding-left:8px;border-left:4px solid #cbd5e1">"#,
+    ),
+    ("<table>", r#"<table style="border-collapse:collapse">"#),
+    ("<th style=\"", r#"<th style="border:1px solid #cbd5e1;padding:4px;"#),
+    ("<th>", r#"<th style="border:1px solid #cbd5e1;padding:4px">"#),
+    ("<td style=\"", r#"<td style="border:1px solid #cbd5e1;padding:4px;"#),
+    ("<td>", r#"<td style="border:1px solid #cbd5e1;padding:4px">"#),
+    ("<img ", r#"<img style="max-width:100%" "#),
+    (r#"<span class="hl-keyword">"#, r#"<span style="color:#7e22ce">"#),
+    (r#"<span class="hl-literal">"#, r#"<span style="color:#b45309">"#),
+    (r#"<span class="hl-number">"#, r#"<span style="color:#b45309">"#),
+    (r#"<span class="hl-string">"#, r#"<span style="color:#15803d">"#),
+    (
+        r#"<span class="hl-comment">"#,
+        r#"<span style="color:#64748b;font-style:italic">"#,
+    ),
+];
+
+/// Converts markdown to HTML for emails, the same as `md_to_html`, but with inline styles.
+/// The replacements are safe because any `<` in the text and attributes is already escaped.
+pub fn md_to_email_html(md: &str) -> String {
+    EMAIL_STYLES
+        .iter()
+        .fold(md_to_html(md, true).html, |html, (tag, styled)| {
+            html.replace(tag, styled)
+        })
+}
+
+/// Escapes the text for use inside HTML attributes and elements.
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len() + text.len() / 8);
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -265,6 +560,167 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_md_to_html_policy() {
+        let md = "H<sub>2</sub>O ![A **diagram**](https://upload.wikimedia.org/d.svg \"Title\") [x](javascript:alert(1)) [y](https://y.com)";
+        assert_eq!(
+            md_to_html(md, true),
+            ValidatedMarkdown {
+                html: "<p>H<sub>2</sub>O <img src=\"https://upload.wikimedia.org/d.svg\" alt=\"A diagram\" title=\"Title\" loading=\"lazy\"> x <a href=\"https://y.com\">y</a></p>\n".to_string(),
+                ignored: vec!["link (javascript:alert(1))".to_string()],
+                links: vec!["https://y.com".to_string()],
+                images: vec!["https://upload.wikimedia.org/d.svg".to_string()],
+            }
+        );
+
+        // a stricter policy drops what the default one allows
+        let policy = HtmlPolicy {
+            tags: &[],
+            image_hosts: &[],
+            ..HtmlPolicy::DEFAULT
+        };
+        let validated = md_to_html_with_policy(md, true, MD_OPTIONS, &policy);
+        assert_eq!(
+            validated.html,
+            "<p>H2O A <strong>diagram</strong> x <a href=\"https://y.com\">y</a></p>\n"
+        );
+        assert_eq!(
+            validated.ignored,
+            vec![
+                "<sub>",
+                "</sub>",
+                "image (https://upload.wikimedia.org/d.svg)",
+                "link (javascript:alert(1))"
+            ]
+        );
+
+        // unbalanced tags do not leak out of the fragment
+        let validated = md_to_html("<details>\n\nOpen </small> *text* <small>small", true);
+        assert_eq!(
+            validated.html,
+            "<details>\n<p>Open  <em>text</em> <small>small</p>\n</small></details>"
+        );
+        assert_eq!(validated.ignored, vec!["</small>"]);
+    }
+
+    #[test]
+    fn test_md_to_html_code_blocks() {
+        let md = "```rust,ignore\nlet x = \"<b>\";\n```\n\n```cobol\nMOVE 1 TO X.\n```";
+        assert_eq!(
+            md_to_html(md, true).html,
+            concat!(
+                "<pre><code class=\"language-rust\"><span class=\"hl-keyword\">let</span> x = ",
+                "<span class=\"hl-string\">&quot;&lt;b&gt;&quot;</span>;\n</code></pre>\n",
+                "<pre><code class=\"language-cobol\">MOVE 1 TO X.\n</code></pre>\n"
+            )
+        );
+    }
+
+    #[test]
+    fn test_md_to_html_gfm() {
+        let md = "| A | B |\n|---|---|\n| <script>x</script> | ~~old~~ <b>new</b> |\n\n- [x] done\n\nText[^1]\n\n[^1]: Note <img src=x onerror=alert(1)>\n\n## Title {#title .evil onclick=alert(1)}";
+        let validated = md_to_html(md, true);
+        assert_eq!(
+            validated.html,
+            concat!(
+                "<table><thead><tr><th>A</th><th>B</th></tr></thead><tbody>\n",
+                "<tr><td>x</td><td><del>old</del> new</td></tr>\n",
+                "</tbody></table>\n",
+                "<ul>\n<li><input disabled=\"\" type=\"checkbox\" checked=\"\"/>\ndone</li>\n</ul>\n",
+                "<p>Text<sup class=\"footnote-reference\"><a href=\"#1\">1</a></sup></p>\n",
+                "<div class=\"footnote-definition\" id=\"1\"><sup class=\"footnote-definition-label\">1</sup>\n",
+                "<p>Note </p>\n",
+                "</div>\n",
+                "<h2 id=\"title\">Title</h2>\n"
+            )
+        );
+        assert_eq!(
+            validated.ignored,
+            vec![
+                "<script>",
+                "</script>",
+                "<b>",
+                "</b>",
+                "<img src=x onerror=alert(1)>",
+                "class (evil)",
+                "attribute (onclick)"
+            ]
+        );
+
+        // the extensions can be turned off
+        let validated = md_to_html_with_policy("~~old~~", true, Options::empty(), &HtmlPolicy::DEFAULT);
+        assert_eq!(validated.html, "<p>~~old~~</p>\n");
+    }
+
+    #[test]
+    fn test_md_to_html_math() {
+        let md = "Binary search is $O(\\log n)$:\n\n$$T(n) = T(\\frac{n}{2}) + \\unknown$$\n\nIt costs $5 or $10.\n\nIt costs \\$5 per $x$.";
+        let validated = md_to_html(md, true);
+        assert_eq!(
+            validated.html,
+            concat!(
+                "<p>Binary search is <math><semantics><mrow><mi>O</mi><mo>(</mo><mi>log</mi><mi>n</mi><mo>)</mo></mrow>",
+                "<annotation encoding=\"application/x-tex\">O(\\log n)</annotation></semantics></math>:</p>\n",
+                "<p><math display=\"block\"><semantics><mrow><mi>T</mi><mo>(</mo><mi>n</mi><mo>)</mo><mo>=</mo>",
+                "<mi>T</mi><mo>(</mo><mfrac><mi>n</mi><mn>2</mn></mfrac><mo>)</mo><mo>+</mo><merror><mtext>\\unknown</mtext></merror></mrow>",
+                "<annotation encoding=\"application/x-tex\">T(n) = T(\\frac{n}{2}) + \\unknown</annotation></semantics></math></p>\n",
+                "<p>It costs $5 or $10.</p>\n",
+                "<p>It costs $5 per <math><semantics><mrow><mi>x</mi></mrow>",
+                "<annotation encoding=\"application/x-tex\">x</annotation></semantics></math>.</p>\n"
+            )
+        );
+        assert_eq!(validated.ignored, vec!["math (\\unknown)"]);
+        assert_eq!(
+            md_to_plain_text(md, 200),
+            "Binary search is O(\\log n): T(n) = T(\\frac{n}{2}) + \\unknown It costs $5 or $10. It costs $5 per x."
+        );
+    }
+
+    #[test]
+    fn test_md_to_plain_text() {
+        let md = "# What is `x`?\n\nSome *text*<br> with a [link](https://example.com).\n\n```rust\nlet x = 1;\n```\n- one\n- two";
+        assert_eq!(md_to_plain_text(md, 100), "What is x? Some text with a link. one two");
+        assert_eq!(md_to_plain_text(md, 20), "What is x? Some…");
+        assert_eq!(md_to_plain_text("Supercalifragilistic", 10), "Supercali…");
+        assert_eq!(md_to_plain_text("", 10), "");
+        assert_eq!(md_to_plain_text("| A | B |\n|---|---|\n| 1 | ~~2~~ |", 100), "A B 1 2");
+    }
+
+    #[test]
+    fn test_md_to_text() {
+        let md = "# Title\n\nSome *text*<br> with a [link](https://example.com) and <https://a.com>.\n\n```rust\nlet x = 1;\n\nlet y = 2;\n```\n1. one\n   - nested\n2. two\n\n| A | B |\n|---|---|\n| 1 | $x^2$ |\n\n![alt](/img) - [x] done";
+        assert_eq!(
+            md_to_text(md),
+            concat!(
+                "Title\n\n",
+                "Some text with a link (https://example.com) and https://a.com.\n\n",
+                "let x = 1;\n\nlet y = 2;\n\n",
+                "1. one\n  - nested\n2. two\n\n",
+                "A | B\n1 | x^2\n\n",
+                "alt - [x] done"
+            )
+        );
+        assert_eq!(md_to_text(""), "");
+    }
+
+    #[test]
+    fn test_md_to_email_html() {
+        let md = "`x` and\n\n```rust\nfn a() {}\n```\n\n| A |\n|:-:|\n| 1 |";
+        assert_eq!(
+            md_to_email_html(md),
+            concat!(
+                "<p><code style=\"font-family:monospace;background:#f1f5f9\">x</code> and</p>\n",
+                "<pre style=\"background:#f1f5f9;padding:8px;border-radius:4px;overflow-x:auto\">",
+                "<code style=\"font-family:monospace;background:#f1f5f9\" class=\"language-rust\">",
+                "<span style=\"color:#7e22ce\">fn</span> a() {}\n</code></pre>\n",
+                "<table style=\"border-collapse:collapse\"><thead><tr>",
+                "<th style=\"border:1px solid #cbd5e1;padding:4px;text-align: center\">A</th></tr></thead><tbody>\n",
+                "<tr><td style=\"border:1px solid #cbd5e1;padding:4px;text-align: center\">1</td></tr>\n",
+                "</tbody></table>\n"
+            )
+        );
+    }
+
     #[test]
     fn playground() {
         let _ = tracing_subscriber::fmt().try_init();
@@ -330,4 +786,12 @@ More info: <https://doc.rust-lang.org/reference/tokens.html#character-escapes>"#
     //         info!("{:?}", evt);
     //     }
     // }
+
+    #[test]
+    fn test_escape_html() {
+        assert_eq!(
+            escape_html(r#"<a href="x">Tom's & Jerry's</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;Tom&#39;s &amp; Jerry&#39;s&lt;/a&gt;"
+        );
+    }
 }
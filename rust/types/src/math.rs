@@ -0,0 +1,599 @@
+//! Conversion of TeX math in questions and answers to MathML.
+//!
+//! `$...$` and `$$...$$` spans are rendered server-side as `<math>` elements, so the browser
+//! displays them natively without a JS library. The same code renders the preview in the WASM module.
+//!
+//! Only a subset of TeX commonly used in algorithm questions is supported:
+//! letters, numbers, operators, `^` and `_` scripts, `\frac`, `\binom`, `\sqrt`, Greek letters,
+//! common symbols and functions, accents, `\text` and `\left`/`\right` delimiters.
+//! Unsupported commands are rendered as `<merror>` and reported in `ValidatedMarkdown::ignored`.
+//! The original TeX is kept in `<annotation>` for copying and screen readers.
+
+use crate::markdown::escape_html;
+
+/// Deeper nesting is rejected to keep the recursion bounded on malicious input.
+const MAX_DEPTH: usize = 32;
+
+/// How a command from `SYMBOLS` is rendered.
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    /// A variable, e.g. `\alpha`, rendered in italics.
+    Ident,
+    /// An upright identifier, e.g. `\Delta`.
+    Upright,
+    /// A function name, e.g. `\log`, rendered upright.
+    Function,
+    /// An operator or a relation, e.g. `\le`.
+    Op,
+    /// An operator with limits under and over it, e.g. `\sum` or `\lim`.
+    Limits,
+}
+
+/// Commands that map to a single symbol or name.
+const SYMBOLS: &[(&str, &str, Kind)] = &[
+    ("alpha", "α", Kind::Ident),
+    ("beta", "β", Kind::Ident),
+    ("gamma", "γ", Kind::Ident),
+    ("delta", "δ", Kind::Ident),
+    ("epsilon", "ϵ", Kind::Ident),
+    ("varepsilon", "ε", Kind::Ident),
+    ("zeta", "ζ", Kind::Ident),
+    ("eta", "η", Kind::Ident),
+    ("theta", "θ", Kind::Ident),
+    ("iota", "ι", Kind::Ident),
+    ("kappa", "κ", Kind::Ident),
+    ("lambda", "λ", Kind::Ident),
+    ("mu", "μ", Kind::Ident),
+    ("nu", "ν", Kind::Ident),
+    ("xi", "ξ", Kind::Ident),
+    ("pi", "π", Kind::Ident),
+    ("rho", "ρ", Kind::Ident),
+    ("sigma", "σ", Kind::Ident),
+    ("tau", "τ", Kind::Ident),
+    ("upsilon", "υ", Kind::Ident),
+    ("phi", "ϕ", Kind::Ident),
+    ("varphi", "φ", Kind::Ident),
+    ("chi", "χ", Kind::Ident),
+    ("psi", "ψ", Kind::Ident),
+    ("omega", "ω", Kind::Ident),
+    ("Gamma", "Γ", Kind::Upright),
+    ("Delta", "Δ", Kind::Upright),
+    ("Theta", "Θ", Kind::Upright),
+    ("Lambda", "Λ", Kind::Upright),
+    ("Xi", "Ξ", Kind::Upright),
+    ("Pi", "Π", Kind::Upright),
+    ("Sigma", "Σ", Kind::Upright),
+    ("Phi", "Φ", Kind::Upright),
+    ("Psi", "Ψ", Kind::Upright),
+    ("Omega", "Ω", Kind::Upright),
+    ("infty", "∞", Kind::Upright),
+    ("emptyset", "∅", Kind::Upright),
+    ("partial", "∂", Kind::Upright),
+    ("nabla", "∇", Kind::Upright),
+    ("ell", "ℓ", Kind::Ident),
+    ("log", "log", Kind::Function),
+    ("ln", "ln", Kind::Function),
+    ("lg", "lg", Kind::Function),
+    ("exp", "exp", Kind::Function),
+    ("sin", "sin", Kind::Function),
+    ("cos", "cos", Kind::Function),
+    ("tan", "tan", Kind::Function),
+    ("gcd", "gcd", Kind::Function),
+    ("det", "det", Kind::Function),
+    ("deg", "deg", Kind::Function),
+    ("mod", "mod", Kind::Function),
+    ("bmod", "mod", Kind::Function),
+    ("lim", "lim", Kind::Limits),
+    ("max", "max", Kind::Limits),
+    ("min", "min", Kind::Limits),
+    ("sup", "sup", Kind::Limits),
+    ("inf", "inf", Kind::Limits),
+    ("argmax", "arg max", Kind::Limits),
+    ("argmin", "arg min", Kind::Limits),
+    ("sum", "∑", Kind::Limits),
+    ("prod", "∏", Kind::Limits),
+    ("bigcup", "⋃", Kind::Limits),
+    ("bigcap", "⋂", Kind::Limits),
+    ("int", "∫", Kind::Op),
+    ("oint", "∮", Kind::Op),
+    ("le", "≤", Kind::Op),
+    ("leq", "≤", Kind::Op),
+    ("ge", "≥", Kind::Op),
+    ("geq", "≥", Kind::Op),
+    ("ne", "≠", Kind::Op),
+    ("neq", "≠", Kind::Op),
+    ("ll", "≪", Kind::Op),
+    ("gg", "≫", Kind::Op),
+    ("approx", "≈", Kind::Op),
+    ("equiv", "≡", Kind::Op),
+    ("sim", "∼", Kind::Op),
+    ("propto", "∝", Kind::Op),
+    ("times", "×", Kind::Op),
+    ("cdot", "⋅", Kind::Op),
+    ("div", "÷", Kind::Op),
+    ("pm", "±", Kind::Op),
+    ("mp", "∓", Kind::Op),
+    ("oplus", "⊕", Kind::Op),
+    ("otimes", "⊗", Kind::Op),
+    ("circ", "∘", Kind::Op),
+    ("ast", "∗", Kind::Op),
+    ("in", "∈", Kind::Op),
+    ("notin", "∉", Kind::Op),
+    ("ni", "∋", Kind::Op),
+    ("subset", "⊂", Kind::Op),
+    ("subseteq", "⊆", Kind::Op),
+    ("supset", "⊃", Kind::Op),
+    ("supseteq", "⊇", Kind::Op),
+    ("cup", "∪", Kind::Op),
+    ("cap", "∩", Kind::Op),
+    ("setminus", "∖", Kind::Op),
+    ("forall", "∀", Kind::Op),
+    ("exists", "∃", Kind::Op),
+    ("neg", "¬", Kind::Op),
+    ("lnot", "¬", Kind::Op),
+    ("land", "∧", Kind::Op),
+    ("wedge", "∧", Kind::Op),
+    ("lor", "∨", Kind::Op),
+    ("vee", "∨", Kind::Op),
+    ("to", "→", Kind::Op),
+    ("rightarrow", "→", Kind::Op),
+    ("leftarrow", "←", Kind::Op),
+    ("gets", "←", Kind::Op),
+    ("leftrightarrow", "↔", Kind::Op),
+    ("Rightarrow", "⇒", Kind::Op),
+    ("Leftarrow", "⇐", Kind::Op),
+    ("iff", "⟺", Kind::Op),
+    ("implies", "⟹", Kind::Op),
+    ("mapsto", "↦", Kind::Op),
+    ("mid", "∣", Kind::Op),
+    ("ldots", "…", Kind::Op),
+    ("dots", "…", Kind::Op),
+    ("cdots", "⋯", Kind::Op),
+    ("vdots", "⋮", Kind::Op),
+    ("lfloor", "⌊", Kind::Op),
+    ("rfloor", "⌋", Kind::Op),
+    ("lceil", "⌈", Kind::Op),
+    ("rceil", "⌉", Kind::Op),
+    ("langle", "⟨", Kind::Op),
+    ("rangle", "⟩", Kind::Op),
+    ("lvert", "|", Kind::Op),
+    ("rvert", "|", Kind::Op),
+    ("vert", "|", Kind::Op),
+    ("lVert", "‖", Kind::Op),
+    ("rVert", "‖", Kind::Op),
+    ("Vert", "‖", Kind::Op),
+];
+
+/// Accents rendered with `<mover>`.
+const ACCENTS: &[(&str, &str)] = &[
+    ("hat", "^"),
+    ("widehat", "^"),
+    ("bar", "‾"),
+    ("overline", "‾"),
+    ("vec", "→"),
+    ("overrightarrow", "→"),
+    ("dot", "˙"),
+    ("ddot", "¨"),
+    ("tilde", "~"),
+    ("widetilde", "~"),
+];
+
+/// Spacing commands and their widths.
+const SPACES: &[(&str, &str)] = &[
+    (",", "0.1667em"),
+    (":", "0.2222em"),
+    (">", "0.2222em"),
+    (";", "0.2778em"),
+    (" ", "0.25em"),
+    ("quad", "1em"),
+    ("qquad", "2em"),
+];
+
+/// Converts a TeX formula to a `<math>` element, with `display="block"` for `$$...$$` formulas.
+/// Unsupported commands are added to `ignored` as `math (\command)`.
+pub fn tex_to_mathml(tex: &str, display: bool, ignored: &mut Vec<String>) -> String {
+    let mut parser = TexParser {
+        tex,
+        pos: 0,
+        depth: 0,
+        ignored,
+    };
+    let elements = parser.parse_row(false);
+    let content = elements.into_iter().map(|(v, _)| v).collect::<String>();
+    let display = if display { r#" display="block""# } else { "" };
+
+    format!(
+        r#"<math{display}><semantics><mrow>{content}</mrow><annotation encoding="application/x-tex">{}</annotation></semantics></math>"#,
+        escape_html(tex)
+    )
+}
+
+/// A recursive descent parser that renders MathML as it goes.
+/// Each element is returned with a flag telling if its scripts go under and over it.
+struct TexParser<'a> {
+    tex: &'a str,
+    pos: usize,
+    depth: usize,
+    ignored: &'a mut Vec<String>,
+}
+
+impl TexParser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.tex[self.pos..].chars().next()
+    }
+
+    fn next_char(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek().filter(|v| v.is_whitespace()) {
+            self.pos += c.len_utf8();
+        }
+    }
+
+    /// Parses elements until the end of the input or the `}` closing the current group.
+    /// The closing brace is not consumed.
+    fn parse_row(&mut self, in_group: bool) -> Vec<(String, bool)> {
+        let mut elements: Vec<(String, bool)> = Vec::new();
+
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return vec![(self.too_deep(), false)];
+        }
+
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                None => break,
+                Some('}') if in_group => break,
+                Some('}') => {
+                    // an unbalanced brace is ignored the same way TeX editors highlight it
+                    self.pos += 1;
+                    self.ignored.push("math (})".to_string());
+                }
+                Some(c @ ('^' | '_')) => {
+                    self.pos += 1;
+                    let (base, limits) = elements.pop().unwrap_or_else(|| ("<mrow></mrow>".to_string(), false));
+                    let script = self.parse_arg();
+
+                    // the other script may follow, e.g. `x_i^2`
+                    self.skip_whitespace();
+                    let other = match self.peek() {
+                        Some(v @ ('^' | '_')) if v != c => {
+                            self.pos += 1;
+                            Some(self.parse_arg())
+                        }
+                        _ => None,
+                    };
+
+                    let (sub_tag, sup_tag, both_tag) = if limits {
+                        ("munder", "mover", "munderover")
+                    } else {
+                        ("msub", "msup", "msubsup")
+                    };
+                    let element = match (c == '_', other) {
+                        (true, None) => format!("<{sub_tag}>{base}{script}</{sub_tag}>"),
+                        (false, None) => format!("<{sup_tag}>{base}{script}</{sup_tag}>"),
+                        (true, Some(sup)) => format!("<{both_tag}>{base}{script}{sup}</{both_tag}>"),
+                        (false, Some(sub)) => format!("<{both_tag}>{base}{sub}{script}</{both_tag}>"),
+                    };
+                    elements.push((element, false));
+                }
+                Some(_) => {
+                    if let Some(v) = self.parse_atom() {
+                        elements.push(v);
+                    }
+                }
+            }
+        }
+
+        self.depth -= 1;
+        elements
+    }
+
+    /// Renders the rest of the input as an error once the nesting is too deep.
+    /// The caller must still decrement `depth`.
+    fn too_deep(&mut self) -> String {
+        let error = "math (too deeply nested)";
+        if !self.ignored.iter().any(|v| v == error) {
+            self.ignored.push(error.to_string());
+        }
+        let rest = &self.tex[self.pos..];
+        self.pos = self.tex.len();
+        format!("<merror><mtext>{}</mtext></merror>", escape_html(rest))
+    }
+
+    /// Parses a `{...}` group as a single `<mrow>`. The opening brace must be the next char.
+    fn parse_group(&mut self) -> String {
+        self.pos += 1;
+        let elements = self.parse_row(true);
+        if self.peek() == Some('}') {
+            self.pos += 1;
+        }
+        row(elements)
+    }
+
+    /// Parses an argument of a command or a script: a group or a single token.
+    /// A digit is a single token, so `\frac12` is `1/2` as in TeX.
+    fn parse_arg(&mut self) -> String {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => self.parse_group(),
+            Some(c) if c.is_ascii_digit() => {
+                self.pos += 1;
+                format!("<mn>{c}</mn>")
+            }
+            Some(_) => self
+                .parse_atom()
+                .map(|(v, _)| v)
+                .unwrap_or_else(|| "<mrow></mrow>".to_string()),
+            None => "<mrow></mrow>".to_string(),
+        }
+    }
+
+    /// Returns the raw text of a `{...}` group, e.g. for `\text`.
+    fn parse_raw_group(&mut self) -> String {
+        self.skip_whitespace();
+        if self.peek() != Some('{') {
+            return self.next_char().map(String::from).unwrap_or_default();
+        }
+
+        self.pos += 1;
+        let start = self.pos;
+        let mut depth = 0;
+        while let Some(c) = self.next_char() {
+            match c {
+                '{' => depth += 1,
+                '}' if depth == 0 => return self.tex[start..self.pos - 1].to_string(),
+                '}' => depth -= 1,
+                _ => {}
+            }
+        }
+        self.tex[start..].to_string()
+    }
+
+    /// Parses a single element. Returns None for commands that produce no output, e.g. `\left.`.
+    fn parse_atom(&mut self) -> Option<(String, bool)> {
+        let c = self.peek()?;
+        match c {
+            '{' => Some((self.parse_group(), false)),
+            '\\' => {
+                // commands take arguments without braces, e.g. `\hat\hat x`, so they count as nesting too
+                self.pos += 1;
+                self.depth += 1;
+                let element = if self.depth > MAX_DEPTH {
+                    Some((self.too_deep(), false))
+                } else {
+                    self.parse_command()
+                };
+                self.depth -= 1;
+                element
+            }
+            '0'..='9' | '.' => {
+                let start = self.pos;
+                while self.peek().is_some_and(|v| v.is_ascii_digit() || v == '.') {
+                    self.pos += 1;
+                }
+                Some((format!("<mn>{}</mn>", &self.tex[start..self.pos]), false))
+            }
+            '~' => {
+                self.pos += 1;
+                Some((r#"<mspace width="0.25em"></mspace>"#.to_string(), false))
+            }
+            _ => {
+                self.pos += c.len_utf8();
+                let element = if c.is_alphabetic() {
+                    format!("<mi>{c}</mi>")
+                } else {
+                    let c = match c {
+                        '-' => "−".to_string(),
+                        '*' => "∗".to_string(),
+                        '\'' => "′".to_string(),
+                        _ => escape_html(&c.to_string()),
+                    };
+                    format!("<mo>{c}</mo>")
+                };
+                Some((element, false))
+            }
+        }
+    }
+
+    /// Parses the command after `\`.
+    fn parse_command(&mut self) -> Option<(String, bool)> {
+        let start = self.pos;
+        match self.peek() {
+            Some(c) if c.is_ascii_alphabetic() => {
+                while self.peek().is_some_and(|v| v.is_ascii_alphabetic()) {
+                    self.pos += 1;
+                }
+            }
+            Some(c) => self.pos += c.len_utf8(),
+            None => return None,
+        }
+        let name = &self.tex[start..self.pos];
+
+        if let Some((_, symbol, kind)) = SYMBOLS.iter().find(|(v, _, _)| *v == name) {
+            let element = match kind {
+                Kind::Ident => format!("<mi>{symbol}</mi>"),
+                Kind::Upright => format!(r#"<mi mathvariant="normal">{symbol}</mi>"#),
+                Kind::Function => format!("<mi>{symbol}</mi>"),
+                Kind::Op | Kind::Limits => format!("<mo>{symbol}</mo>"),
+            };
+            return Some((element, *kind == Kind::Limits));
+        }
+
+        if let Some((_, width)) = SPACES.iter().find(|(v, _)| *v == name) {
+            return Some((format!(r#"<mspace width="{width}"></mspace>"#), false));
+        }
+
+        if let Some((_, accent)) = ACCENTS.iter().find(|(v, _)| *v == name) {
+            let base = self.parse_arg();
+            return Some((
+                format!(r#"<mover accent="true">{base}<mo>{accent}</mo></mover>"#),
+                false,
+            ));
+        }
+
+        let element = match name {
+            "frac" | "dfrac" | "tfrac" => {
+                let numerator = self.parse_arg();
+                let denominator = self.parse_arg();
+                format!("<mfrac>{numerator}{denominator}</mfrac>")
+            }
+            "binom" => {
+                let n = self.parse_arg();
+                let k = self.parse_arg();
+                format!(r#"<mrow><mo>(</mo><mfrac linethickness="0">{n}{k}</mfrac><mo>)</mo></mrow>"#)
+            }
+            "sqrt" => {
+                self.skip_whitespace();
+                if self.peek() == Some('[') {
+                    self.pos += 1;
+                    let start = self.pos;
+                    let end = self.tex[start..].find(']').map_or(self.tex.len(), |v| start + v);
+                    let index = &self.tex[start..end];
+                    self.pos = (end + 1).min(self.tex.len());
+                    let index = row(TexParser {
+                        tex: index,
+                        pos: 0,
+                        depth: self.depth,
+                        ignored: self.ignored,
+                    }
+                    .parse_row(false));
+                    let base = self.parse_arg();
+                    format!("<mroot>{base}{index}</mroot>")
+                } else {
+                    format!("<msqrt>{}</msqrt>", self.parse_arg())
+                }
+            }
+            "text" | "textrm" | "mbox" => format!("<mtext>{}</mtext>", escape_html(&self.parse_raw_group())),
+            "mathrm" | "operatorname" => {
+                format!(
+                    r#"<mi mathvariant="normal">{}</mi>"#,
+                    escape_html(self.parse_raw_group().trim())
+                )
+            }
+            "mathbf" => format!(
+                r#"<mi mathvariant="bold">{}</mi>"#,
+                escape_html(self.parse_raw_group().trim())
+            ),
+            "mathbb" => {
+                let text = self
+                    .parse_raw_group()
+                    .chars()
+                    .map(|c| match c {
+                        'N' => 'ℕ',
+                        'Z' => 'ℤ',
+                        'Q' => 'ℚ',
+                        'R' => 'ℝ',
+                        'C' => 'ℂ',
+                        _ => c,
+                    })
+                    .collect::<String>();
+                format!(r#"<mi mathvariant="normal">{}</mi>"#, escape_html(text.trim()))
+            }
+            // stretchy delimiters are rendered as plain operators, MathML stretches fences on its own
+            "left" | "right" | "big" | "Big" | "bigl" | "bigr" | "Bigl" | "Bigr" => {
+                self.skip_whitespace();
+                if self.peek() == Some('.') {
+                    self.pos += 1;
+                }
+                return None;
+            }
+            "{" | "}" | "|" | "%" | "$" | "#" | "&" | "_" => format!("<mo>{}</mo>", escape_html(name)),
+            "\\" => return None,
+            _ => {
+                self.ignored.push(["math (\\", name, ")"].concat());
+                format!(r#"<merror><mtext>\{}</mtext></merror>"#, escape_html(name))
+            }
+        };
+
+        Some((element, false))
+    }
+}
+
+/// Returns the elements wrapped in `<mrow>` unless it is a single element.
+fn row(mut elements: Vec<(String, bool)>) -> String {
+    if elements.len() == 1 {
+        return elements.pop().map(|(v, _)| v).unwrap_or_default();
+    }
+    let content = elements.into_iter().map(|(v, _)| v).collect::<String>();
+    format!("<mrow>{content}</mrow>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the MathML between `<semantics><mrow>` and `</mrow><annotation`.
+    fn convert(tex: &str) -> (String, Vec<String>) {
+        let mut ignored = Vec::new();
+        let mathml = tex_to_mathml(tex, false, &mut ignored);
+        let start = mathml.find("<semantics><mrow>").unwrap() + 17;
+        let end = mathml.rfind("</mrow><annotation").unwrap();
+        (mathml[start..end].to_string(), ignored)
+    }
+
+    #[test]
+    fn test_tex_to_mathml() {
+        let mut ignored = Vec::new();
+        assert_eq!(
+            tex_to_mathml("x < 1", true, &mut ignored),
+            r#"<math display="block"><semantics><mrow><mi>x</mi><mo>&lt;</mo><mn>1</mn></mrow><annotation encoding="application/x-tex">x &lt; 1</annotation></semantics></math>"#
+        );
+        assert!(ignored.is_empty());
+
+        assert_eq!(
+            convert("O(n \\log n)").0,
+            "<mi>O</mi><mo>(</mo><mi>n</mi><mi>log</mi><mi>n</mi><mo>)</mo>"
+        );
+        assert_eq!(
+            convert("x_i^2 - 1.5").0,
+            "<msubsup><mi>x</mi><mi>i</mi><mn>2</mn></msubsup><mo>−</mo><mn>1.5</mn>"
+        );
+        assert_eq!(
+            convert("\\sum_{i=1}^n i").0,
+            "<munderover><mo>∑</mo><mrow><mi>i</mi><mo>=</mo><mn>1</mn></mrow><mi>n</mi></munderover><mi>i</mi>"
+        );
+        assert_eq!(
+            convert("\\frac12 \\sqrt[3]{x}").0,
+            "<mfrac><mn>1</mn><mn>2</mn></mfrac><mroot><mi>x</mi><mn>3</mn></mroot>"
+        );
+        assert_eq!(
+            convert("\\left\\lfloor \\frac{n}{2} \\right.").0,
+            "<mo>⌊</mo><mfrac><mi>n</mi><mn>2</mn></mfrac>"
+        );
+        assert_eq!(
+            convert("\\text{if } n \\in \\mathbb{N}").0,
+            r#"<mtext>if </mtext><mi>n</mi><mo>∈</mo><mi mathvariant="normal">ℕ</mi>"#
+        );
+    }
+
+    #[test]
+    fn test_tex_to_mathml_errors() {
+        let (mathml, ignored) = convert("\\begin{matrix} a }");
+        assert_eq!(
+            mathml,
+            "<merror><mtext>\\begin</mtext></merror><mrow><mi>m</mi><mi>a</mi><mi>t</mi><mi>r</mi><mi>i</mi><mi>x</mi></mrow><mi>a</mi>"
+        );
+        assert_eq!(ignored, vec!["math (\\begin)", "math (})"]);
+
+        // the nesting is limited
+        let (mathml, ignored) = convert(&"{".repeat(100));
+        assert!(mathml.contains("<merror>"));
+        assert_eq!(ignored, vec!["math (too deeply nested)"]);
+
+        // commands without braces are nested too
+        for command in ["\\hat", "\\sqrt", "\\frac1", "\\sqrt[2]"] {
+            let (mathml, ignored) = convert(&(command.repeat(5000) + "x"));
+            assert!(mathml.contains("<merror>"));
+            assert_eq!(ignored, vec!["math (too deeply nested)"]);
+        }
+
+        // scripts with nothing to attach to or nothing to attach
+        assert_eq!(convert("^").0, "<msup><mrow></mrow><mrow></mrow></msup>");
+    }
+}
@@ -17,6 +17,14 @@ pub enum QuestionFormat {
     HtmlFull(Option<Vec<usize>>),
     /// Return the short question in HTML format for the user to answer.
     HtmlShort,
+    /// Return the question and the answers as plain text without explanations for emails and notifications.
+    /// Each answer has a deep link that records it as the learner's choice.
+    PlainText,
+    /// Return the question and the answers as HTML with inline styles and without explanations
+    /// for email clients that ignore `<style>`. Each answer has a deep link like in `PlainText`.
+    ///
+    /// `PlainText` and `EmailHtml` are library-only for now: no lambda emails questions to learners yet.
+    EmailHtml,
 }
 
 /// A question with multiple answers.
@@ -37,6 +45,22 @@ pub struct Answer {
     /// Present in JSON only if true.
     #[serde(skip_serializing_if = "Option::is_none", default)]
     sel: Option<bool>,
+    /// A link to the question on the website that submits this answer when opened.
+    /// Only set in `PlainText` and `EmailHtml` formats. Values submitted from UI are ignored.
+    #[serde(skip_serializing_if = "Option::is_none", skip_deserializing)]
+    url: Option<String>,
+}
+
+impl Answer {
+    /// The answer text as shown to the learner, Markdown or HTML depending on the question format.
+    pub fn text(&self) -> &str {
+        &self.a
+    }
+
+    /// The deep link that records this answer, if set by the question format.
+    pub fn url(&self) -> Option<&str> {
+        self.url.as_deref()
+    }
 }
 
 /// Stats about the user answers, correct, incorrect, skipped.
@@ -1,13 +1,23 @@
 use super::{Answer, ContributorProfile, PublishStage, QuestionFormat, Stats};
+use crate::answer_token::{sign_answer_token, ANSWER_TOKEN_PARAM};
+use crate::links::{LinkSource, RefresherLink};
 use crate::markdown::{self, md_to_html, ValidatedMarkdown};
 use crate::topic::Topic;
+use crate::user::AnswerStatus;
 use anyhow::{Error, Result};
 use chrono::{DateTime, Timelike, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::fmt::Display;
 use std::str::FromStr;
 use tracing::error;
 
+/// The page where learners answer questions, used in deep links.
+const QUESTION_URL: &str = "https://bitesized.info/question";
+
+/// The query string parameter with the learner's answers, e.g. `answers=0.2`.
+const ANSWERS_PARAM: &str = "answers";
+
 /// A question with multiple answers.
 #[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -61,9 +71,9 @@ pub struct Question {
     /// A sorted list of links extracted from the Markdown of the question,
     /// answers and explanations.
     /// This data is not persisted in the DB.
-    /// The links are built on the fly by the server.
-    #[serde(skip_serializing_if = "Option::is_none", default)]
-    pub refresher_links: Option<Vec<String>>,
+    /// The links are built on the fly by the server and the values submitted from UI are ignored.
+    #[serde(skip_serializing_if = "Option::is_none", skip_deserializing)]
+    pub refresher_links: Option<Vec<RefresherLink>>,
 }
 
 impl Question {
@@ -72,7 +82,7 @@ impl Question {
     pub const MAX_QUESTION_LEN: usize = 12_000;
 
     /// The maximum size of a deserialized title in bytes
-    /// The excess should be truncated.
+    /// The excess should be truncated at a char boundary.
     pub const MAX_TITLE_LEN: usize = 120;
 
     /// The value to use when no title is present and it cannot be generated from the question.
@@ -125,6 +135,7 @@ impl Question {
                     e,
                     c: answer.c,
                     sel: None,
+                    url: None,
                 }
             })
             .collect::<Vec<Answer>>();
@@ -152,7 +163,21 @@ impl Question {
             None => answers_as_html,
         };
 
-        let refresher_links = markdown::sort_links(q_links, c_links, i_links);
+        // a link found in more than one place is listed once, in the first group it appears in
+        let mut seen = HashSet::new();
+        let refresher_links = [
+            (LinkSource::Question, q_links),
+            (LinkSource::CorrectAnswer, c_links),
+            (LinkSource::IncorrectAnswer, i_links),
+        ]
+        .into_iter()
+        .flat_map(|(source, links)| {
+            markdown::sort_links(links, Vec::new(), Vec::new())
+                .into_iter()
+                .map(move |url| RefresherLink::new(url, source))
+        })
+        .filter(|v| seen.insert(v.url.clone()))
+        .collect::<Vec<RefresherLink>>();
         let refresher_links = if refresher_links.is_empty() {
             None
         } else {
@@ -169,6 +194,7 @@ impl Question {
 
     /// Removes detailed explanations from the answers
     /// to display the question for answering.
+    /// The answer links are re-sorted and marked as `LinkSource::Answer` not to give away the correct answers.
     fn without_detailed_explanations(self) -> Self {
         let answers = self
             .answers
@@ -180,7 +206,22 @@ impl Question {
             })
             .collect();
 
-        Question { answers, ..self }
+        let refresher_links = self.refresher_links.map(|links| {
+            let (mut question_links, mut answer_links): (Vec<RefresherLink>, Vec<RefresherLink>) =
+                links.into_iter().partition(|v| v.source == LinkSource::Question);
+            answer_links.sort_by(|a, b| a.url.cmp(&b.url));
+            question_links.extend(answer_links.into_iter().map(|v| RefresherLink {
+                source: LinkSource::Answer,
+                ..v
+            }));
+            question_links
+        });
+
+        Question {
+            answers,
+            refresher_links,
+            ..self
+        }
     }
 
     /// Formats the question to provide the the required format.
@@ -190,9 +231,70 @@ impl Question {
             QuestionFormat::HtmlFull(v) => self.into_html(v),
             // TODO: add a flag to extract links, but do not convert certain parts of the question into HTML
             QuestionFormat::HtmlShort => self.into_html(None).without_detailed_explanations(),
+            QuestionFormat::PlainText => self.into_delivery_format(markdown::md_to_text),
+            QuestionFormat::EmailHtml => self.into_delivery_format(markdown::md_to_email_html),
         }
     }
 
+    /// Converts the question and the answers with `convert` for delivery outside the website, e.g. by email.
+    /// Explanations and correct answer flags are removed and each answer gets a deep link
+    /// that records it as the learner's choice.
+    /// A single answer is not a complete choice if there are several correct answers,
+    /// so in that case the links open the question without submitting anything.
+    fn into_delivery_format(self, convert: fn(&str) -> String) -> Self {
+        let question_url = format!("{QUESTION_URL}?topic={}&qid={}", self.topic, self.qid);
+
+        let answers = self
+            .answers
+            .into_iter()
+            .enumerate()
+            .map(|(idx, answer)| Answer {
+                a: convert(&answer.a),
+                e: None,
+                c: None,
+                sel: None,
+                url: Some(if self.correct == 1 {
+                    format!("{question_url}&{ANSWERS_PARAM}={idx}")
+                } else {
+                    question_url.clone()
+                }),
+            })
+            .collect();
+
+        Question {
+            question: convert(&self.question),
+            answers,
+            refresher_links: None,
+            ..self
+        }
+    }
+
+    /// Adds signed answer tokens to the deep links set by `PlainText` and `EmailHtml` formats,
+    /// so that the learner with `email_hash` can answer from an email without logging in.
+    /// Links that do not submit an answer, e.g. for questions with several correct answers, are not changed.
+    /// Only the verify side is wired in, see `question-handler`. This is for the sender of question emails,
+    /// which does not exist yet.
+    pub fn with_answer_tokens(self, secret: &[u8], email_hash: &str, expires: DateTime<Utc>) -> Self {
+        if self.correct != 1 {
+            return self;
+        }
+
+        let answers = self
+            .answers
+            .into_iter()
+            .enumerate()
+            .map(|(idx, answer)| {
+                let url = answer.url.map(|url| {
+                    let token = sign_answer_token(secret, email_hash, &self.topic, &self.qid, &[idx], expires);
+                    format!("{url}&{ANSWER_TOKEN_PARAM}={token}")
+                });
+                Answer { url, ..answer }
+            })
+            .collect();
+
+        Question { answers, ..self }
+    }
+
     /// Generates a random question ID as UUID4 in Base58 encoding.,
     /// e.g. 1D759ksnnlogULbRPng3noG, 2gS2XiBnscLX5dQFDP3kiJo, 3SPUtNR96QCIsdu1je8Duki
     fn generate_random_qid() -> String {
@@ -309,6 +411,76 @@ impl Question {
         true
     }
 
+    /// Validates a deserialized question and normalizes its fields:
+    /// - qid is a valid UUID4 in Base58 encoding or a new random one is generated
+    /// - topic is present in the TOPICS list
+    /// - correct is recalculated from the answers
+    /// - title is trimmed and truncated or generated from the question if missing
+    /// - answering stats are set to None
+    pub fn validated(self) -> Result<Self> {
+        // Checks how many answers have `correct` flag set to true
+        // and updates `correct` attribute.
+        let correct = self.answers.iter().filter(|a| a.c.unwrap_or_default()).count() as u8;
+
+        // qid is missing for new questions
+        // it should be a valid UUID4 if present, but check it just in case
+        let qid = match bs58::decode(&self.qid).into_vec() {
+            Ok(v) if v.len() == 16 => self.qid.clone(),
+            _ => Question::generate_random_qid(),
+        };
+
+        // only supported topics are allowed
+        let topic = self.topic.trim().to_lowercase();
+        if !Topic::TOPICS.contains(&topic.as_str()) {
+            error!("Invalid topic {topic}");
+            return Err(Error::msg("Invalid topic"));
+        }
+
+        // the title should be trimmed and truncated, if present
+        // if not, we should make something up
+        // it is needed in the front-end to display the list of questions
+        let title = {
+            let v = self.title.trim();
+            if v.is_empty() {
+                Self::title_from_question(&self.question)
+            } else {
+                v[..v.floor_char_boundary(Self::MAX_TITLE_LEN)].to_string()
+            }
+        };
+
+        // this structure should be safe enough for further processing
+        Ok(Question {
+            qid,
+            topic,
+            title,
+            correct,
+            stats: None,
+            ..self
+        })
+    }
+
+    /// Generates a title from the question text for questions submitted without one.
+    /// Line breaks are replaced with spaces and the result is truncated to `MAX_TITLE_LEN`.
+    pub fn title_from_question(question: &str) -> String {
+        if question.len() > 10 {
+            let v = &question.trim().replace(['\n', '\r'], " ").replace("  ", " ");
+            v[..v.floor_char_boundary(Self::MAX_TITLE_LEN)].to_string()
+        } else {
+            Self::DEFAULT_TITLE.to_string()
+        }
+    }
+
+    /// Returns the status of the learner's answers given at `ts`.
+    /// List present - correct or incorrect, blank list - skipped, no list - asked.
+    pub fn answer_status(&self, answers: Option<&[usize]>, ts: DateTime<Utc>) -> AnswerStatus {
+        match answers {
+            None => AnswerStatus::Asked(ts),
+            Some([]) => AnswerStatus::Skipped(ts),
+            Some(v) if self.is_correct(v) => AnswerStatus::Correct(ts),
+            Some(_) => AnswerStatus::Incorrect(ts),
+        }
+    }
+
     /// Returns True if the question has all the required parts.
     pub fn is_complete(&self) -> bool {
         !self.topic.is_empty()
@@ -323,11 +495,8 @@ impl Question {
     }
 }
 
-/// Converts a JSON string to a Question struct with validation:
-/// - qid is a valid UUID4 in Base58 encoding or a new random one is generated
-/// - topic is present in the TOPICS list
-/// - correct is recalculated from the answers
-/// - answering stats are set to None
+/// Converts a JSON string to a Question struct with validation.
+/// See `Question::validated` for details.
 impl FromStr for Question {
     type Err = anyhow::Error;
 
@@ -349,55 +518,7 @@ impl FromStr for Question {
             }
         };
 
-        // Checks how many answers have `correct` flag set to true
-        // and updates `correct` attribute.
-        let correct = q.answers.iter().filter(|a| a.c.unwrap_or_default()).count() as u8;
-
-        // qid is missing for new questions
-        // it should be a valid UUID4 if present, but check it just in case
-        let qid = match bs58::decode(&q.qid).into_vec() {
-            Ok(v) if v.len() == 16 => q.qid.clone(),
-            _ => Question::generate_random_qid(),
-        };
-
-        // only supported topics are allowed
-        let topic = q.topic.trim().to_lowercase();
-        if !Topic::TOPICS.contains(&topic.as_str()) {
-            error!("Invalid topic {topic}");
-            return Err(Error::msg("Invalid topic"));
-        }
-
-        // a helper function needed at the next step
-        let title_from_question = || {
-            if q.question.len() > 10 {
-                let v = &q.question.trim().replace(['\n', '\r'], " ").replace("  ", " ");
-                v[..v.len().min(Self::MAX_TITLE_LEN)].to_string()
-            } else {
-                Self::DEFAULT_TITLE.to_string()
-            }
-        };
-
-        // the title should be trimmed and truncated, if present
-        // if not, we should make something up
-        // it is needed in the front-end to display the list of questions
-        let title = {
-            let v = q.title.trim();
-            if v.is_empty() {
-                title_from_question()
-            } else {
-                v[..v.len().min(Self::MAX_TITLE_LEN)].to_string()
-            }
-        };
-
-        // this structure should be safe enough for further processing
-        Ok(Question {
-            qid,
-            topic,
-            title,
-            correct,
-            stats: None,
-            ..q
-        })
+        q.validated()
     }
 }
 
@@ -418,6 +539,7 @@ impl Display for Question {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::answer_token::verify_answer_token;
 
     #[test]
     fn test_question_is_correct_1() {
@@ -431,18 +553,21 @@ mod test {
                     e: None,
                     c: Some(false),
                     sel: None,
+                    url: None,
                 },
                 Answer {
                     a: "2".to_string(),
                     e: None,
                     c: Some(true),
                     sel: None,
+                    url: None,
                 },
                 Answer {
                     a: "3".to_string(),
                     e: None,
                     c: Some(false),
                     sel: None,
+                    url: None,
                 },
             ],
             correct: 1,
@@ -474,18 +599,21 @@ mod test {
                     e: None,
                     c: Some(true),
                     sel: None,
+                    url: None,
                 },
                 Answer {
                     a: "2".to_string(),
                     e: None,
                     c: Some(false),
                     sel: None,
+                    url: None,
                 },
                 Answer {
                     a: "cherry".to_string(),
                     e: None,
                     c: Some(true),
                     sel: None,
+                    url: None,
                 },
             ],
             correct: 2,
@@ -519,18 +647,21 @@ mod test {
                     e: None,
                     c: Some(false),
                     sel: None,
+                    url: None,
                 },
                 Answer {
                     a: "2".to_string(),
                     e: None,
                     c: Some(true),
                     sel: None,
+                    url: None,
                 },
                 Answer {
                     a: "3".to_string(),
                     e: None,
                     c: Some(false),
                     sel: None,
+                    url: None,
                 },
             ],
             correct: 1,
@@ -567,18 +698,21 @@ mod test {
                     e: None,
                     c: Some(false),
                     sel: None,
+                    url: None,
                 },
                 Answer {
                     a: "2".to_string(),
                     e: None,
                     c: Some(true),
                     sel: None,
+                    url: None,
                 },
                 Answer {
                     a: "3".to_string(),
                     e: None,
                     c: Some(false),
                     sel: None,
+                    url: None,
                 },
             ],
             correct: 1,
@@ -619,12 +753,14 @@ mod test {
                     e: None,
                     c: Some(false),
                     sel: None,
+                    url: None,
                 },
                 Answer {
                     a: "2".to_string(),
                     e: None,
                     c: Some(true),
                     sel: None,
+                    url: None,
                 },
             ],
             correct: 1,
@@ -667,6 +803,21 @@ mod test {
             "The syntax and capabilities of closures make them very convenient for on the fly usage. Calling a closure is exactly lik".to_string(),
             "expected question truncated to MAX_TITLE_LEN with line breaks removed"
         );
+
+        // the same title is generated for an already deserialized question, e.g. from WASM
+        assert_eq!(
+            q.clone().validated().unwrap().title,
+            Question::title_from_question(&q.question),
+            "validated() and title_from_question() should match from_str()"
+        );
+        assert_eq!(Question::title_from_question("short"), Question::DEFAULT_TITLE);
+
+        // multibyte chars are not split, byte 120 falls inside the 60th `ж`
+        let text = "x".to_string() + &"ж".repeat(100);
+        let expected = "x".to_string() + &"ж".repeat(59);
+        assert_eq!(Question::title_from_question(&text), expected);
+        q.title = text;
+        assert_eq!(q.validated().unwrap().title, expected);
     }
 
     #[test]
@@ -704,18 +855,21 @@ mod test {
                     e: Some("<https://b.com/c>".to_string()),
                     c: Some(false),
                     sel: None,
+                    url: None,
                 },
                 Answer {
                     a: "<https://c.com>".to_string(),
                     e: Some("<https://c.com#c>".to_string()),
                     c: Some(true),
                     sel: None,
+                    url: None,
                 },
                 Answer {
                     a: "3".to_string(),
                     e: None,
                     c: Some(false),
                     sel: None,
+                    url: None,
                 },
             ],
             correct: 1,
@@ -728,13 +882,32 @@ mod test {
             refresher_links: None,
         };
 
+        let links = |links: &[(&str, LinkSource)]| {
+            Some(
+                links
+                    .iter()
+                    .map(|(url, source)| RefresherLink::new(url.to_string(), *source))
+                    .collect::<Vec<RefresherLink>>(),
+            )
+        };
         assert_eq!(
             q.clone().into_html(None).refresher_links,
-            Some(vec![
-                "https://a.com".to_string(),
-                "https://c.com".to_string(),
-                "https://b.com".to_string(),
-                "https://b.com/c".to_string(),
+            links(&[
+                ("https://a.com", LinkSource::Question),
+                ("https://c.com", LinkSource::CorrectAnswer),
+                ("https://b.com", LinkSource::IncorrectAnswer),
+                ("https://b.com/c", LinkSource::IncorrectAnswer),
+            ])
+        );
+
+        // unanswered questions do not tell correct answer links from incorrect ones
+        assert_eq!(
+            q.clone().format(QuestionFormat::HtmlShort).refresher_links,
+            links(&[
+                ("https://a.com", LinkSource::Question),
+                ("https://b.com", LinkSource::Answer),
+                ("https://b.com/c", LinkSource::Answer),
+                ("https://c.com", LinkSource::Answer),
             ])
         );
 
@@ -748,6 +921,68 @@ mod test {
         assert_eq!(q.into_html(None).refresher_links, None);
     }
 
+    #[test]
+    fn test_question_delivery_formats() {
+        let q = Question::from_str(
+            r#"{"qid":"89yZBXJBa9t2LB6xfj46Rm","topic":"aws","question":"What is **1+1**? [docs](https://a.com)","answers":[{"a":"`1`","e":"No"},{"a":"2","e":"Yes","c":true}],"title":"Math","updated":null}"#,
+        )
+        .unwrap();
+
+        let text = q.clone().format(QuestionFormat::PlainText);
+        assert_eq!(text.question, "What is 1+1? docs (https://a.com)");
+        assert_eq!(text.answers[0].text(), "1");
+        assert_eq!(
+            text.answers[1].url(),
+            Some("https://bitesized.info/question?topic=aws&qid=89yZBXJBa9t2LB6xfj46Rm&answers=1")
+        );
+        assert!(text.answers.iter().all(|v| v.e.is_none() && v.c.is_none()));
+        assert!(text.refresher_links.is_none());
+
+        let html = q.clone().format(QuestionFormat::EmailHtml);
+        assert_eq!(
+            html.answers[0].text(),
+            "<p><code style=\"font-family:monospace;background:#f1f5f9\">1</code></p>\n"
+        );
+        assert!(html.answers.iter().all(|v| v.e.is_none() && v.c.is_none()));
+
+        // a single click cannot answer a question with several correct answers
+        let mut q = q;
+        q.answers[0].c = Some(true);
+        q.correct = 2;
+        let text = q.format(QuestionFormat::PlainText);
+        assert_eq!(
+            text.answers[0].url(),
+            Some("https://bitesized.info/question?topic=aws&qid=89yZBXJBa9t2LB6xfj46Rm")
+        );
+        let expires = Utc::now() + chrono::Duration::days(1);
+        assert_eq!(text.clone().with_answer_tokens(b"secret", "abc", expires), text);
+    }
+
+    #[test]
+    fn test_question_with_answer_tokens() {
+        let q = Question::from_str(
+            r#"{"qid":"89yZBXJBa9t2LB6xfj46Rm","topic":"aws","question":"What is 1+1?","answers":[{"a":"1"},{"a":"2","c":true}],"title":"Math","updated":null}"#,
+        )
+        .unwrap();
+        let expires = Utc::now() + chrono::Duration::days(1);
+
+        let q = q
+            .format(QuestionFormat::PlainText)
+            .with_answer_tokens(b"secret", "abc", expires);
+        let url = q.answers[1].url().unwrap();
+        let (url, token) = url.split_once("&t=").unwrap();
+        assert_eq!(
+            url,
+            "https://bitesized.info/question?topic=aws&qid=89yZBXJBa9t2LB6xfj46Rm&answers=1"
+        );
+        assert_eq!(
+            verify_answer_token(b"secret", token, "aws", "89yZBXJBa9t2LB6xfj46Rm", &[1], Utc::now())
+                .map(|v| v.email_hash)
+                .as_deref(),
+            Some("abc")
+        );
+    }
+
     // test if the question complete
     #[test]
     fn test_question_is_complete() {
@@ -762,18 +997,21 @@ mod test {
                     e: Some("<https://b.com/c>".to_string()),
                     c: Some(false),
                     sel: None,
+                    url: None,
                 },
                 Answer {
                     a: "<https://c.com>".to_string(),
                     e: Some("<https://c.com#c>".to_string()),
                     c: Some(true),
                     sel: None,
+                    url: None,
                 },
                 Answer {
                     a: "3 is an invalid answer".to_string(),
                     e: Some("3 is an invalid explanation".to_string()),
                     c: Some(false),
                     sel: None,
+                    url: None,
                 },
             ],
             correct: 1,
@@ -834,12 +1072,14 @@ mod test {
                         e: Some("".to_string()),
                         c: Some(false),
                         sel: None,
+                        url: None,
                     },
                     Answer {
                         a: "<https://c.com>".to_string(),
                         e: Some("<https://c.com#c>".to_string()),
                         c: Some(true),
                         sel: None,
+                        url: None,
                     },
                 ],
                 ..q.clone()
@@ -856,12 +1096,14 @@ mod test {
                         e: Some("<https".to_string()),
                         c: Some(false),
                         sel: None,
+                        url: None,
                     },
                     Answer {
                         a: "<https://c.com>".to_string(),
                         e: Some("<https://c.com#c>".to_string()),
                         c: Some(true),
                         sel: None,
+                        url: None,
                     },
                 ],
                 ..q.clone()
@@ -0,0 +1,390 @@
+//! Allow-list sanitising of the HTML produced from Markdown.
+//!
+//! The same policy is applied by the lambdas and by the WASM preview in the browser,
+//! so authors see exactly what the learners will see.
+
+use crate::{embed::host_from_url, markdown::escape_html};
+
+/// Tags without content or a closing tag.
+const VOID_TAGS: &[&str] = &["br", "hr", "img", "wbr"];
+
+/// What the Markdown converter lets through into the HTML.
+/// Anything not listed is dropped and reported in `ValidatedMarkdown::ignored`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HtmlPolicy {
+    /// Tags allowed in inline and block HTML, in lower case.
+    pub tags: &'static [&'static str],
+    /// Attributes allowed on the permitted tags, in lower case.
+    pub attributes: &'static [&'static str],
+    /// Hosts images can be loaded from over HTTPS. Subdomains have to be listed separately.
+    pub image_hosts: &'static [&'static str],
+    /// URL schemes allowed in links. Relative links are always allowed.
+    pub link_schemes: &'static [&'static str],
+}
+
+impl HtmlPolicy {
+    /// The policy for questions, answers and explanations.
+    pub const DEFAULT: Self = Self {
+        tags: &[
+            "abbr", "br", "del", "details", "ins", "kbd", "mark", "small", "sub", "summary", "sup",
+        ],
+        attributes: &["title"],
+        image_hosts: &[
+            "bitesized.info",
+            "raw.githubusercontent.com",
+            "upload.wikimedia.org",
+            "i.imgur.com",
+        ],
+        link_schemes: &["https", "http", "mailto"],
+    };
+
+    /// Returns true if the link is relative or has an allowed scheme.
+    pub fn is_allowed_link(&self, url: &str) -> bool {
+        match url_scheme(url) {
+            Some(v) => self.link_schemes.contains(&v.as_str()),
+            None => true,
+        }
+    }
+
+    /// Returns true if the image is loaded over HTTPS from an approved host.
+    pub fn is_allowed_image(&self, url: &str) -> bool {
+        url_scheme(url).as_deref() == Some("https")
+            && host_from_url(url).is_some_and(|v| self.image_hosts.contains(&v.as_str()))
+    }
+
+    /// Returns an `<img>` tag for an allowed image with lazy loading and alt text.
+    pub fn render_image(&self, url: &str, alt: &str, title: &str) -> String {
+        let title = if title.is_empty() {
+            String::new()
+        } else {
+            format!(r#" title="{}""#, escape_html(title))
+        };
+        format!(
+            r#"<img src="{}" alt="{}"{title} loading="lazy">"#,
+            escape_html(url),
+            escape_html(alt)
+        )
+    }
+
+    /// Rebuilds the HTML fragment with allowed tags and attributes only.
+    /// Dropped tags, attributes and comments are added to `ignored`.
+    /// The text between the tags is kept, with `<` and `>` escaped.
+    /// Tags left open are closed at the end, so the fragment cannot break the markup around it.
+    pub fn sanitize_html(&self, html: &str, ignored: &mut Vec<String>) -> String {
+        let mut open_tags = Vec::new();
+        let mut sanitized = self.sanitize_html_part(html, &mut open_tags, ignored);
+        sanitized.push_str(&close_tags(&mut open_tags));
+        sanitized
+    }
+
+    /// Same as `sanitize_html` for a fragment split into parts, e.g. the HTML events of a Markdown document.
+    /// The tags opened and not yet closed are kept in `open_tags` between the parts.
+    /// Closing tags that do not match an open tag are dropped.
+    /// Call `close_tags` after the last part.
+    pub fn sanitize_html_part(
+        &self,
+        html: &str,
+        open_tags: &mut Vec<&'static str>,
+        ignored: &mut Vec<String>,
+    ) -> String {
+        let mut sanitized = String::with_capacity(html.len());
+        let mut rest = html;
+
+        while let Some(start) = rest.find('<') {
+            push_text(&mut sanitized, &rest[..start]);
+            rest = &rest[start..];
+
+            // `<` not followed by a tag name is text, e.g. `x < y`
+            if !rest[1..].starts_with(|c: char| c.is_ascii_alphabetic() || c == '/' || c == '!') {
+                sanitized.push_str("&lt;");
+                rest = &rest[1..];
+                continue;
+            }
+
+            // comments may contain `>`, so they end at `-->`
+            if rest.starts_with("<!--") {
+                let end = rest.find("-->").map_or(rest.len(), |v| v + 3);
+                ignored.push(rest[..end].to_string());
+                rest = &rest[end..];
+                continue;
+            }
+
+            let end = match tag_end(rest) {
+                Some(v) => v,
+                None => break,
+            };
+            let tag = &rest[..=end];
+            rest = &rest[end + 1..];
+
+            match self.sanitize_tag(tag, ignored) {
+                Some((name, false, v)) => {
+                    if !VOID_TAGS.contains(&name) {
+                        open_tags.push(name);
+                    }
+                    sanitized.push_str(&v);
+                }
+                // the tags opened after the matching one are closed first to keep the nesting valid
+                Some((name, true, _)) => match open_tags.iter().rposition(|v| *v == name) {
+                    Some(i) => {
+                        let mut inner = open_tags.split_off(i);
+                        sanitized.push_str(&close_tags(&mut inner));
+                    }
+                    None => ignored.push(tag.to_string()),
+                },
+                None => ignored.push(tag.to_string()),
+            }
+        }
+
+        push_text(&mut sanitized, rest);
+        sanitized
+    }
+
+    /// Returns the tag name, true for closing tags and the tag rebuilt from its allowed attributes.
+    /// Returns None if the tag is not allowed or is not a tag at all.
+    fn sanitize_tag(&self, tag: &str, ignored: &mut Vec<String>) -> Option<(&'static str, bool, String)> {
+        let inner = tag.strip_prefix('<')?.strip_suffix('>')?;
+        let (is_closing, inner) = match inner.strip_prefix('/') {
+            Some(v) => (true, v),
+            None => (false, inner),
+        };
+
+        let name_len = inner.find(|c: char| !c.is_ascii_alphanumeric()).unwrap_or(inner.len());
+        let name = inner[..name_len].to_ascii_lowercase();
+        let name = *self.tags.iter().find(|v| **v == name)?;
+
+        if is_closing {
+            return Some((name, true, format!("</{name}>")));
+        }
+
+        let mut sanitized = format!("<{name}");
+        for (attr, value) in parse_attributes(&inner[name_len..]) {
+            let allowed = self.attributes.contains(&attr.as_str())
+                && match attr.as_str() {
+                    "href" => self.is_allowed_link(&value),
+                    "src" => self.is_allowed_image(&value),
+                    _ => true,
+                };
+            if allowed {
+                sanitized.push_str(&format!(r#" {attr}="{}""#, escape_html(&value)));
+            } else {
+                ignored.push(format!(r#"{attr}="{value}""#));
+            }
+        }
+        sanitized.push('>');
+
+        Some((name, false, sanitized))
+    }
+}
+
+/// Returns the closing tags for `open_tags` in reverse order and clears the list.
+pub fn close_tags(open_tags: &mut Vec<&'static str>) -> String {
+    open_tags.drain(..).rev().map(|v| format!("</{v}>")).collect()
+}
+
+/// Returns the lower-case scheme of the URL, e.g. `https`, or None for relative URLs.
+/// Browsers ignore whitespace and control characters inside the scheme, e.g. `java\tscript:`, so they are removed.
+fn url_scheme(url: &str) -> Option<String> {
+    let url = url
+        .chars()
+        .filter(|c| !c.is_ascii_whitespace() && !c.is_ascii_control())
+        .collect::<String>();
+    let colon = url.find(':')?;
+    let scheme = &url[..colon];
+
+    // a `/`, `?` or `#` before the colon means it is a relative URL, e.g. `/a:b`
+    let is_scheme = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.');
+
+    if is_scheme {
+        Some(scheme.to_ascii_lowercase())
+    } else {
+        None
+    }
+}
+
+/// Returns the index of the `>` that closes the tag at the start of the text, skipping quoted values.
+fn tag_end(text: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if c == q => quote = None,
+            (None, '>') => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Parses `name="value" name='value' name=value name` into lower-case names and raw values.
+fn parse_attributes(text: &str) -> Vec<(String, String)> {
+    let mut attributes = Vec::new();
+    let mut chars = text.trim_end_matches('/').chars().peekable();
+
+    loop {
+        while chars.next_if(|c| c.is_whitespace() || *c == '/').is_some() {}
+
+        let mut name = String::new();
+        while let Some(c) = chars.next_if(|c| !c.is_whitespace() && *c != '=') {
+            name.push(c.to_ascii_lowercase());
+        }
+        if name.is_empty() {
+            break;
+        }
+
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+
+        let mut value = String::new();
+        if chars.next_if_eq(&'=').is_some() {
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+            match chars.next_if(|c| *c == '"' || *c == '\'') {
+                Some(quote) => {
+                    for c in chars.by_ref() {
+                        if c == quote {
+                            break;
+                        }
+                        value.push(c);
+                    }
+                }
+                None => {
+                    while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                        value.push(c);
+                    }
+                }
+            }
+        }
+
+        attributes.push((name, value));
+    }
+
+    attributes
+}
+
+/// Adds the text to the HTML with `<` and `>` escaped. Entities are kept as-is.
+fn push_text(html: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            _ => html.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_links() {
+        let policy = HtmlPolicy::DEFAULT;
+        assert!(policy.is_allowed_link("https://example.com"));
+        assert!(policy.is_allowed_link("mailto:max@onebro.me"));
+        assert!(policy.is_allowed_link("/question?topic=aws"));
+        assert!(policy.is_allowed_link("#top"));
+        assert!(policy.is_allowed_link("./a:b"));
+        assert!(!policy.is_allowed_link("javascript:alert(1)"));
+        assert!(!policy.is_allowed_link(" JavaScript:alert(1)"));
+        assert!(!policy.is_allowed_link("java\tscript:alert(1)"));
+        assert!(!policy.is_allowed_link("data:text/html;base64,PHNjcmlwdD4="));
+    }
+
+    #[test]
+    fn test_images() {
+        let policy = HtmlPolicy::DEFAULT;
+        assert!(policy.is_allowed_image("https://upload.wikimedia.org/a.png"));
+        assert!(!policy.is_allowed_image("http://upload.wikimedia.org/a.png"));
+        assert!(!policy.is_allowed_image("https://example.com/a.png"));
+        assert!(!policy.is_allowed_image("/a.png"));
+        assert_eq!(
+            policy.render_image("https://i.imgur.com/a.png?x=1&y=2", "A \"diagram\"", ""),
+            r#"<img src="https://i.imgur.com/a.png?x=1&amp;y=2" alt="A &quot;diagram&quot;" loading="lazy">"#
+        );
+    }
+
+    #[test]
+    fn test_sanitize_html() {
+        let policy = HtmlPolicy::DEFAULT;
+        let mut ignored = Vec::new();
+
+        assert_eq!(policy.sanitize_html("<SUB>2</sub >", &mut ignored), "<sub>2</sub>");
+        assert_eq!(policy.sanitize_html("<br/>", &mut ignored), "<br>");
+        assert_eq!(
+            policy.sanitize_html(
+                r#"<abbr title='Amazon "Web" Services' onclick="alert(1)">"#,
+                &mut ignored
+            ),
+            r#"<abbr title="Amazon &quot;Web&quot; Services"></abbr>"#
+        );
+        assert_eq!(ignored, vec![r#"onclick="alert(1)""#]);
+
+        ignored.clear();
+        assert_eq!(
+            policy.sanitize_html(
+                "<details>\n<summary>Hint</summary>\n<script>alert('>')</script><!-- a > b -->x < y\n</details>\n",
+                &mut ignored
+            ),
+            "<details>\n<summary>Hint</summary>\nalert('&gt;')x &lt; y\n</details>\n"
+        );
+        assert_eq!(ignored, vec!["<script>", "</script>", "<!-- a > b -->"]);
+
+        // an unclosed tag is escaped as text
+        ignored.clear();
+        assert_eq!(
+            policy.sanitize_html("<img src=x onerror=alert(1)", &mut ignored),
+            "&lt;img src=x onerror=alert(1)"
+        );
+        assert!(ignored.is_empty());
+    }
+
+    #[test]
+    fn test_sanitize_html_balancing() {
+        let policy = HtmlPolicy::DEFAULT;
+        let mut ignored = Vec::new();
+
+        // unclosed tags are closed at the end and stray closing tags are dropped
+        assert_eq!(
+            policy.sanitize_html("<details><small>Hint", &mut ignored),
+            "<details><small>Hint</small></details>"
+        );
+        assert_eq!(policy.sanitize_html("a</small></details>b", &mut ignored), "ab");
+        assert_eq!(ignored, vec!["</small>", "</details>"]);
+
+        // the inner tags are closed before the outer one
+        assert_eq!(
+            policy.sanitize_html("<details><small>a</details>b</small>", &mut ignored),
+            "<details><small>a</small></details>b"
+        );
+
+        // the open tags are carried over between the parts
+        let mut open_tags = Vec::new();
+        assert_eq!(
+            policy.sanitize_html_part("<sub><br>", &mut open_tags, &mut ignored),
+            "<sub><br>"
+        );
+        assert_eq!(open_tags, vec!["sub"]);
+        assert_eq!(
+            policy.sanitize_html_part("</sub>", &mut open_tags, &mut ignored),
+            "</sub>"
+        );
+        assert!(open_tags.is_empty());
+        policy.sanitize_html_part("<mark><kbd>", &mut open_tags, &mut ignored);
+        assert_eq!(close_tags(&mut open_tags), "</kbd></mark>");
+        assert!(open_tags.is_empty());
+    }
+
+    #[test]
+    fn test_parse_attributes() {
+        assert_eq!(
+            parse_attributes(r#" a="1 2" B='3' c=4 d /"#),
+            vec![
+                ("a".to_string(), "1 2".to_string()),
+                ("b".to_string(), "3".to_string()),
+                ("c".to_string(), "4".to_string()),
+                ("d".to_string(), String::new()),
+            ]
+        );
+    }
+}
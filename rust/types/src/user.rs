@@ -44,7 +44,7 @@ pub struct AskedQuestion {
 }
 
 /// Full user details.
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct User {
     /// User's email address
@@ -66,6 +66,15 @@ pub struct User {
     /// Set to true if the user is a moderator
     #[serde(skip_serializing_if = "Option::is_none", default)]
     pub is_mod: Option<bool>,
+    /// Set to true if the author does not want to be notified about feedback on their questions
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub feedback_opt_out: Option<bool>,
+    /// When the author was last sent a feedback digest
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub feedback_notified: Option<DateTime<Utc>>,
+    /// Set to true if the author does not want to be told about broken links in their questions
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub links_opt_out: Option<bool>,
 }
 
 /// Convert it into 2024-01-01T00:00:00Za format,
@@ -148,19 +157,19 @@ impl PartialOrd for AnswerStatus {
 
 impl Ord for AnswerStatus {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        let a = match self {
-            AnswerStatus::Asked(v) => v,
-            AnswerStatus::Skipped(v) => v,
-            AnswerStatus::Correct(v) => v,
-            AnswerStatus::Incorrect(v) => v,
-        };
-        let b = match other {
+        self.timestamp().cmp(other.timestamp())
+    }
+}
+
+impl AnswerStatus {
+    /// When the question was asked, answered or skipped.
+    pub fn timestamp(&self) -> &DateTime<Utc> {
+        match self {
             AnswerStatus::Asked(v) => v,
             AnswerStatus::Skipped(v) => v,
             AnswerStatus::Correct(v) => v,
             AnswerStatus::Incorrect(v) => v,
-        };
-        a.cmp(b)
+        }
     }
 }
 
@@ -347,7 +356,7 @@ mod tests {
             status: s3.clone(),
         };
 
-        let mut questions = vec![q0, q1, q2, q3];
+        let mut questions = [q0, q1, q2, q3];
 
         // sort ascending
         questions.sort_by(|a, b| a.status.cmp(&b.status));
@@ -1,4 +1,9 @@
 use bitie_types::markdown;
+use bitie_types::offline::{self, OfflineAnswer, OfflineBundle};
+use bitie_types::question::{Question, QuestionFormat};
+use chrono::Utc;
+use serde::Serialize;
+use std::str::FromStr;
 use wasm_bindgen::prelude::*;
 
 // Two logging options - browser console for WASM and tracing for native
@@ -20,7 +25,8 @@ pub async fn hello_world() {
     info!("Hello world!");
 }
 
-/// Converts a markdown string to HTML. Available in WASM only.
+/// Converts a markdown string to HTML with math rendered as MathML for the live preview.
+/// Available in WASM only.
 #[wasm_bindgen]
 pub async fn md_to_html(md: &str) -> markdown::ValidatedMarkdown {
     info!("Converting MD ({}) to HTML", md.len());
@@ -47,3 +53,172 @@ pub fn sort_links(
         incorrect_answer_links.clone(),
     )
 }
+
+/// TS types for the question objects passed to and returned by the question functions.
+/// They mirror the JSON the server returns.
+#[wasm_bindgen(typescript_custom_section)]
+const QUESTION_TS_TYPES: &str = r#"
+export interface WasmAnswer {
+  a: string;
+  e?: string;
+  c?: boolean;
+  sel?: boolean;
+}
+
+export interface WasmQuestion {
+  qid: string;
+  topic: string;
+  title: string;
+  question: string;
+  answers: WasmAnswer[];
+  correct: number;
+  [key: string]: unknown;
+}
+
+export interface WasmOfflineScore {
+  correct: number;
+  incorrect: number;
+  skipped: number;
+  remaining: number;
+}
+"#;
+
+/// Parses and validates a question JSON string the same way the server does when a question is saved.
+/// Missing qid and title are generated and `correct` is recalculated from the answers.
+#[wasm_bindgen(unchecked_return_type = "WasmQuestion")]
+pub fn parse_question(json: &str) -> Result<JsValue, JsError> {
+    info!("Parsing question ({})", json.len());
+    let question = Question::from_str(json).map_err(|e| JsError::new(&e.to_string()))?;
+    to_js(&question)
+}
+
+/// Converts a question in Markdown into HTML with explanations, same as `QuestionFormat::HtmlFull`.
+/// `answers` are the learner's choices to mark as selected, if any.
+#[wasm_bindgen(unchecked_return_type = "WasmQuestion")]
+pub fn question_to_html_full(
+    #[wasm_bindgen(unchecked_param_type = "WasmQuestion")] question: JsValue,
+    answers: Option<Vec<usize>>,
+) -> Result<JsValue, JsError> {
+    info!("Formatting question as full HTML");
+    to_js(&from_js(question)?.format(QuestionFormat::HtmlFull(answers)))
+}
+
+/// Converts a question in Markdown into HTML without explanations for the learner to answer,
+/// same as `QuestionFormat::HtmlShort`.
+#[wasm_bindgen(unchecked_return_type = "WasmQuestion")]
+pub fn question_to_html_short(
+    #[wasm_bindgen(unchecked_param_type = "WasmQuestion")] question: JsValue,
+) -> Result<JsValue, JsError> {
+    info!("Formatting question as short HTML");
+    to_js(&from_js(question)?.format(QuestionFormat::HtmlShort))
+}
+
+/// Returns true if `answers` are exactly the correct answers of the question.
+#[wasm_bindgen]
+pub fn is_correct(
+    #[wasm_bindgen(unchecked_param_type = "WasmQuestion")] question: JsValue,
+    answers: Vec<usize>,
+) -> Result<bool, JsError> {
+    Ok(from_js(question)?.is_correct(&answers))
+}
+
+/// Returns true if the question has all the parts required for publishing.
+#[wasm_bindgen]
+pub fn is_complete(#[wasm_bindgen(unchecked_param_type = "WasmQuestion")] question: JsValue) -> Result<bool, JsError> {
+    Ok(from_js(question)?.is_complete())
+}
+
+/// Generates a title from the question text, same as the server does for questions without one.
+#[wasm_bindgen]
+pub fn title_from_question(question: &str) -> String {
+    Question::title_from_question(question)
+}
+
+/// Converts a JS question object into a validated Question.
+fn from_js(question: JsValue) -> Result<Question, JsError> {
+    serde_wasm_bindgen::from_value::<Question>(question)
+        .map_err(|e| JsError::new(&format!("Cannot deserialize question: {e}")))?
+        .validated()
+        .map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// Converts a Question into a plain JS object with the same shape as the server JSON.
+fn to_js(question: &Question) -> Result<JsValue, JsError> {
+    question
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .map_err(|e| JsError::new(&format!("Cannot serialize question: {e}")))
+}
+
+/// Practising questions from a signed offline bundle without connectivity.
+/// The answers are scored locally and queued for syncing with `question-handler` when back online.
+#[wasm_bindgen]
+pub struct OfflinePractice(offline::OfflinePractice);
+
+#[wasm_bindgen]
+impl OfflinePractice {
+    /// Verifies the bundle downloaded from `question-list-handler` with the Ed25519 public key in PEM format
+    /// and restores the queue of answers from `export_answers`, if any.
+    #[wasm_bindgen(constructor)]
+    pub fn new(bundle: &str, public_key: &str, queue: Option<String>) -> Result<OfflinePractice, JsError> {
+        let bundle = OfflineBundle::verify(bundle, public_key.as_bytes(), Utc::now())
+            .map_err(|e| JsError::new(&e.to_string()))?;
+
+        let queue = match queue.as_deref().map(serde_json::from_str::<Vec<OfflineAnswer>>) {
+            Some(Ok(v)) => v,
+            Some(Err(e)) => return Err(JsError::new(&format!("Cannot restore offline answers: {e}"))),
+            None => Vec::new(),
+        };
+
+        info!(
+            "Offline bundle loaded: {} questions, {} queued answers",
+            bundle.questions.len(),
+            queue.len()
+        );
+        Ok(Self(offline::OfflinePractice::new(bundle, queue)))
+    }
+
+    /// The topics the bundle was downloaded for.
+    pub fn topics(&self) -> Vec<String> {
+        self.0.topics().to_vec()
+    }
+
+    /// Returns the next unanswered question on the topic or any topic in HTML without explanations,
+    /// or `undefined` if all questions were answered.
+    #[wasm_bindgen(unchecked_return_type = "WasmQuestion | undefined")]
+    pub fn next_question(&self, topic: Option<String>) -> Result<JsValue, JsError> {
+        match self.0.next_question(topic.as_deref()) {
+            Some(v) => to_js(&v),
+            None => Ok(JsValue::UNDEFINED),
+        }
+    }
+
+    /// Scores and queues the learner's answers and returns the question in HTML with explanations.
+    /// An empty list of answers means the question was skipped.
+    #[wasm_bindgen(unchecked_return_type = "WasmQuestion")]
+    pub fn answer(&mut self, topic: &str, qid: &str, answers: Vec<usize>) -> Result<JsValue, JsError> {
+        let question = self
+            .0
+            .answer(topic, qid, answers, Utc::now())
+            .map_err(|e| JsError::new(&e.to_string()))?;
+        to_js(&question)
+    }
+
+    /// The learner's progress in this session.
+    #[wasm_bindgen(unchecked_return_type = "WasmOfflineScore")]
+    pub fn score(&self) -> Result<JsValue, JsError> {
+        self.0
+            .score()
+            .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .map_err(|e| JsError::new(&format!("Cannot serialize score: {e}")))
+    }
+
+    /// Returns the queued answers as JSON for the local storage or as the body of the sync request.
+    pub fn export_answers(&self) -> Result<String, JsError> {
+        serde_json::to_string(self.0.queue()).map_err(|e| JsError::new(&format!("Cannot export offline answers: {e}")))
+    }
+
+    /// Removes all queued answers after they were synced.
+    pub fn clear_answers(&mut self) {
+        self.0.clear_queue();
+    }
+}
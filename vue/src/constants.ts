@@ -1,12 +1,16 @@
+/// The origin of all lambda endpoints, e.g. http://localhost:3000 for the local dev server in rust/dev_server.
+/// Set via VITE_API_BASE_URL in .env files, defaults to prod.
+export const API_BASE_URL = import.meta.env.VITE_API_BASE_URL || "https://bitesized.info";
+
 /// The endpoint for question-related requests.
-export const QUESTION_HANDLER_URL = "https://bitesized.info/q?";
-export const QUESTION_LIST_HANDLER_URL = "https://bitesized.info/ql?";
-export const QUESTION_STAGE_HANDLER_URL = "https://bitesized.info/qs?";
-export const QUESTION_FEEDBACK_HANDLER_URL = "https://bitesized.info/qf?";
+export const QUESTION_HANDLER_URL = `${API_BASE_URL}/q?`;
+export const QUESTION_LIST_HANDLER_URL = `${API_BASE_URL}/ql?`;
+export const QUESTION_STAGE_HANDLER_URL = `${API_BASE_URL}/qs?`;
+export const QUESTION_FEEDBACK_HANDLER_URL = `${API_BASE_URL}/qf?`;
 /// The endpoint for user-related requests.
-export const USER_HANDLER_URL = "https://bitesized.info/u?";
+export const USER_HANDLER_URL = `${API_BASE_URL}/u?`;
 /// The endpoint for payment-related requests.
-export const PAYMENTS_HANDLER_URL = "https://bitesized.info/checkout?";
+export const PAYMENTS_HANDLER_URL = `${API_BASE_URL}/checkout?`;
 
 /// E.g. .../q?topic=foo&qid=bar
 export const URL_PARAM_TOPIC = "topic"
@@ -18,6 +22,8 @@ export const URL_PARAM_QID = "qid"
 export const URL_PARAM_STAGE = "stage"
 /// E.g. .../q?topic=foo&qid=bar&answers=0.1
 export const URL_PARAM_ANSWERS = "answers"
+/// A signed token from an answer link in an email, e.g. .../q?topic=foo&qid=bar&answers=1&t=abc.123.def
+export const URL_PARAM_ANSWER_TOKEN = "t"
 /// A character used to separate values within the same param value,
 /// e.g. .../q?topics=foo.bar
 export const URL_PARAM_LIST_SEPARATOR = "."
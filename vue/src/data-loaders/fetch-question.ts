@@ -1,9 +1,12 @@
-import { QUESTION_HANDLER_URL, TOKEN_HEADER_NAME, URL_PARAM_TOPIC, URL_PARAM_QID } from "@/constants";
+import { QUESTION_HANDLER_URL, TOKEN_HEADER_NAME, URL_PARAM_TOPIC, URL_PARAM_QID, URL_PARAM_ANSWERS, URL_PARAM_ANSWER_TOKEN } from "@/constants";
 import { type Question } from "@/interfaces";
 
 
-/** Makes the best effort to fetch a single question. Returns `undefined` on error. */
-export const fetchQuestion = async (topic: string, qid?: string, token?: string): Promise<Question | undefined | null> => {
+/** Makes the best effort to fetch a single question. Returns `undefined` on error.
+ * Answers submit the learner's choice, e.g. from a deep link in an email, and return the question with explanations.
+ * The answer token from an email link identifies the learner if there is no JWT.
+ */
+export const fetchQuestion = async (topic: string, qid?: string, token?: string, answers?: string, answerToken?: string): Promise<Question | undefined | null> => {
 
   console.log(`Fetching question for: ${topic} / ${qid}`);
 
@@ -20,7 +23,8 @@ export const fetchQuestion = async (topic: string, qid?: string, token?: string)
     // fetching by topic returns a random question
     // fetching with qid returns a specific question
     // fetching any topic has "any" for the topic
-    const fetchParams = `${URL_PARAM_TOPIC}=${topic}`.concat(qid ? `&${URL_PARAM_QID}=${qid}` : "");
+    const fetchParams = `${URL_PARAM_TOPIC}=${topic}`.concat(qid ? `&${URL_PARAM_QID}=${qid}` : "").concat(qid && answers ? `&${URL_PARAM_ANSWERS}=${answers}` : "")
+      .concat(qid && answers && answerToken ? `&${URL_PARAM_ANSWER_TOKEN}=${encodeURIComponent(answerToken)}` : "");
     console.log("fetch params", fetchParams);
 
     const response = await fetch(`${QUESTION_HANDLER_URL}${fetchParams}`,
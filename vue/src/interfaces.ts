@@ -8,6 +8,8 @@ export interface Answer {
   c: boolean,
   /// Set to true if this is the user selection
   sel: boolean,
+  /// A deep link that submits this answer, only present in email and plain text formats
+  url?: string,
 }
 
 /// A mirror of the Rust's type
@@ -49,8 +51,23 @@ export interface Question {
   title: string,
   /** This value is read-only. The server ignores the values submitted from UI. */
   stage: PublishStage,
-  /** An ordered list of URLs for refresher material to display on request */
-  refresherLinks?: string[],
+  /** An ordered list of links to refresher material to display on request */
+  refresherLinks?: RefresherLink[],
+}
+
+/// A mirror of the Rust's type
+/// Where in the question a refresher link was found
+export type LinkSource = "question" | "answer" | "correctAnswer" | "incorrectAnswer";
+
+/// A mirror of the Rust's type
+export interface RefresherLink {
+  /// The URL as it appears in the Markdown
+  url: string,
+  /// The title of the linked page, if known
+  title?: string,
+  /// The host of the linked site without www., e.g. docs.aws.amazon.com
+  site?: string,
+  source: LinkSource,
 }
 
 /// A mirror of the Rust's type
@@ -71,7 +88,10 @@ export interface User {
   },
   /** True if the user is a moderator, otherwise undefined */
   isMod?: boolean,
-
+  /** True if the author turned off feedback notifications, otherwise undefined */
+  feedbackOptOut?: boolean,
+  /** True if the author turned off broken link reports, otherwise undefined */
+  linksOptOut?: boolean,
 }
 
 /// Questions contributor details to be displayed alongside the question
@@ -103,15 +103,17 @@ export const useMainStore = defineStore('main', () => {
    * Returns a random question for the topic if qid is not provided.
    * Updates the questionStatus value during the loading process.
    * Clear the question value to guarantee the latest version is loaded.
+   * Answers are submitted with the request and the question is returned with explanations.
+   * The answer token from an email link is sent with the answers.
    */
-  const loadQuestion = async (paramTopic: string, paramQid?: string) => {
+  const loadQuestion = async (paramTopic: string, paramQid?: string, paramAnswers?: string, paramAnswerToken?: string) => {
 
     // hide the refresher links and feedback on reload
     refresherLinksToggleFlag.value = false;
     feedbackStatus.value = undefined;
 
     // check if the currently loaded question can be reused
-    if (question.value && question.value.topic === paramTopic && question.value.qid === paramQid) {
+    if (!paramAnswers && question.value && question.value.topic === paramTopic && question.value.qid === paramQid) {
       questionStatus.value = LoadingStatus.Loaded;
       console.log("Reusing the current question.");
       return;
@@ -126,7 +128,7 @@ export const useMainStore = defineStore('main', () => {
       paramTopic = randomTopicId();
     }
 
-    const fetchedQuestion = await fetchQuestion(paramTopic, paramQid, token.value);
+    const fetchedQuestion = await fetchQuestion(paramTopic, paramQid, token.value, paramAnswers, paramAnswerToken);
 
     if (fetchedQuestion) {
       // success